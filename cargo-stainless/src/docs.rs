@@ -0,0 +1,331 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use serde_json::{self, Value};
+use stainless_runtime::test_name;
+
+use list::{parse_specs, Kind, Spec};
+use package::{crate_roots, package_root};
+
+/// Usage printed for `cargo stainless docs --help`.
+const USAGE: &str = "\
+Usage: cargo stainless docs [OPTIONS] [FILES...]
+
+Writes the describe! blocks of the package as a document: blocks become headings and tests
+bullets, and ignored tests are listed as pending behavior at the end. FILES are parsed like
+crate roots, by default the roots of all targets of the package are.
+
+Options:
+        --format markdown|html
+                        Write Markdown or a static HTML page
+        --report PATH   Mark tests as passed or failed as in the JSON events of a test run
+        --title TITLE   The title of the document, \"Specification\" by default
+    -o, --output PATH   Write the document to PATH instead of stdout
+    -h, --help          Display this message
+";
+
+/// The format written by `cargo stainless docs`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocsFormat {
+    /// Markdown, with headings for `describe!` blocks and bullets for tests.
+    Markdown,
+    /// A static HTML page with the same structure.
+    Html
+}
+
+/// Options of `cargo stainless docs`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocsOptions {
+    /// The crate roots to parse, all targets of the package if empty.
+    pub files: Vec<PathBuf>,
+    /// The format of the document.
+    pub format: DocsFormat,
+    /// The JSON events of a test run, from `--json PATH` of the runtime.
+    pub report: Option<PathBuf>,
+    /// The title of the document.
+    pub title: String,
+    /// Where to write the document, stdout if `None`.
+    pub output: Option<PathBuf>,
+    /// Print the usage and exit.
+    pub help: bool
+}
+
+impl DocsOptions {
+    /// Parse the options following `cargo stainless docs`.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<DocsOptions, String> {
+        let mut options = DocsOptions {
+            files: vec![],
+            format: DocsFormat::Markdown,
+            report: None,
+            title: "Specification".to_string(),
+            output: None,
+            help: false
+        };
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = |flag: &str| args.next().ok_or_else(|| format!("missing argument for `{}`", flag));
+            match &*arg {
+                "--format" => {
+                    options.format = match &*value("--format")? {
+                        "markdown" | "md" => DocsFormat::Markdown,
+                        "html" => DocsFormat::Html,
+                        other => return Err(format!("invalid `--format` value `{}`", other))
+                    };
+                },
+                "--report" => options.report = Some(PathBuf::from(value("--report")?)),
+                "--title" => options.title = value("--title")?,
+                "-o" | "--output" => options.output = Some(PathBuf::from(value("--output")?)),
+                "-h" | "--help" => options.help = true,
+                flag if flag.starts_with('-') => return Err(format!("unrecognized option `{}`", flag)),
+                file => options.files.push(PathBuf::from(file))
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// The outcome of a test in a test run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// The test passed.
+    Passed,
+    /// The test failed.
+    Failed,
+    /// The test was ignored.
+    Skipped,
+    /// The test was not started, after `--fail-fast`.
+    NotRun
+}
+
+impl Status {
+    fn name(self) -> &'static str {
+        match self {
+            Status::Passed => "passed",
+            Status::Failed => "failed",
+            Status::Skipped => "skipped",
+            Status::NotRun => "not run"
+        }
+    }
+
+    fn badge(self) -> &'static str {
+        match self {
+            Status::Passed => "✅",
+            Status::Failed => "❌",
+            Status::Skipped => "⏸️",
+            Status::NotRun => "⏭️"
+        }
+    }
+}
+
+/// Write the documentation of the package in the current directory.
+pub fn docs(options: &DocsOptions) -> Result<(), String> {
+    if options.help {
+        print!("{}", USAGE);
+        return Ok(());
+    }
+
+    let root = package_root()?;
+    let files = if options.files.is_empty() { crate_roots(&root) } else { options.files.clone() };
+    let specs = parse_specs(&root, &files)?;
+
+    let statuses = match options.report {
+        Some(ref report) => {
+            let events = fs::read_to_string(report)
+                .map_err(|e| format!("failed to read `{}`: {}", report.display(), e))?;
+            report_statuses(&events)
+        },
+        None => HashMap::new()
+    };
+
+    let document = match options.format {
+        DocsFormat::Markdown => markdown(&options.title, &specs, &statuses),
+        DocsFormat::Html => html(&options.title, &specs, &statuses)
+    };
+
+    match options.output {
+        Some(ref output) => fs::write(output, document)
+            .map_err(|e| format!("failed to write `{}`: {}", output.display(), e)),
+        None => io::stdout().write_all(document.as_bytes())
+            .map_err(|e| format!("failed to print the document: {}", e))
+    }
+}
+
+/// The latest outcome of every test in the JSON events of the runtime, by the name of the test,
+/// like `arithmetic::nested::carries_the_one`. Reports can contain several runs, since the
+/// runtime appends to them.
+pub fn report_statuses(report: &str) -> HashMap<String, Status> {
+    report.lines().filter_map(|line| {
+        let event: Value = serde_json::from_str(line).ok()?;
+        let status = match event["type"].as_str()? {
+            "test_passed" => Status::Passed,
+            "test_failed" => Status::Failed,
+            "test_skipped" => Status::Skipped,
+            "test_not_run" => Status::NotRun,
+            _ => return None
+        };
+        let path: Vec<&str> = event["path"].as_array()?.iter().filter_map(Value::as_str).collect();
+        Some((test_name(&path, event["description"].as_str()?), status))
+    }).collect()
+}
+
+/// Write the specs as Markdown. Top-level blocks are `##` headings, nested blocks deeper
+/// headings, and tests bullets, with a badge if the test is in `statuses`. Ignored tests are
+/// listed under "Pending" at the end.
+pub fn markdown(title: &str, specs: &[Spec], statuses: &HashMap<String, Status>) -> String {
+    let mut out = format!("# {}\n", title);
+    for spec in specs {
+        markdown_describe(spec, &mut vec![], statuses, &mut out);
+    }
+
+    let pending = pending(specs);
+    if !pending.is_empty() {
+        out.push_str("\n## Pending\n\n");
+        for (path, description) in pending {
+            out.push_str(&format!("- {} › {}\n", path.join(" › "), description));
+        }
+    }
+    out
+}
+
+fn markdown_describe<'s>(spec: &'s Spec, path: &mut Vec<&'s str>, statuses: &HashMap<String, Status>,
+                         out: &mut String) {
+    if !has_behavior(spec) {
+        return;
+    }
+
+    path.push(&spec.name);
+    out.push_str(&format!("\n{} {}\n", "#".repeat((path.len() + 1).min(6)), heading(&spec.name)));
+
+    let tests: Vec<&Spec> = spec.children.iter().filter(|child| is_behavior(child)).collect();
+    if !tests.is_empty() {
+        out.push('\n');
+    }
+    for test in tests {
+        match statuses.get(&test_name(path, &test.name)) {
+            Some(status) => out.push_str(&format!("- {} {}\n", status.badge(), test.name)),
+            None => out.push_str(&format!("- {}\n", test.name))
+        }
+    }
+
+    for child in spec.children.iter().filter(|child| child.kind == Kind::Describe) {
+        markdown_describe(child, path, statuses, out);
+    }
+    path.pop();
+}
+
+/// Write the specs as a static HTML page with the structure of `markdown`.
+pub fn html(title: &str, specs: &[Spec], statuses: &HashMap<String, Status>) -> String {
+    let mut out = format!("\
+<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{title}</title>
+<style>
+body {{ font-family: sans-serif; max-width: 50em; margin: 2em auto; line-height: 1.5; }}
+.badge {{ font-size: 0.8em; padding: 0 0.4em; border-radius: 0.3em; color: white; }}
+.passed {{ background: #2a2; }}
+.failed {{ background: #c22; }}
+.skipped, .not-run {{ background: #888; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+", title = escape(title));
+
+    for spec in specs {
+        html_describe(spec, &mut vec![], statuses, &mut out);
+    }
+
+    let pending = pending(specs);
+    if !pending.is_empty() {
+        out.push_str("<h2>Pending</h2>\n<ul>\n");
+        for (path, description) in pending {
+            out.push_str(&format!("<li>{} › {}</li>\n", escape(&path.join(" › ")), escape(&description)));
+        }
+        out.push_str("</ul>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn html_describe<'s>(spec: &'s Spec, path: &mut Vec<&'s str>, statuses: &HashMap<String, Status>,
+                     out: &mut String) {
+    if !has_behavior(spec) {
+        return;
+    }
+
+    path.push(&spec.name);
+    let level = (path.len() + 1).min(6);
+    out.push_str(&format!("<section>\n<h{level}>{}</h{level}>\n", escape(&heading(&spec.name)), level = level));
+
+    let tests: Vec<&Spec> = spec.children.iter().filter(|child| is_behavior(child)).collect();
+    if !tests.is_empty() {
+        out.push_str("<ul>\n");
+        for test in tests {
+            out.push_str(&format!("<li>{}", escape(&test.name)));
+            if let Some(status) = statuses.get(&test_name(path, &test.name)) {
+                out.push_str(&format!(" <span class=\"badge {}\">{}</span>", status.name().replace(' ', "-"),
+                                      status.name()));
+            }
+            out.push_str("</li>\n");
+        }
+        out.push_str("</ul>\n");
+    }
+
+    for child in spec.children.iter().filter(|child| child.kind == Kind::Describe) {
+        html_describe(child, path, statuses, out);
+    }
+    out.push_str("</section>\n");
+    path.pop();
+}
+
+// Tests which are not ignored, benchmarks are not behavior.
+fn is_behavior(spec: &Spec) -> bool {
+    spec.kind == Kind::Test && !spec.modifiers.iter().any(|modifier| modifier == "ignore")
+}
+
+fn has_behavior(spec: &Spec) -> bool {
+    spec.children.iter().any(|child| is_behavior(child) || has_behavior(child))
+}
+
+// The ignored tests with the names of their `describe!` blocks.
+fn pending(specs: &[Spec]) -> Vec<(Vec<String>, String)> {
+    fn collect(spec: &Spec, path: &mut Vec<String>, pending: &mut Vec<(Vec<String>, String)>) {
+        path.push(heading(&spec.name));
+        for child in &spec.children {
+            if child.kind == Kind::Describe {
+                collect(child, path, pending);
+            } else if child.kind == Kind::Test && !is_behavior(child) {
+                pending.push((path.clone(), child.name.clone()));
+            }
+        }
+        path.pop();
+    }
+
+    let mut pending = vec![];
+    for spec in specs {
+        collect(spec, &mut vec![], &mut pending);
+    }
+    pending
+}
+
+// `describe!` blocks are named with identifiers, which read better with spaces.
+fn heading(name: &str) -> String {
+    name.replace('_', " ")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
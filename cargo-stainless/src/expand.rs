@@ -0,0 +1,123 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use package::package_root;
+
+/// Usage printed for `cargo stainless expand --help`.
+const USAGE: &str = "\
+Usage: cargo stainless expand PATH [OPTIONS]
+
+Prints the module the plugin generates for the describe! block at PATH, like `stack > empty` for
+the block `empty` nested in `stack`. Every run of statements in a test is preceded by a comment
+with the hook, test or step it was written in. The package is compiled with `cargo test --no-run`
+in `target/stainless-expand`, all other options are passed to it, e.g. `--test spec`.
+
+Options:
+    -h, --help          Display this message
+";
+
+/// Options of `cargo stainless expand`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExpandOptions {
+    /// The names of the top-level block and the nested blocks leading to the expanded block.
+    pub path: Vec<String>,
+    /// Passed to `cargo test --no-run`, e.g. `--test spec`.
+    pub cargo_args: Vec<String>,
+    /// Print the usage and exit.
+    pub help: bool
+}
+
+impl ExpandOptions {
+    /// Parse the options following `cargo stainless expand`.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<ExpandOptions, String> {
+        let mut options = ExpandOptions::default();
+        let mut path = None;
+
+        for arg in args {
+            match &*arg {
+                "-h" | "--help" => options.help = true,
+                _ if path.is_none() && !arg.starts_with('-') => path = Some(describe_path(&arg)?),
+                _ => options.cargo_args.push(arg)
+            }
+        }
+
+        match path {
+            Some(path) => options.path = path,
+            None if options.help => {},
+            None => return Err("missing the path of the `describe!` block to expand".to_string())
+        }
+        Ok(options)
+    }
+}
+
+/// Split the path of a `describe!` block into the names of the blocks, separated by `>` like in
+/// the test paths of the runtime, or by `::` like module paths.
+pub fn describe_path(path: &str) -> Result<Vec<String>, String> {
+    let names: Vec<String> = path.split('>')
+        .flat_map(|part| part.split("::"))
+        .map(|name| name.trim().to_string())
+        .collect();
+    if names.iter().any(|name| name.is_empty()) {
+        return Err(format!("invalid path of a `describe!` block `{}`", path));
+    }
+    Ok(names)
+}
+
+/// Print the expansion of a `describe!` block of the package in the current directory.
+///
+/// The plugin writes the module it generates for the block to a file while the package is
+/// compiled, if `STAINLESS_EXPAND` is the path of the block and `STAINLESS_EXPAND_OUTPUT` the
+/// file. A block can be expanded more than once, when a module is part of several crates.
+pub fn expand(options: &ExpandOptions) -> Result<(), String> {
+    if options.help {
+        print!("{}", USAGE);
+        return Ok(());
+    }
+
+    let root = package_root()?;
+    let target = root.join("target").join("stainless-expand");
+    let output = target.join("expanded.rs");
+    if output.exists() {
+        fs::remove_file(&output).map_err(|e| format!("failed to remove `{}`: {}", output.display(), e))?;
+    }
+
+    // The plugin only runs when the package is compiled, so it is compiled again. A target
+    // directory of its own keeps the builds of `cargo test` as they are.
+    let package = cargo(&root, &target, &["pkgid"]).output()
+        .map_err(|e| format!("failed to run `cargo pkgid`: {}", e))?;
+    if !package.status.success() {
+        return Err("`cargo pkgid` failed".to_string());
+    }
+    let package = String::from_utf8_lossy(&package.stdout).trim().to_string();
+    cargo(&root, &target, &["clean", "--quiet", "-p", &package]).status()
+        .map_err(|e| format!("failed to run `cargo clean`: {}", e))?;
+    let built = cargo(&root, &target, &["test", "--no-run"])
+        .args(&options.cargo_args)
+        .env("STAINLESS_EXPAND", options.path.join(" > "))
+        .env("STAINLESS_EXPAND_OUTPUT", &output)
+        .status()
+        .map_err(|e| format!("failed to run `cargo test`: {}", e))?;
+
+    // The module is written before it is compiled, so it is there for borrow errors too.
+    let modules = fs::read_to_string(&output).unwrap_or_default();
+    match (modules.is_empty(), built.success()) {
+        (false, _) => print!("{}", modules),
+        (true, true) => return Err(format!("there is no `describe!` block `{}`", options.path.join(" > "))),
+        (true, false) => return Err("`cargo test --no-run` failed".to_string())
+    }
+    Ok(())
+}
+
+// `cargo` with `args`, run in `root` with the target directory `target`.
+fn cargo(root: &Path, target: &Path, args: &[&str]) -> Command {
+    let mut cargo = Command::new("cargo");
+    cargo.args(args).current_dir(root).env("CARGO_TARGET_DIR", target);
+    cargo
+}
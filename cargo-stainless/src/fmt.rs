@@ -0,0 +1,432 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+// Formatting `describe!` blocks with rustfmt.
+//
+// rustfmt does not touch the bodies of macros, so every `describe!` block is translated to an
+// equivalent module: hooks, tests and benchmarks become functions with the same block, nested
+// blocks become nested modules, and items and comments stay as they are. The module is
+// formatted with rustfmt and translated back, taking the formatted blocks, items and comments
+// from rustfmt and writing normalized headers for hooks, tests and benchmarks.
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use grammar::{BLOCKS, STEPS};
+
+use lexer::{matching, tokenize, Kind, Token};
+use package::{package_root, source_files};
+
+/// Usage printed for `cargo stainless fmt --help`.
+const USAGE: &str = "\
+Usage: cargo stainless fmt [OPTIONS] [FILES...]
+
+Formats the describe! blocks in FILES, by default in all source files of the package, with
+rustfmt. Code outside of describe! blocks is left to `cargo fmt`.
+
+Options:
+        --check         Only report blocks which are not formatted, exit with 1 if any
+        --edition EDITION
+                        The edition to parse the code with
+    -h, --help          Display this message
+";
+
+/// Options of `cargo stainless fmt`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FmtOptions {
+    /// The files to format, all source files of the package if empty.
+    pub files: Vec<PathBuf>,
+    /// Only check whether the files are formatted.
+    pub check: bool,
+    /// Passed to rustfmt.
+    pub edition: Option<String>,
+    /// Print the usage and exit.
+    pub help: bool
+}
+
+impl FmtOptions {
+    /// Parse the options following `cargo stainless fmt`.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<FmtOptions, String> {
+        let mut options = FmtOptions::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match &*arg {
+                "--check" => options.check = true,
+                "--edition" => {
+                    options.edition = Some(args.next().ok_or("missing argument for `--edition`")?);
+                },
+                "-h" | "--help" => options.help = true,
+                flag if flag.starts_with('-') => return Err(format!("unrecognized option `{}`", flag)),
+                file => options.files.push(PathBuf::from(file))
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Format the `describe!` blocks of the package in the current directory, or check that they
+/// are formatted. Returns whether all blocks were formatted already.
+pub fn fmt(options: &FmtOptions) -> Result<bool, String> {
+    if options.help {
+        print!("{}", USAGE);
+        return Ok(true);
+    }
+
+    let files = if options.files.is_empty() { source_files(&package_root()?) } else { options.files.clone() };
+    let mut formatted = true;
+    for file in files {
+        let source = fs::read_to_string(&file).map_err(|e| format!("failed to read `{}`: {}", file.display(), e))?;
+        if !source.contains("describe!") {
+            continue;
+        }
+
+        let result = format_source(&source, options.edition.as_deref())
+            .map_err(|e| format!("failed to format `{}`: {}", file.display(), e))?;
+        if result == source {
+            continue;
+        }
+
+        formatted = false;
+        if options.check {
+            println!("{} is not formatted", file.display());
+        } else {
+            fs::write(&file, result).map_err(|e| format!("failed to write `{}`: {}", file.display(), e))?;
+        }
+    }
+    Ok(formatted)
+}
+
+/// Format every `describe!` block in Rust source with rustfmt.
+pub fn format_source(source: &str, edition: Option<&str>) -> Result<String, String> {
+    let tokens = tokenize(source)?;
+    let mut result = String::with_capacity(source.len());
+    let mut copied = 0;
+
+    let mut i = 0;
+    while i + 3 < tokens.len() {
+        let is_describe = tokens[i].is(source, "describe") && tokens[i + 1].is(source, "!") &&
+            tokens[i + 2].kind == Kind::Ident && tokens[i + 3].is(source, "{");
+        if !is_describe {
+            i += 1;
+            continue;
+        }
+
+        let close = matching(&tokens, i + 3)?;
+        let line_start = source[..tokens[i].start].rfind('\n').map_or(0, |at| at + 1);
+        let indent = source[line_start..tokens[i].start].chars().take_while(|c| c.is_whitespace()).count();
+        let name = tokens[i + 2].text(source);
+
+        let formatted = format_describe(source, &tokens, name, i + 3, close, indent / 4, edition)
+            .map_err(|e| format!("`describe! {}`: {}", name, e))?;
+        result.push_str(&source[copied..tokens[i].start]);
+        result.push_str(&formatted);
+        copied = tokens[close].end;
+        i = close + 1;
+    }
+
+    result.push_str(&source[copied..]);
+    Ok(result)
+}
+
+// Formats the `describe!` block with the body between the braces at `open` and `close`, written
+// `depth` levels deep.
+fn format_describe(source: &str, tokens: &[Token], name: &str, open: usize, close: usize, depth: usize,
+                   edition: Option<&str>) -> Result<String, String> {
+    let entries = split(source, tokens, open + 1, close, true)?;
+
+    // Wrap the module so that rustfmt sees the same indentation.
+    let mut module = String::new();
+    for _ in 0..depth {
+        module.push_str("mod __stainless {\n");
+    }
+    module.push_str(&format!("mod {} {{", name));
+    to_module(source, tokens, &entries, open, close, &mut module)?;
+    module.push('}');
+    for _ in 0..depth {
+        module.push_str("\n}");
+    }
+    module.push('\n');
+
+    let formatted = rustfmt(&module, edition)?;
+    let formatted_tokens = tokenize(&formatted)?;
+
+    // Unwrap the module again.
+    let (mut open, mut close) = (0, formatted_tokens.len());
+    for _ in 0..depth + 1 {
+        let entries = split(&formatted, &formatted_tokens, open, close, false)?;
+        match entries.first().and_then(|entry| entry.block) {
+            Some((block_open, block_close)) if entries.len() == 1 => {
+                open = block_open + 1;
+                close = block_close;
+            },
+            _ => return Err("unexpected output of rustfmt".to_string())
+        }
+    }
+
+    let mut result = format!("describe! {} {{", name);
+    from_module(source, tokens, &entries, &formatted, &formatted_tokens, open, close, &mut result)?;
+    result.push('}');
+    Ok(result)
+}
+
+/// What an entry of a `describe!` block or module body is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryKind {
+    /// An ordinary item.
+    Item,
+    /// A hook, test or benchmark.
+    Block,
+    /// A nested `describe!` block.
+    Describe,
+    /// A `scenario`, whose body is a list of steps.
+    Scenario
+}
+
+/// A part of a `describe!` block or module body, as token indices. Comments and attributes
+/// belong to the following entry.
+#[derive(Clone, Copy, Debug)]
+pub struct Entry {
+    /// What the entry is.
+    pub kind: EntryKind,
+    /// The first token after comments and attributes.
+    pub core: usize,
+    /// The first brace-delimited group, the block of a test or the body of a module.
+    pub block: Option<(usize, usize)>,
+    /// The token after the entry.
+    pub end: usize
+}
+
+/// Split the tokens between `start` and `end` into entries. The kinds of blocks of `describe!`
+/// are only recognized if `describe` is set, otherwise everything is an item.
+pub fn split(source: &str, tokens: &[Token], start: usize, end: usize, describe: bool) -> Result<Vec<Entry>, String> {
+    let mut entries = vec![];
+    let mut i = start;
+
+    loop {
+        // Comments and inner and outer attributes.
+        loop {
+            if i < end && tokens[i].kind == Kind::Comment {
+                i += 1;
+            } else if i + 1 < end && tokens[i].is(source, "#") && tokens[i + 1].is(source, "[") {
+                i = matching(tokens, i + 1)? + 1;
+            } else if i + 2 < end && tokens[i].is(source, "#") && tokens[i + 1].is(source, "!") &&
+                      tokens[i + 2].is(source, "[") {
+                i = matching(tokens, i + 2)? + 1;
+            } else {
+                break;
+            }
+        }
+        if i >= end {
+            return Ok(entries);
+        }
+
+        let core = i;
+        // The blocks of `describe!` and the steps of a `scenario`.
+        let block_name = tokens[i].kind == Kind::Ident &&
+            (BLOCKS.contains(&tokens[i].text(source)) || STEPS.contains(&tokens[i].text(source)));
+        let nested = tokens[i].is(source, "describe") && i + 1 < end && tokens[i + 1].is(source, "!");
+        let kind = if !describe {
+            EntryKind::Item
+        } else if nested {
+            EntryKind::Describe
+        } else if tokens[i].is(source, "scenario") {
+            EntryKind::Scenario
+        } else if block_name {
+            EntryKind::Block
+        } else {
+            EntryKind::Item
+        };
+
+        let block;
+        match kind {
+            EntryKind::Block | EntryKind::Describe | EntryKind::Scenario => {
+                // Everything up to the block is the header, groups like `(b)` are skipped.
+                while i < end && !tokens[i].is(source, "{") {
+                    i = if tokens[i].kind == Kind::Open { matching(tokens, i)? + 1 } else { i + 1 };
+                }
+                if i >= end {
+                    return Err(format!("expected a block after `{}`", tokens[core].text(source)));
+                }
+                let close = matching(tokens, i)?;
+                block = Some((i, close));
+                i = close + 1;
+            },
+            EntryKind::Item => {
+                let (item_end, item_block) = self::item_end(source, tokens, i, end)?;
+                i = item_end;
+                block = item_block;
+            }
+        }
+
+        entries.push(Entry { kind, core, block, end: i });
+    }
+}
+
+/// The token after the item starting at `start`, and its first brace-delimited group. An item
+/// ends with `;` or with a block, like `fn` and `impl`, but `{...};` as in `use a::{b, c};` also
+/// ends with the `;`.
+pub fn item_end(source: &str, tokens: &[Token], start: usize, end: usize)
+                -> Result<(usize, Option<(usize, usize)>), String> {
+    let mut i = start;
+    while i < end {
+        if tokens[i].is(source, ";") {
+            return Ok((i + 1, None));
+        }
+        if tokens[i].kind == Kind::Open {
+            let close = matching(tokens, i)?;
+            if tokens[i].is(source, "{") {
+                let after = if close + 1 < end && tokens[close + 1].is(source, ";") { close + 2 } else { close + 1 };
+                return Ok((after, Some((i, close))));
+            }
+            i = close + 1;
+        } else {
+            i += 1;
+        }
+    }
+    Ok((i, None))
+}
+
+// Writes the module body equivalent to the entries between the braces at `open` and `close`.
+fn to_module(source: &str, tokens: &[Token], entries: &[Entry], open: usize, close: usize, module: &mut String)
+             -> Result<(), String> {
+    let mut copied = tokens[open].end;
+    for entry in entries {
+        let core = tokens[entry.core].start;
+        module.push_str(&source[copied..core]);
+        match (entry.kind, entry.block) {
+            (EntryKind::Block, Some((open, close))) => {
+                module.push_str("fn __stainless() ");
+                module.push_str(&source[tokens[open].start..tokens[close].end]);
+            },
+            (EntryKind::Describe, Some((open, close))) => {
+                let nested = split(source, tokens, open + 1, close, true)?;
+                module.push_str(&format!("mod {} {{", tokens[entry.core + 2].text(source)));
+                to_module(source, tokens, &nested, open, close, module)?;
+                module.push('}');
+            },
+            // The steps become functions of a module.
+            (EntryKind::Scenario, Some((open, close))) => {
+                let steps = split(source, tokens, open + 1, close, true)?;
+                module.push_str("mod __stainless_scenario {");
+                to_module(source, tokens, &steps, open, close, module)?;
+                module.push('}');
+            },
+            _ => module.push_str(&source[core..tokens[entry.end - 1].end])
+        }
+        copied = tokens[entry.end - 1].end;
+    }
+    module.push_str(&source[copied..tokens[close].start]);
+    Ok(())
+}
+
+// Writes the `describe!` block for the formatted module body between `open` and `close`,
+// taking the headers from the original `entries`.
+#[allow(clippy::too_many_arguments)]
+fn from_module(source: &str, tokens: &[Token], entries: &[Entry], formatted: &str, formatted_tokens: &[Token],
+               open: usize, close: usize, result: &mut String) -> Result<(), String> {
+    let formatted_entries = split(formatted, formatted_tokens, open, close, false)?;
+    if formatted_entries.len() != entries.len() {
+        return Err("rustfmt changed the number of items".to_string());
+    }
+
+    let mut copied = formatted_tokens[open - 1].end;
+    for (entry, formatted_entry) in entries.iter().zip(&formatted_entries) {
+        let core = formatted_tokens[formatted_entry.core].start;
+        result.push_str(&formatted[copied..core]);
+
+        match (entry.kind, entry.block, formatted_entry.block) {
+            (EntryKind::Block, Some((header_end, _)), Some((block_open, block_close))) => {
+                result.push_str(&header(source, &tokens[entry.core..header_end]));
+                result.push(' ');
+                result.push_str(&formatted[formatted_tokens[block_open].start..formatted_tokens[block_close].end]);
+            },
+            (EntryKind::Describe, Some((nested_open, nested_close)), Some((block_open, block_close))) => {
+                let nested = split(source, tokens, nested_open + 1, nested_close, true)?;
+                result.push_str(&format!("describe! {} {{", tokens[entry.core + 2].text(source)));
+                from_module(source, tokens, &nested, formatted, formatted_tokens, block_open + 1, block_close,
+                            result)?;
+                result.push('}');
+            },
+            (EntryKind::Scenario, Some((header_end, steps_close)), Some((block_open, block_close))) => {
+                let steps = split(source, tokens, header_end + 1, steps_close, true)?;
+                result.push_str(&header(source, &tokens[entry.core..header_end]));
+                result.push_str(" {");
+                from_module(source, tokens, &steps, formatted, formatted_tokens, block_open + 1, block_close,
+                            result)?;
+                result.push('}');
+            },
+            (EntryKind::Item, _, _) => {
+                result.push_str(&formatted[core..formatted_tokens[formatted_entry.end - 1].end]);
+            },
+            _ => return Err("rustfmt changed a block into an item".to_string())
+        }
+        copied = formatted_tokens[formatted_entry.end - 1].end;
+    }
+
+    result.push_str(&formatted[copied..formatted_tokens[close].start]);
+    Ok(())
+}
+
+// The normalized header of a hook, test or benchmark: `it "description" tags(a, b)`,
+// `failing("message") "description"` or `bench "description" (b)`.
+fn header(source: &str, tokens: &[Token]) -> String {
+    let mut header = String::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        if token.kind == Kind::Open {
+            // Groups stick to identifiers, like `tags(a, b)`, but not to descriptions.
+            if i > 0 && tokens[i - 1].kind != Kind::Ident {
+                header.push(' ');
+            }
+            let close = matching(tokens, i).unwrap_or(tokens.len() - 1);
+            header.push_str(token.text(source));
+            for (j, inner) in tokens[i + 1..close].iter().enumerate() {
+                if j > 0 && !inner.is(source, ",") {
+                    header.push(' ');
+                }
+                header.push_str(inner.text(source));
+            }
+            header.push_str(tokens[close].text(source));
+            i = close + 1;
+        } else {
+            if i > 0 {
+                header.push(' ');
+            }
+            header.push_str(token.text(source));
+            i += 1;
+        }
+    }
+    header
+}
+
+fn rustfmt(source: &str, edition: Option<&str>) -> Result<String, String> {
+    let mut command = Command::new("rustfmt");
+    command.args(["--emit", "stdout"]);
+    if let Some(edition) = edition {
+        command.args(["--edition", edition]);
+    }
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run rustfmt: {}", e))?;
+    child.stdin.take().unwrap().write_all(source.as_bytes())
+        .map_err(|e| format!("failed to run rustfmt: {}", e))?;
+    let output = child.wait_with_output().map_err(|e| format!("failed to run rustfmt: {}", e))?;
+
+    if output.status.success() {
+        String::from_utf8(output.stdout).map_err(|_| "rustfmt printed invalid UTF-8".to_string())
+    } else {
+        Err(format!("rustfmt failed:\n{}", String::from_utf8_lossy(&output.stderr)))
+    }
+}
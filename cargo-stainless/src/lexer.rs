@@ -0,0 +1,217 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+// A minimal lexer for Rust source, enough to find `describe!` blocks and their parts without
+// the compiler. Whitespace is skipped, comments are kept so they can be carried along.
+
+/// What a token is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// An identifier or keyword, including raw identifiers.
+    Ident,
+    /// A string, character, byte or number literal.
+    Literal,
+    /// A lifetime or label, `'a`.
+    Lifetime,
+    /// A single punctuation character.
+    Punct,
+    /// `(`, `[` or `{`.
+    Open,
+    /// `)`, `]` or `}`.
+    Close,
+    /// A line or block comment, including doc comments.
+    Comment
+}
+
+/// A token, as a byte range of the source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Token {
+    /// What the token is.
+    pub kind: Kind,
+    /// The offset of the first byte.
+    pub start: usize,
+    /// The offset after the last byte.
+    pub end: usize
+}
+
+impl Token {
+    /// The text of the token.
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start..self.end]
+    }
+
+    /// Whether the token is the given identifier or punctuation.
+    pub fn is(&self, source: &str, text: &str) -> bool {
+        self.kind != Kind::Literal && self.kind != Kind::Comment && self.text(source) == text
+    }
+}
+
+/// Split Rust source into tokens.
+pub fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let bytes = source.as_bytes();
+    let mut tokens = vec![];
+    let mut at = 0;
+
+    while at < bytes.len() {
+        let c = source[at..].chars().next().unwrap();
+        let start = at;
+        let next = bytes.get(at + 1).cloned();
+
+        let kind = if c.is_whitespace() {
+            at += c.len_utf8();
+            continue;
+        } else if c == '/' && next == Some(b'/') {
+            at = source[at..].find('\n').map_or(bytes.len(), |end| at + end);
+            Kind::Comment
+        } else if c == '/' && next == Some(b'*') {
+            at = block_comment(source, at)?;
+            Kind::Comment
+        } else if let Some(end) = string_literal(source, at)? {
+            at = end;
+            Kind::Literal
+        } else if c == '\'' {
+            let (end, kind) = quote(source, at)?;
+            at = end;
+            kind
+        } else if c.is_ascii_digit() {
+            at += 1;
+            while at < bytes.len() {
+                let b = bytes[at];
+                let fraction = b == b'.' && bytes.get(at + 1).is_some_and(|b| b.is_ascii_digit());
+                if b.is_ascii_alphanumeric() || b == b'_' || fraction {
+                    at += 1;
+                } else {
+                    break;
+                }
+            }
+            Kind::Literal
+        } else if c == '_' || c.is_alphabetic() {
+            if source[at..].starts_with("r#") {
+                at += 2;
+            }
+            at += identifier_length(&source[at..]);
+            Kind::Ident
+        } else if c == '(' || c == '[' || c == '{' {
+            at += 1;
+            Kind::Open
+        } else if c == ')' || c == ']' || c == '}' {
+            at += 1;
+            Kind::Close
+        } else {
+            at += c.len_utf8();
+            Kind::Punct
+        };
+
+        tokens.push(Token { kind, start, end: at });
+    }
+
+    Ok(tokens)
+}
+
+/// The index of the token closing the group opened at `open`.
+pub fn matching(tokens: &[Token], open: usize) -> Result<usize, String> {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token.kind {
+            Kind::Open => depth += 1,
+            Kind::Close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(i);
+                }
+            },
+            _ => {}
+        }
+    }
+    Err("unclosed delimiter".to_string())
+}
+
+fn identifier_length(text: &str) -> usize {
+    text.char_indices()
+        .find(|&(_, c)| !(c == '_' || c.is_alphanumeric()))
+        .map_or(text.len(), |(at, _)| at)
+}
+
+// Returns the end of the nested block comment starting at `at`.
+fn block_comment(source: &str, mut at: usize) -> Result<usize, String> {
+    let mut depth = 0;
+    while at < source.len() {
+        if source[at..].starts_with("/*") {
+            depth += 1;
+            at += 2;
+        } else if source[at..].starts_with("*/") {
+            depth -= 1;
+            at += 2;
+            if depth == 0 {
+                return Ok(at);
+            }
+        } else {
+            at += source[at..].chars().next().unwrap().len_utf8();
+        }
+    }
+    Err("unterminated block comment".to_string())
+}
+
+// Returns the end of the string literal starting at `at`, if there is one: `"..."`, `b"..."`,
+// `c"..."`, or raw strings like `r#"..."#` and `br"..."`.
+fn string_literal(source: &str, at: usize) -> Result<Option<usize>, String> {
+    let rest = &source[at..];
+    let prefix = ["br", "cr", "r", "b", "c"].iter()
+        .find(|prefix| rest.starts_with(*prefix) && rest[prefix.len()..].starts_with(['"', '#']))
+        .map_or("", |prefix| *prefix);
+    let raw = prefix.ends_with('r');
+    let rest = &rest[prefix.len()..];
+
+    if raw {
+        let hashes = rest.len() - rest.trim_start_matches('#').len();
+        if !rest[hashes..].starts_with('"') {
+            return Ok(None);
+        }
+        let terminator = format!("\"{}", "#".repeat(hashes));
+        let body = at + prefix.len() + hashes + 1;
+        return match source[body..].find(&terminator) {
+            Some(end) => Ok(Some(body + end + terminator.len())),
+            None => Err("unterminated raw string".to_string())
+        };
+    }
+
+    if !rest.starts_with('"') {
+        return Ok(None);
+    }
+    let mut chars = rest.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => { chars.next(); },
+            '"' => return Ok(Some(at + prefix.len() + i + 1)),
+            _ => {}
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+// Returns the end of the character literal or lifetime starting at `at`. The `b` of byte
+// literals is lexed as an identifier before it, which makes no difference here.
+fn quote(source: &str, at: usize) -> Result<(usize, Kind), String> {
+    let mut chars = source[at + 1..].char_indices();
+    match chars.next() {
+        Some((_, '\\')) => {
+            // An escaped character, `'\n'`, `'\''` or `'\u{1F600}'`.
+            match source.get(at + 3..).and_then(|rest| rest.find('\'')) {
+                Some(end) => Ok((at + 3 + end + 1, Kind::Literal)),
+                None => Err("unterminated character literal".to_string())
+            }
+        },
+        Some((_, c)) => {
+            let after = at + 1 + c.len_utf8();
+            if source[after..].starts_with('\'') {
+                Ok((after + 1, Kind::Literal))
+            } else {
+                Ok((after + identifier_length(&source[after..]), Kind::Lifetime))
+            }
+        },
+        None => Err("unexpected end of input after `'`".to_string())
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+#![deny(missing_docs)]
+
+//! The `cargo stainless` subcommand, for working with the `describe!` specs of a crate.
+//!
+//! Install it with `cargo install cargo-stainless`, then run it inside of a crate using
+//! stainless:
+//!
+//! ``` text
+//! cargo stainless watch [OPTIONS] [-- ARGS]
+//! cargo stainless list [--format text|json] [FILES...]
+//! cargo stainless fmt [--check] [FILES...]
+//! cargo stainless migrate [--hoist] [--dry-run] [FILES...]
+//! cargo stainless expand PATH [OPTIONS]
+//! cargo stainless docs [--format markdown|html] [--report PATH] [FILES...]
+//! ```
+//!
+//! ## Watch mode
+//!
+//! `cargo stainless watch` runs `cargo test`, then waits for files in `src`, `tests`,
+//! `benches` and `examples` or `Cargo.toml` to change and runs the tests again. Tests which
+//! failed in the previous run are run first, followed by the rest. When all changed files
+//! contain `describe!` blocks of test targets in harness mode, only these targets run and only
+//! the tests of these blocks, `--all` always runs everything.
+//!
+//! Failed tests are read from the JSON events of the `stainless_runtime` runner in harness mode,
+//! and from the list of failures the test harness of Rust prints otherwise. Other options are
+//! passed on to `cargo test`, and arguments after `--` to the test binaries.
+//!
+//! ## Listing specs
+//!
+//! `cargo stainless list` prints the `describe!` blocks, tests and benchmarks of a crate with
+//! their locations, tags and modifiers, without compiling it. The blocks are read from the source
+//! with the grammar of the `describe!` macro, following `mod` declarations to the files of the
+//! modules.
+//!
+//! `--format json` prints the tree as JSON for editors and other tools, see `print_json`.
+//!
+//! ## Formatting
+//!
+//! rustfmt leaves the bodies of macros alone, so `cargo stainless fmt` formats `describe!`
+//! blocks: hooks, tests and items with rustfmt, and the headers of tests and benchmarks like
+//! `failing("message") "description" tags(a, b)`. `--check` only reports unformatted files.
+//!
+//! ## Migrating
+//!
+//! `cargo stainless migrate` converts private modules of `#[test]` functions to `describe!`
+//! blocks, see `migrate_source`. The crates still need to enable the plugin themselves.
+//!
+//! ## Expanding
+//!
+//! `cargo stainless expand "stack > empty"` prints the module the plugin generates for a
+//! `describe!` block, with a comment before the statements spliced in from every hook. The
+//! package is compiled with `cargo test --no-run`, and the plugin writes the module it generates
+//! for the block to a file.
+//!
+//! ## Living documentation
+//!
+//! `cargo stainless docs` writes the spec tree as Markdown or a static HTML page, with the
+//! outcomes of the last test run from a JSON report as badges, see `markdown` and `html`.
+
+extern crate notify;
+#[macro_use]
+extern crate serde_json;
+extern crate stainless_grammar as grammar;
+extern crate stainless_runtime;
+
+pub use docs::{docs, html, markdown, report_statuses, DocsFormat, DocsOptions, Status};
+pub use expand::{describe_path, expand, ExpandOptions};
+pub use fmt::{fmt, format_source, FmtOptions};
+pub use list::{list, parse_specs, print_json, print_text, Kind, ListFormat, ListOptions, Spec};
+pub use migrate::{migrate, migrate_source, MigrateOptions};
+pub use package::{crate_roots, package_root, source_files, SOURCE_DIRS};
+pub use watch::{affected_tests, failed_tests, libtest_failures, spec_names, watch, Selection, WatchOptions};
+
+mod docs;
+mod expand;
+mod fmt;
+mod lexer;
+mod list;
+mod migrate;
+mod package;
+mod source;
+mod watch;
+
+/// Usage printed for `--help`.
+pub const USAGE: &str = "\
+Usage: cargo stainless <COMMAND> [OPTIONS]
+
+Commands:
+    watch       Run the tests again whenever files change
+    list        List the describe! blocks, tests and benchmarks of the package
+    fmt         Format the describe! blocks of the package
+    migrate     Convert #[test] modules of the package to describe! blocks
+    expand      Print the code generated for a describe! block
+    docs        Write the specs of the package as Markdown or HTML
+
+Run `cargo stainless <COMMAND> --help` for the options of a command.
+";
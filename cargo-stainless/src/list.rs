@@ -0,0 +1,194 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::{self, Value};
+
+use package::{crate_roots, package_root};
+
+/// Usage printed for `cargo stainless list --help`.
+const USAGE: &str = "\
+Usage: cargo stainless list [OPTIONS] [FILES...]
+
+Lists the describe! blocks, tests and benchmarks of the package without compiling it. FILES
+are parsed like crate roots, by default the roots of all targets of the package are.
+
+Options:
+        --format text|json
+                        Print an indented tree or a JSON array of the top-level blocks
+    -h, --help          Display this message
+";
+
+/// The format printed by `cargo stainless list`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListFormat {
+    /// An indented tree with the locations of the blocks.
+    Text,
+    /// A JSON array of the top-level blocks, see `print_json`.
+    Json
+}
+
+/// Options of `cargo stainless list`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListOptions {
+    /// The crate roots to parse, all targets of the package if empty.
+    pub files: Vec<PathBuf>,
+    /// The format of the output.
+    pub format: ListFormat,
+    /// Print the usage and exit.
+    pub help: bool
+}
+
+impl ListOptions {
+    /// Parse the options following `cargo stainless list`.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<ListOptions, String> {
+        let mut options = ListOptions { files: vec![], format: ListFormat::Text, help: false };
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match &*arg {
+                "--format" => {
+                    options.format = match args.next().as_deref() {
+                        Some("text") => ListFormat::Text,
+                        Some("json") => ListFormat::Json,
+                        Some(other) => return Err(format!("invalid `--format` value `{}`", other)),
+                        None => return Err("missing argument for `--format`".to_string())
+                    };
+                },
+                "-h" | "--help" => options.help = true,
+                flag if flag.starts_with('-') => return Err(format!("unrecognized option `{}`", flag)),
+                file => options.files.push(PathBuf::from(file))
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// What a node of the spec tree is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// A `describe!` block.
+    Describe,
+    /// An `it`, `failing` or `ignore` test.
+    Test,
+    /// A `bench` block.
+    Bench
+}
+
+/// A `describe!` block, test or benchmark and where it is written.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Spec {
+    /// What this is.
+    pub kind: Kind,
+    /// The name of a `describe!` block, or the description of a test or benchmark.
+    pub name: String,
+    /// The file, relative to the package.
+    pub file: String,
+    /// The line of the name or description, starting at 1.
+    pub line: usize,
+    /// The tags given on this block, not including the tags inherited from `describe!` blocks.
+    pub tags: Vec<String>,
+    /// Modifiers as written, like `failing("overflow")`, `ignore`, `serial(db)`, `isolated` or
+    /// `no_super_glob`.
+    pub modifiers: Vec<String>,
+    /// The tests, benchmarks and nested blocks of a `describe!` block.
+    pub children: Vec<Spec>
+}
+
+/// List the specs of the package in the current directory.
+pub fn list(options: &ListOptions) -> Result<(), String> {
+    if options.help {
+        print!("{}", USAGE);
+        return Ok(());
+    }
+
+    let root = package_root()?;
+    let files = if options.files.is_empty() { crate_roots(&root) } else { options.files.clone() };
+    let specs = parse_specs(&root, &files)?;
+
+    let stdout = io::stdout();
+    match options.format {
+        ListFormat::Text => print_text(&specs, stdout.lock()),
+        ListFormat::Json => print_json(&specs, stdout.lock())
+    }.map_err(|e| format!("failed to print the specs: {}", e))
+}
+
+/// The specs of the crates rooted at `files` and their modules, with locations relative to
+/// `root`.
+pub fn parse_specs(root: &Path, files: &[PathBuf]) -> Result<Vec<Spec>, String> {
+    ::source::parse_specs(root, files)
+}
+
+/// Print the specs as an indented tree, with tags in brackets and modifiers in parentheses:
+///
+/// ```text
+/// arithmetic                                tests/spec.rs:8
+///   adds                                    tests/spec.rs:9
+///   overflows [slow] (failing("overflow"))  tests/spec.rs:13
+///   multiplies (bench)                      tests/spec.rs:17
+/// ```
+pub fn print_text<W: Write>(specs: &[Spec], mut out: W) -> io::Result<()> {
+    let mut lines = vec![];
+    for spec in specs {
+        text_lines(spec, 0, &mut lines);
+    }
+
+    let width = lines.iter().map(|(line, _)| line.chars().count()).max().unwrap_or(0);
+    for (line, location) in lines {
+        writeln!(out, "{:width$}  {}", line, location, width = width)?;
+    }
+    out.flush()
+}
+
+fn text_lines(spec: &Spec, depth: usize, lines: &mut Vec<(String, String)>) {
+    let mut line = format!("{}{}", "  ".repeat(depth), spec.name);
+    if !spec.tags.is_empty() {
+        line.push_str(&format!(" [{}]", spec.tags.join(", ")));
+    }
+
+    let mut modifiers = spec.modifiers.clone();
+    if spec.kind == Kind::Bench {
+        modifiers.insert(0, "bench".to_string());
+    }
+    if !modifiers.is_empty() {
+        line.push_str(&format!(" ({})", modifiers.join(", ")));
+    }
+
+    lines.push((line, format!("{}:{}", spec.file, spec.line)));
+    for child in &spec.children {
+        text_lines(child, depth + 1, lines);
+    }
+}
+
+/// Print the specs as a JSON array of objects with the fields of `Spec`. `kind` is one of
+/// `describe`, `test` or `bench`.
+pub fn print_json<W: Write>(specs: &[Spec], mut out: W) -> io::Result<()> {
+    let specs: Vec<Value> = specs.iter().map(json).collect();
+    serde_json::to_writer(&mut out, &specs)?;
+    writeln!(out)?;
+    out.flush()
+}
+
+fn json(spec: &Spec) -> Value {
+    let kind = match spec.kind {
+        Kind::Describe => "describe",
+        Kind::Test => "test",
+        Kind::Bench => "bench"
+    };
+
+    json!({
+        "kind": kind,
+        "name": spec.name,
+        "file": spec.file,
+        "line": spec.line,
+        "tags": spec.tags,
+        "modifiers": spec.modifiers,
+        "children": spec.children.iter().map(json).collect::<Vec<Value>>()
+    })
+}
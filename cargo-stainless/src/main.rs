@@ -0,0 +1,45 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+extern crate cargo_stainless;
+
+use std::{env, process};
+
+use cargo_stainless::{docs, expand, fmt, list, migrate, watch, DocsOptions, ExpandOptions, FmtOptions, ListOptions,
+                      MigrateOptions, WatchOptions, USAGE};
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    // Cargo passes the name of the subcommand first when run as `cargo stainless`.
+    if args.first().map(|arg| &**arg) == Some("stainless") {
+        args.remove(0);
+    }
+
+    let result = match args.first().map(|arg| &**arg) {
+        Some("watch") => WatchOptions::parse(args.drain(1..)).and_then(|options| watch(&options)),
+        Some("list") => ListOptions::parse(args.drain(1..)).and_then(|options| list(&options)),
+        Some("fmt") => FmtOptions::parse(args.drain(1..)).and_then(|options| {
+            // Like `cargo fmt --check`, unformatted files fail the check.
+            if !fmt(&options)? && options.check {
+                process::exit(1);
+            }
+            Ok(())
+        }),
+        Some("migrate") => MigrateOptions::parse(args.drain(1..)).and_then(|options| migrate(&options)),
+        Some("expand") => ExpandOptions::parse(args.drain(1..)).and_then(|options| expand(&options)),
+        Some("docs") => DocsOptions::parse(args.drain(1..)).and_then(|options| docs(&options)),
+        Some("-h") | Some("--help") | None => {
+            print!("{}", USAGE);
+            Ok(())
+        },
+        Some(command) => Err(format!("unknown command `{}`", command))
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,489 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+// Converting modules of `#[test]` functions to `describe!` blocks.
+//
+// Modules are found with the lexer of `cargo stainless fmt` and rewritten with a list of edits,
+// so that everything which is not converted, including comments and formatting, stays as it is
+// written.
+
+use std::fs;
+use std::path::PathBuf;
+
+use fmt::{split, Entry};
+use lexer::{matching, tokenize, Kind, Token};
+use package::{package_root, source_files};
+
+/// Usage printed for `cargo stainless migrate --help`.
+const USAGE: &str = "\
+Usage: cargo stainless migrate [OPTIONS] [FILES...]
+
+Converts private modules with #[test] functions in FILES, by default in all source files of the
+package, to describe! blocks. #[should_panic] becomes failing, #[ignore] becomes ignore, and
+function names become descriptions which generate the same names again. Tests which take
+arguments or return a value are kept as they are.
+
+Options:
+        --hoist         Move the statements all tests of a block start with to before_each
+        --dry-run       Only print the files which would be changed
+    -h, --help          Display this message
+";
+
+/// Options of `cargo stainless migrate`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MigrateOptions {
+    /// The files to migrate, all source files of the package if empty.
+    pub files: Vec<PathBuf>,
+    /// Move common leading statements of tests to `before_each`.
+    pub hoist: bool,
+    /// Only print which files would be changed.
+    pub dry_run: bool,
+    /// Print the usage and exit.
+    pub help: bool
+}
+
+impl MigrateOptions {
+    /// Parse the options following `cargo stainless migrate`.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<MigrateOptions, String> {
+        let mut options = MigrateOptions::default();
+
+        for arg in args {
+            match &*arg {
+                "--hoist" => options.hoist = true,
+                "--dry-run" => options.dry_run = true,
+                "-h" | "--help" => options.help = true,
+                flag if flag.starts_with('-') => return Err(format!("unrecognized option `{}`", flag)),
+                file => options.files.push(PathBuf::from(file))
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Migrate the test modules of the package in the current directory to `describe!` blocks.
+pub fn migrate(options: &MigrateOptions) -> Result<(), String> {
+    if options.help {
+        print!("{}", USAGE);
+        return Ok(());
+    }
+
+    let files = if options.files.is_empty() { source_files(&package_root()?) } else { options.files.clone() };
+    for file in files {
+        let source = fs::read_to_string(&file).map_err(|e| format!("failed to read `{}`: {}", file.display(), e))?;
+        if !source.contains("#[test]") {
+            continue;
+        }
+
+        let result = migrate_source(&source, options.hoist)
+            .map_err(|e| format!("failed to migrate `{}`: {}", file.display(), e))?;
+        if result == source {
+            continue;
+        }
+
+        if options.dry_run {
+            println!("would migrate {}", file.display());
+        } else {
+            fs::write(&file, result).map_err(|e| format!("failed to write `{}`: {}", file.display(), e))?;
+            println!("migrated {}", file.display());
+        }
+    }
+    Ok(())
+}
+
+/// Convert every private module with `#[test]` functions in Rust source to a `describe!` block.
+/// With `hoist`, the statements which all tests of a block start with are moved to a
+/// `before_each` block, unless that would leave a test without any statement.
+pub fn migrate_source(source: &str, hoist: bool) -> Result<String, String> {
+    let tokens = tokenize(source)?;
+    let mut migration = Migration { source, tokens: &tokens, edits: vec![], tests: vec![], roots: vec![] };
+
+    let blocks = migration.modules(0, tokens.len())?;
+    migration.roots.extend(blocks);
+    if hoist {
+        for block in migration.roots.split_off(0) {
+            migration.hoist(&block)?;
+        }
+        migration.trim_tests();
+    }
+
+    let mut edits = migration.edits;
+    edits.sort_by_key(|&(start, end, _)| (start, end));
+
+    let mut result = String::with_capacity(source.len());
+    let mut copied = 0;
+    for (start, end, text) in edits {
+        result.push_str(&source[copied..start]);
+        result.push_str(&text);
+        copied = end;
+    }
+    result.push_str(&source[copied..]);
+    Ok(result)
+}
+
+struct Migration<'a> {
+    source: &'a str,
+    tokens: &'a [Token],
+    // Replacements of byte ranges of the source, applied at the end.
+    edits: Vec<(usize, usize, String)>,
+    // The converted tests.
+    tests: Vec<Body>,
+    // Converted modules which are not nested in another converted module.
+    roots: Vec<Block>
+}
+
+// The block of a converted test.
+struct Body {
+    close: usize,
+    // The first token which was not moved to `before_each`.
+    from: usize,
+    // The first token which was moved to `before_each`.
+    first: Option<usize>
+}
+
+// A converted module.
+struct Block {
+    // Indices into `Migration::tests`.
+    tests: Vec<usize>,
+    // Converted modules nested in this one, which inherit its `before_each`.
+    children: Vec<Block>,
+    // The token before which `before_each` goes, the start of the first test or nested block.
+    insert: Option<usize>
+}
+
+// A `#[test]` function which can be converted.
+struct Test<'a> {
+    name: &'a str,
+    // The first token of the function, after the attributes.
+    core: usize,
+    open: usize,
+    close: usize,
+    // The `#[test]`, `#[should_panic]` and `#[ignore]` attributes, as the `#` and the `]`.
+    test: (usize, usize),
+    should_panic: Option<(usize, usize)>,
+    ignore: Option<(usize, usize)>,
+    // The message of `#[should_panic(expected = "...")]` as written.
+    expected: Option<&'a str>
+}
+
+impl Block {
+    fn subtree_tests(&self) -> Vec<usize> {
+        let mut tests = self.tests.clone();
+        for child in &self.children {
+            tests.extend(child.subtree_tests());
+        }
+        tests
+    }
+}
+
+impl<'a> Migration<'a> {
+    // Converts the modules between the tokens `start` and `end`, returning the converted ones.
+    fn modules(&mut self, start: usize, end: usize) -> Result<Vec<Block>, String> {
+        let mut blocks = vec![];
+        for (_, entry) in self.entries(start, end)? {
+            if let (Some((keyword, private)), Some((open, close))) = (self.module(entry.core), entry.block) {
+                blocks.extend(self.module_body(keyword, private, open, close)?);
+            }
+        }
+        Ok(blocks)
+    }
+
+    // Converts the module with the `mod` keyword at `keyword` if it is private and contains tests.
+    // Modules nested in a module which is not converted are added to the roots.
+    fn module_body(&mut self, keyword: usize, private: bool, open: usize, close: usize)
+                   -> Result<Option<Block>, String> {
+        let entries = self.entries(open + 1, close)?;
+        let mut tests = vec![];
+        for &(from, entry) in &entries {
+            tests.push(self.test(from, entry)?);
+        }
+
+        if !private || tests.iter().all(Option::is_none) {
+            let blocks = self.modules(open + 1, close)?;
+            self.roots.extend(blocks);
+            return Ok(None);
+        }
+
+        let tokens = self.tokens;
+        self.edits.push((tokens[keyword].start, tokens[keyword].end, "describe!".to_string()));
+
+        let mut block = Block { tests: vec![], children: vec![], insert: None };
+        for (&(from, entry), test) in entries.iter().zip(tests) {
+            let start = self.skip_inner_attributes(from);
+            if let Some(test) = test {
+                block.insert.get_or_insert(start);
+                block.tests.push(self.convert(&test));
+            } else if self.is_super_glob(entry) {
+                // `describe!` imports the parent module itself.
+                self.remove(tokens[entry.core].start, tokens[entry.end - 1].end);
+            } else if let (Some((keyword, private)), Some((open, close))) = (self.module(entry.core), entry.block) {
+                if let Some(child) = self.module_body(keyword, private, open, close)? {
+                    block.insert.get_or_insert(start);
+                    block.children.push(child);
+                }
+            }
+        }
+        Ok(Some(block))
+    }
+
+    // The entries between `start` and `end`, with the token each one starts with, including its
+    // comments and attributes.
+    fn entries(&self, start: usize, end: usize) -> Result<Vec<(usize, Entry)>, String> {
+        let mut from = start;
+        Ok(split(self.source, self.tokens, start, end, false)?.into_iter().map(|entry| {
+            let item = (from, entry);
+            from = entry.end;
+            item
+        }).collect())
+    }
+
+    // The `mod` keyword of a module with a body starting at `core` and whether it is private.
+    fn module(&self, core: usize) -> Option<(usize, bool)> {
+        let (source, tokens) = (self.source, self.tokens);
+        let mut i = core;
+        let private = !tokens[i].is(source, "pub");
+        if !private {
+            i += 1;
+            if i < tokens.len() && tokens[i].is(source, "(") {
+                i = matching(tokens, i).ok()? + 1;
+            }
+        }
+
+        let is_module = i + 2 < tokens.len() && tokens[i].is(source, "mod") && tokens[i + 1].kind == Kind::Ident &&
+            tokens[i + 2].is(source, "{");
+        if is_module { Some((i, private)) } else { None }
+    }
+
+    fn is_super_glob(&self, entry: Entry) -> bool {
+        let texts: Vec<&str> = self.tokens[entry.core..entry.end].iter().map(|token| token.text(self.source)).collect();
+        texts == ["use", "super", ":", ":", "*", ";"]
+    }
+
+    fn skip_inner_attributes(&self, mut i: usize) -> usize {
+        let (source, tokens) = (self.source, self.tokens);
+        while i + 2 < tokens.len() && tokens[i].is(source, "#") && tokens[i + 1].is(source, "!") &&
+              tokens[i + 2].is(source, "[") {
+            i = matching(tokens, i + 2).map_or(tokens.len(), |close| close + 1);
+        }
+        i
+    }
+
+    // The test of an entry starting at `from`, if it is a `#[test]` function without arguments
+    // or return type.
+    fn test(&self, from: usize, entry: Entry) -> Result<Option<Test<'a>>, String> {
+        let (source, tokens) = (self.source, self.tokens);
+        let (open, close) = match entry.block {
+            Some(block) => block,
+            None => return Ok(None)
+        };
+
+        let mut i = entry.core;
+        if tokens[i].is(source, "pub") {
+            i += 1;
+            if tokens[i].is(source, "(") {
+                i = matching(tokens, i)? + 1;
+            }
+        }
+        let plain = i + 4 == open && tokens[i].is(source, "fn") && tokens[i + 1].kind == Kind::Ident &&
+            !tokens[i + 1].text(source).starts_with("r#") && tokens[i + 2].is(source, "(") &&
+            tokens[i + 3].is(source, ")");
+        if !plain {
+            return Ok(None);
+        }
+
+        let mut test = Test {
+            name: tokens[i + 1].text(source),
+            core: entry.core,
+            open,
+            close,
+            test: (0, 0),
+            should_panic: None,
+            ignore: None,
+            expected: None
+        };
+        let mut is_test = false;
+        let mut j = from;
+        while j < entry.core {
+            if tokens[j].is(source, "#") && tokens[j + 1].is(source, "!") {
+                j = matching(tokens, j + 2)? + 1;
+            } else if tokens[j].is(source, "#") {
+                let end = matching(tokens, j + 1)?;
+                match tokens[j + 2].text(source) {
+                    "test" if end == j + 3 => {
+                        is_test = true;
+                        test.test = (j, end);
+                    },
+                    "should_panic" => {
+                        test.should_panic = Some((j, end));
+                        test.expected = tokens[j + 3..end].iter()
+                            .find(|token| token.kind == Kind::Literal)
+                            .map(|token| token.text(source));
+                    },
+                    // `ignore` has no reason, so `#[ignore = "..."]` is kept as it is.
+                    "ignore" if end == j + 3 => test.ignore = Some((j, end)),
+                    _ => {}
+                }
+                j = end + 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        Ok(if is_test { Some(test) } else { None })
+    }
+
+    // Replaces the attributes and signature of the test with the header of a test block.
+    fn convert(&mut self, test: &Test) -> usize {
+        let tokens = self.tokens;
+        let description = test.name.replace('_', " ");
+        let header = match (test.should_panic, test.expected) {
+            (Some(_), Some(expected)) => format!("failing({}) \"{}\" ", expected, description),
+            (Some(_), None) => format!("failing \"{}\" ", description),
+            (None, _) if test.ignore.is_some() => format!("ignore \"{}\" ", description),
+            (None, _) => format!("it \"{}\" ", description)
+        };
+
+        // An ignored failing test keeps `#[ignore]`, which is copied onto the function.
+        let mut removed = vec![test.test];
+        removed.extend(test.should_panic);
+        if test.should_panic.is_none() {
+            removed.extend(test.ignore);
+        }
+        for (start, end) in removed {
+            self.remove(tokens[start].start, tokens[end].end);
+        }
+
+        self.edits.push((tokens[test.core].start, tokens[test.open].start, header));
+        self.tests.push(Body { close: test.close, from: test.open + 1, first: None });
+        self.tests.len() - 1
+    }
+
+    // Removes the bytes from `start` to `end`, with their line if nothing else is on it.
+    fn remove(&mut self, start: usize, end: usize) {
+        let source = self.source;
+        let line_start = source[..start].rfind('\n').map_or(0, |at| at + 1);
+        let mut line_end = source[end..].find('\n').map_or(source.len(), |at| end + at + 1);
+
+        if source[line_start..start].trim().is_empty() && source[end..line_end].trim().is_empty() {
+            // Don't leave a blank line at the start of a block.
+            let next_end = source[line_end..].find('\n').map_or(source.len(), |at| line_end + at + 1);
+            if source[..line_start].trim_end().ends_with('{') && source[line_end..next_end].trim().is_empty() {
+                line_end = next_end;
+            }
+            self.edits.push((line_start, line_end, String::new()));
+        } else {
+            let rest = &source[end..];
+            let spaces = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+            self.edits.push((start, end + spaces, String::new()));
+        }
+    }
+
+    // Moves the statements which all tests of the block and its nested blocks start with to a
+    // `before_each` block, then does the same for the nested blocks.
+    fn hoist(&mut self, block: &Block) -> Result<(), String> {
+        let tests = block.subtree_tests();
+        if tests.len() >= 2 {
+            let mut statements = vec![];
+            for &test in &tests {
+                statements.push(self.statements(&self.tests[test])?);
+            }
+
+            let first = &statements[0];
+            let mut count = (0..first.len())
+                .take_while(|&k| statements.iter().all(|other| k < other.len() && self.same(other[k], first[k])))
+                .count();
+            // Every test keeps at least one statement, an empty body would not test anything.
+            if count > 0 && tests.iter().zip(&statements)
+                .any(|(&test, statements)| self.is_empty(statements[count - 1].1 + 1, self.tests[test].close)) {
+                count -= 1;
+            }
+
+            if count > 0 {
+                self.insert_before_each(block.insert.unwrap_or(0), first[0].0, first[count - 1].1);
+                for (&test, statements) in tests.iter().zip(&statements) {
+                    let body = &mut self.tests[test];
+                    body.first.get_or_insert(statements[0].0);
+                    body.from = statements[count - 1].1 + 1;
+                }
+            }
+        }
+
+        for child in &block.children {
+            self.hoist(child)?;
+        }
+        Ok(())
+    }
+
+    // The statements ending with `;` from the start of a test block, as the first and last token.
+    fn statements(&self, body: &Body) -> Result<Vec<(usize, usize)>, String> {
+        let (source, tokens) = (self.source, self.tokens);
+        let mut statements = vec![];
+        let mut i = body.from;
+        loop {
+            while i < body.close && tokens[i].kind == Kind::Comment {
+                i += 1;
+            }
+            let start = i;
+            loop {
+                if i >= body.close {
+                    return Ok(statements);
+                }
+                if tokens[i].is(source, ";") {
+                    statements.push((start, i));
+                    i += 1;
+                    break;
+                }
+                i = if tokens[i].kind == Kind::Open { matching(tokens, i)? + 1 } else { i + 1 };
+            }
+        }
+    }
+
+    // Whether there are only comments from the token `start` up to `end`.
+    fn is_empty(&self, start: usize, end: usize) -> bool {
+        self.tokens[start..end].iter().all(|token| token.kind == Kind::Comment)
+    }
+
+    // Whether two statements are the same tokens, ignoring whitespace and comments.
+    fn same(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        let texts = |(start, end): (usize, usize)| {
+            self.tokens[start..end + 1].iter()
+                .filter(|token| token.kind != Kind::Comment)
+                .map(|token| token.text(self.source))
+                .collect::<Vec<&str>>()
+        };
+        texts(a) == texts(b)
+    }
+
+    // Inserts `before_each` with the source from the token `first` to `last` before the token
+    // `insert`.
+    fn insert_before_each(&mut self, insert: usize, first: usize, last: usize) {
+        let source = self.source;
+        let at = self.tokens[insert].start;
+        let statements = &source[self.tokens[first].start..self.tokens[last].end];
+        let line_start = source[..at].rfind('\n').map_or(0, |at| at + 1);
+        let indent = &source[line_start..at];
+
+        let text = if indent.trim().is_empty() {
+            format!("{}before_each {{\n{}    {}\n{}}}\n\n", indent, indent, statements, indent)
+        } else {
+            format!("before_each {{ {} }} ", statements)
+        };
+        let at = if indent.trim().is_empty() { line_start } else { at };
+        self.edits.push((at, at, text));
+    }
+
+    // Removes the statements moved to `before_each` from the tests.
+    fn trim_tests(&mut self) {
+        let tokens = self.tokens;
+        for body in &self.tests {
+            let first = match body.first {
+                Some(first) => first,
+                None => continue
+            };
+            self.edits.push((tokens[first].start, tokens[body.from].start, String::new()));
+        }
+    }
+}
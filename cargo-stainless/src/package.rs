@@ -0,0 +1,66 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The directories with the sources of a package.
+pub const SOURCE_DIRS: &[&str] = &["src", "tests", "benches", "examples"];
+
+/// The closest directory containing a `Cargo.toml`, starting from the current directory.
+pub fn package_root() -> Result<PathBuf, String> {
+    let cwd = env::current_dir().map_err(|e| format!("failed to read the current directory: {}", e))?;
+    cwd.ancestors()
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .map(Path::to_path_buf)
+        .ok_or_else(|| "could not find `Cargo.toml` in the current directory or any parent".to_string())
+}
+
+/// The root files of the library, binaries, tests, benchmarks and examples of the package at
+/// `root`, following the layout cargo discovers targets with.
+///
+/// Modules are found by parsing the roots, so files of other modules are not included.
+pub fn crate_roots(root: &Path) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = ["src/lib.rs", "src/main.rs"].iter()
+        .map(|file| root.join(file))
+        .filter(|file| file.is_file())
+        .collect();
+
+    for dir in &["src/bin", "tests", "benches", "examples"] {
+        let mut files: Vec<PathBuf> = match fs::read_dir(root.join(dir)) {
+            Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect(),
+            Err(_) => continue
+        };
+        files.sort();
+
+        for path in files {
+            if path.extension().is_some_and(|extension| extension == "rs") {
+                roots.push(path);
+            } else if path.join("main.rs").is_file() {
+                roots.push(path.join("main.rs"));
+            }
+        }
+    }
+    roots
+}
+
+/// All Rust files in the source directories of the package at `root`, sorted.
+pub fn source_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = vec![];
+    let mut dirs: Vec<PathBuf> = SOURCE_DIRS.iter().map(|dir| root.join(dir)).collect();
+    while let Some(dir) = dirs.pop() {
+        for path in fs::read_dir(&dir).into_iter().flatten().filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|extension| extension == "rs") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
@@ -0,0 +1,565 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+// Reading `describe!` blocks from source files without the compiler.
+//
+// Files are split into tokens with the lexer of `cargo stainless fmt`, and the blocks are read
+// with the grammar the plugin reads them with, from the `stainless_grammar` crate. Modules
+// declared with `mod name;` are loaded from the files the compiler would load them from.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use grammar::{self, Meta, Punct};
+
+use fmt::{item_end, split};
+use lexer::{matching, tokenize, Kind, Token};
+use list::{self, Spec};
+
+/// A `describe!` block.
+#[derive(Clone, Debug)]
+pub struct Describe {
+    /// The name of the block.
+    pub name: String,
+    /// The file the block is written in.
+    pub file: PathBuf,
+    /// The line of `describe!` for top-level blocks, and of the name for nested blocks.
+    pub line: usize,
+    /// Unless `#![no_super_glob]` is given.
+    pub super_glob: bool,
+    /// `#![tags(...)]`.
+    pub tags: Vec<String>,
+    /// `#![serial]` or `#![serial(group)]`.
+    pub serial: Option<Option<String>>,
+    /// `#![isolated]`.
+    pub isolated: bool,
+    /// The tests, benchmarks and nested blocks, in the order they are written.
+    pub children: Vec<Child>
+}
+
+/// A test, benchmark or nested block of a `describe!` block.
+#[derive(Clone, Debug)]
+pub enum Child {
+    /// An `it`, `when`, `failing`, `ignore` or `scenario` test.
+    Test(Test),
+    /// A `bench` block.
+    Bench(Bench),
+    /// A nested `describe!` block.
+    Describe(Describe)
+}
+
+/// A test.
+#[derive(Clone, Debug)]
+pub struct Test {
+    /// `it`, `when`, `failing`, `ignore` or `scenario`.
+    pub keyword: String,
+    /// The description of the test.
+    pub description: String,
+    /// The line of the description.
+    pub line: usize,
+    /// The message of `failing("message")`, for failing tests.
+    pub failing: Option<Option<String>>,
+    /// `tags(...)`.
+    pub tags: Vec<String>,
+    /// `serial` or `serial(group)`.
+    pub serial: Option<Option<String>>,
+    /// `isolated`.
+    pub isolated: bool
+}
+
+impl Test {
+    /// Whether the test is written with `ignore`.
+    pub fn ignored(&self) -> bool {
+        self.keyword == "ignore"
+    }
+}
+
+/// A `bench` block.
+#[derive(Clone, Debug)]
+pub struct Bench {
+    /// The description of the benchmark.
+    pub description: String,
+    /// The line of the description.
+    pub line: usize
+}
+
+/// Parse the `describe!` blocks of the crates rooted at `files`, including the blocks in their
+/// modules. Locations are relative to `root`.
+pub fn parse_specs(root: &Path, files: &[PathBuf]) -> Result<Vec<Spec>, String> {
+    let mut specs = vec![];
+    with_describes(files, |describe| specs.push(self::describe(&describe, root)))?;
+    Ok(specs)
+}
+
+/// Call `f` with every top-level `describe!` block of the crates rooted at `files`, including
+/// the blocks in their modules, in the order they are written. Invalid blocks are skipped with a
+/// warning, compiling them reports the error.
+pub fn with_describes<F: FnMut(Describe)>(files: &[PathBuf], mut f: F) -> Result<(), String> {
+    for file in files {
+        read_crate(file)?.describes.into_iter().for_each(&mut f);
+    }
+    Ok(())
+}
+
+/// A crate read from the source.
+#[derive(Clone, Debug, Default)]
+pub struct Crate {
+    /// Whether the crate uses `#![plugin(stainless(harness))]`.
+    pub harness: bool,
+    /// The root file and the files of the modules of the crate.
+    pub files: Vec<PathBuf>,
+    /// The top-level `describe!` blocks, including the blocks in modules.
+    pub describes: Vec<Describe>
+}
+
+/// Read the crate rooted at `file`, following `mod` declarations.
+pub fn read_crate(file: &Path) -> Result<Crate, String> {
+    let mut krate = Crate::default();
+    read_module(file, file.parent().unwrap_or(Path::new("")), &mut krate)?;
+    Ok(krate)
+}
+
+// A file being read.
+struct SourceFile<'a> {
+    path: &'a Path,
+    source: &'a str,
+    tokens: &'a [Token]
+}
+
+// Reads the module in `path`, whose modules are in `dir`, into `krate`. The first file read is
+// the crate root, which enables harness mode.
+fn read_module(path: &Path, dir: &Path, krate: &mut Crate) -> Result<(), String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("failed to read `{}`: {}", path.display(), e))?;
+    let tokens = tokenize(&source).map_err(|e| format!("failed to read `{}`: {}", path.display(), e))?;
+    if krate.files.is_empty() {
+        krate.harness = is_harness(&source, &tokens);
+    }
+    krate.files.push(path.to_path_buf());
+
+    let file = SourceFile { path, source: &source, tokens: &tokens };
+    items(&file, 0, tokens.len(), dir, krate)
+}
+
+// Finds the `describe!` blocks in the items between `start` and `end` and in their modules.
+fn items(file: &SourceFile, start: usize, end: usize, dir: &Path, krate: &mut Crate) -> Result<(), String> {
+    let (source, tokens) = (file.source, file.tokens);
+    let mut attrs_start = start;
+    for entry in split(source, tokens, start, end, false)? {
+        let attrs = attributes(file, attrs_start, entry.core);
+        attrs_start = entry.end;
+
+        let mut i = entry.core;
+        if tokens[i].is(source, "pub") {
+            i += 1;
+            if i < entry.end && tokens[i].is(source, "(") {
+                i = matching(tokens, i)? + 1;
+            }
+        }
+
+        if i + 1 < entry.end && tokens[i].is(source, "mod") && tokens[i + 1].kind == Kind::Ident {
+            let name = tokens[i + 1].text(source);
+            match entry.block {
+                Some((open, close)) => items(file, open + 1, close, &dir.join(name), krate)?,
+                None => {
+                    // Modules which are missing are configured out, or generated by a build
+                    // script.
+                    if let Some((path, dir)) = module_file(file, dir, name, &attrs) {
+                        read_module(&path, &dir, krate)?;
+                    }
+                }
+            }
+            continue;
+        }
+
+        let core = entry.core;
+        let is_describe = core + 2 < entry.end && tokens[core].is(source, "describe") &&
+            tokens[core + 1].is(source, "!") && tokens[core + 2].kind == Kind::Ident;
+        if let (true, Some((open, close))) = (is_describe, entry.block) {
+            let line = line(source, tokens[core].start);
+            let name = tokens[core + 2].text(source).to_string();
+            let mut cursor = Cursor { file, at: open + 1, end: close };
+            match grammar::parse_describe(&mut cursor, name, line).map(|describe| self::convert(file, describe)) {
+                Ok(describe) => krate.describes.push(describe),
+                Err(e) => eprintln!("warning: skipping the invalid `describe!` block at {}:{}, {}",
+                                    file.path.display(), line, e)
+            }
+        }
+    }
+    Ok(())
+}
+
+// The file of the module `name` declared with `mod name;` and the directory of its modules, like
+// the compiler finds them. `#[path = "..."]` is relative to the directory of the declaring file.
+fn module_file(file: &SourceFile, dir: &Path, name: &str, attrs: &[(usize, usize)]) -> Option<(PathBuf, PathBuf)> {
+    let (source, tokens) = (file.source, file.tokens);
+    let path = attrs.iter()
+        .filter(|&&(start, close)| start + 5 <= close && tokens[start + 2].is(source, "path") &&
+                tokens[start + 3].is(source, "="))
+        .filter_map(|&(start, _)| string(source, &tokens[start + 4]))
+        .next();
+    if let Some(path) = path {
+        let path = file.path.parent().unwrap_or(Path::new("")).join(path);
+        let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        return if path.is_file() { Some((path, dir)) } else { None };
+    }
+
+    let own = dir.join(format!("{}.rs", name));
+    let nested = dir.join(name).join("mod.rs");
+    if own.is_file() {
+        Some((own, dir.join(name)))
+    } else if nested.is_file() {
+        Some((nested, dir.join(name)))
+    } else {
+        None
+    }
+}
+
+// Whether the inner attributes of a crate root contain `#![plugin(stainless(harness))]`.
+fn is_harness(source: &str, tokens: &[Token]) -> bool {
+    let mut i = 0;
+    loop {
+        if i < tokens.len() && tokens[i].kind == Kind::Comment {
+            i += 1;
+            continue;
+        }
+        if !(i + 2 < tokens.len() && tokens[i].is(source, "#") && tokens[i + 1].is(source, "!") &&
+             tokens[i + 2].is(source, "[")) {
+            return false;
+        }
+
+        let close = match matching(tokens, i + 2) {
+            Ok(close) => close,
+            Err(_) => return false
+        };
+        let attr: Vec<&str> = tokens[i + 3..close].iter()
+            .filter(|token| token.kind != Kind::Comment)
+            .map(|token| token.text(source))
+            .collect();
+        if attr.first() == Some(&"plugin") && attr.windows(3).any(|words| words == ["stainless", "(", "harness"]) {
+            return true;
+        }
+        i = close + 1;
+    }
+}
+
+// The outer attributes between `start` and `end`, from `#` to `]`.
+fn attributes(file: &SourceFile, start: usize, end: usize) -> Vec<(usize, usize)> {
+    let (source, tokens) = (file.source, file.tokens);
+    let mut attrs = vec![];
+    let mut i = start;
+    while i + 1 < end {
+        if tokens[i].is(source, "#") && tokens[i + 1].is(source, "[") {
+            if let Ok(close) = matching(tokens, i + 1) {
+                attrs.push((i, close));
+                i = close + 1;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    attrs
+}
+
+// The tokens of the body of a `describe!` block, read with the grammar of the plugin. Comments are
+// skipped.
+struct Cursor<'a> {
+    file: &'a SourceFile<'a>,
+    // The current token, or a comment before it.
+    at: usize,
+    // The closing brace of the body.
+    end: usize
+}
+
+impl<'a> Cursor<'a> {
+    // The index of the token `ahead` tokens after the current one, skipping comments.
+    fn index(&self, ahead: usize) -> Option<usize> {
+        (self.at..self.end).filter(|&i| self.file.tokens[i].kind != Kind::Comment).nth(ahead)
+    }
+
+    fn token(&self, ahead: usize) -> Option<&Token> {
+        self.index(ahead).map(|i| &self.file.tokens[i])
+    }
+
+    fn text(&self, ahead: usize) -> Option<&str> {
+        self.token(ahead).map(|token| token.text(self.file.source))
+    }
+
+    // Reads `#[...]` if `inner` is not set, or `#![...]` if it is.
+    fn attributes(&mut self, inner: bool) -> Result<Vec<Meta>, String> {
+        let bang = if inner { 1 } else { 0 };
+        let mut attrs = vec![];
+        while self.text(0) == Some("#") && (!inner || self.text(1) == Some("!")) && self.text(1 + bang) == Some("[") {
+            let (source, tokens) = (self.file.source, self.file.tokens);
+            let open = self.index(1 + bang).unwrap();
+            let close = matching(tokens, open)?;
+            let words: Vec<&Token> = tokens[open + 1..close].iter().filter(|token| token.kind != Kind::Comment).collect();
+            let name = words.first().map_or("", |token| token.text(source)).to_string();
+            let list = match words.get(1) {
+                Some(paren) if paren.is(source, "(") => {
+                    let items = words[2..words.len() - 1].split(|token| token.is(source, ","));
+                    Some(items.filter(|item| !item.is_empty())
+                        .map(|item| match *item {
+                            [name] if name.kind == Kind::Ident => Some(name.text(source).to_string()),
+                            _ => None
+                        })
+                        .collect())
+                },
+                _ => None
+            };
+            attrs.push(Meta { name, list });
+            self.at = close + 1;
+        }
+        Ok(attrs)
+    }
+}
+
+impl<'a> grammar::Tokens for Cursor<'a> {
+    type Span = usize;
+    type Ident = String;
+    type Attribute = Meta;
+    type Block = ();
+    type Item = ();
+    type Error = String;
+
+    fn span(&self) -> usize {
+        line(self.file.source, self.file.tokens[self.index(0).unwrap_or(self.end)].start)
+    }
+
+    fn ident(&self, ahead: usize) -> Option<String> {
+        self.token(ahead).filter(|token| token.kind == Kind::Ident).map(|token| token.text(self.file.source).to_string())
+    }
+
+    fn is(&self, ahead: usize, punct: Punct) -> bool {
+        let text = match punct {
+            Punct::Not => "!",
+            Punct::Comma => ",",
+            Punct::OpenParen => "(",
+            Punct::CloseParen => ")",
+            Punct::OpenBrace => "{",
+            Punct::CloseBrace => "}"
+        };
+        self.token(ahead).is_some_and(|token| token.is(self.file.source, text))
+    }
+
+    fn at_end(&self) -> bool {
+        self.index(0).is_none()
+    }
+
+    fn bump(&mut self) {
+        self.at = self.index(0).map_or(self.end, |i| i + 1);
+    }
+
+    fn parse_ident(&mut self) -> Result<String, String> {
+        let ident = self.ident(0).ok_or_else(|| self.error("expected an identifier"))?;
+        self.bump();
+        Ok(ident)
+    }
+
+    fn name(&self, ident: &String) -> String {
+        ident.clone()
+    }
+
+    fn parse_str(&mut self) -> Result<String, String> {
+        let string = self.token(0)
+            .and_then(|token| string(self.file.source, token))
+            .ok_or_else(|| self.error("expected a string literal"))?;
+        self.bump();
+        Ok(string)
+    }
+
+    fn parse_block(&mut self) -> Result<(), String> {
+        let (source, tokens) = (self.file.source, self.file.tokens);
+        let open = match self.index(0) {
+            Some(open) if tokens[open].is(source, "{") => open,
+            _ => return Err(self.error("expected a block"))
+        };
+        let close = matching(tokens, open)?;
+        self.at = close + 1;
+        Ok(())
+    }
+
+    fn parse_inner_attributes(&mut self) -> Result<Vec<Meta>, String> {
+        self.attributes(true)
+    }
+
+    fn parse_outer_attributes(&mut self) -> Result<Vec<Meta>, String> {
+        self.attributes(false)
+    }
+
+    fn parse_item(&mut self, _: Vec<Meta>) -> Result<(), String> {
+        let start = self.index(0).ok_or_else(|| self.error("expected an item"))?;
+        self.at = item_end(self.file.source, self.file.tokens, start, self.end)?.0;
+        Ok(())
+    }
+
+    fn meta(&self, attr: &Meta) -> Meta {
+        attr.clone()
+    }
+
+    fn without(&self, attr: Meta, names: &[&str]) -> Option<Meta> {
+        let others: Vec<Option<String>> = attr.list.unwrap_or_default().into_iter()
+            .filter(|name| !name.as_ref().is_some_and(|name| names.contains(&&**name)))
+            .collect();
+        if others.is_empty() {
+            return None;
+        }
+        Some(Meta { name: attr.name, list: Some(others) })
+    }
+
+    fn error(&self, message: &str) -> String {
+        match self.text(0) {
+            Some(found) => format!("{}, found `{}`", message, found),
+            None => format!("{}, found the end of the block", message)
+        }
+    }
+}
+
+// The `describe!` block read with the grammar, as found in `file`.
+fn convert(file: &SourceFile, describe: grammar::Describe<Cursor>) -> Describe {
+    let children = describe.children.into_iter().filter_map(|child| match child {
+        grammar::Child::Test(test) => Some(Child::Test(Test {
+            keyword: test.keyword,
+            description: test.description,
+            line: test.span,
+            failing: test.failing,
+            tags: test.tags,
+            serial: test.serial,
+            isolated: test.isolated
+        })),
+        grammar::Child::Bench(bench) => Some(Child::Bench(Bench { description: bench.description, line: bench.span })),
+        grammar::Child::Describe(nested) => Some(Child::Describe(convert(file, nested))),
+        grammar::Child::Item(()) => None
+    }).collect();
+
+    Describe {
+        name: describe.name,
+        file: file.path.to_path_buf(),
+        line: describe.span,
+        super_glob: describe.super_glob,
+        tags: describe.tags,
+        serial: describe.serial,
+        isolated: describe.isolated,
+        children
+    }
+}
+
+// The value of a string literal like `"a \"quoted\" word"` or `r#"raw"#`.
+fn string(source: &str, token: &Token) -> Option<String> {
+    if token.kind != Kind::Literal {
+        return None;
+    }
+    let text = token.text(source);
+    if let Some(raw) = text.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        return raw.get(hashes + 1..raw.len() - hashes - 1).map(str::to_string);
+    }
+
+    let mut value = String::new();
+    let mut chars = text.strip_prefix('"')?.strip_suffix('"')?.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => value.push('\n'),
+            'r' => value.push('\r'),
+            't' => value.push('\t'),
+            '0' => value.push('\0'),
+            'x' => {
+                let code: String = chars.by_ref().take(2).collect();
+                value.push(char::from(u8::from_str_radix(&code, 16).ok()?));
+            },
+            'u' => {
+                let code: String = chars.by_ref().skip(1).take_while(|&c| c != '}').collect();
+                value.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+            },
+            // A line continuation skips the whitespace at the start of the next line.
+            '\n' => {
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+            },
+            c => value.push(c)
+        }
+    }
+    Some(value)
+}
+
+// The line of a byte offset, starting at 1.
+fn line(source: &str, offset: usize) -> usize {
+    source[..offset].matches('\n').count() + 1
+}
+
+fn describe(describe: &Describe, root: &Path) -> Spec {
+    let file = relative(&describe.file, root);
+    let mut modifiers = vec![];
+    if !describe.super_glob {
+        modifiers.push("no_super_glob".to_string());
+    }
+    modifiers.extend(serial(&describe.serial));
+    if describe.isolated {
+        modifiers.push("isolated".to_string());
+    }
+
+    let children = describe.children.iter().map(|child| match *child {
+        Child::Test(ref test) => self::test_spec(test, &file),
+        Child::Bench(ref bench) => {
+            Spec { kind: list::Kind::Bench, name: bench.description.clone(), file: file.clone(),
+                   line: bench.line, tags: vec![], modifiers: vec![], children: vec![] }
+        },
+        Child::Describe(ref nested) => self::describe(nested, root)
+    }).collect();
+
+    Spec {
+        kind: list::Kind::Describe,
+        name: describe.name.clone(),
+        file,
+        line: describe.line,
+        tags: describe.tags.clone(),
+        modifiers,
+        children
+    }
+}
+
+fn test_spec(test: &Test, file: &str) -> Spec {
+    let mut modifiers = vec![];
+    match test.failing {
+        Some(Some(ref message)) => modifiers.push(format!("failing({:?})", message)),
+        Some(None) => modifiers.push("failing".to_string()),
+        None => {}
+    }
+    if test.ignored() {
+        modifiers.push("ignore".to_string());
+    }
+    modifiers.extend(serial(&test.serial));
+    if test.isolated {
+        modifiers.push("isolated".to_string());
+    }
+
+    Spec {
+        kind: list::Kind::Test,
+        name: test.description.clone(),
+        file: file.to_string(),
+        line: test.line,
+        tags: test.tags.clone(),
+        modifiers,
+        children: vec![]
+    }
+}
+
+fn serial(serial: &Option<Option<String>>) -> Option<String> {
+    match *serial {
+        Some(Some(ref group)) => Some(format!("serial({})", group)),
+        Some(None) => Some("serial".to_string()),
+        None => None
+    }
+}
+
+/// The path of `file` relative to `root`, or as given if it is not in `root`.
+pub fn relative(file: &Path, root: &Path) -> String {
+    file.strip_prefix(root).unwrap_or(file).display().to_string()
+}
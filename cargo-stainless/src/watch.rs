@@ -0,0 +1,318 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+use notify::{self, Event, EventKind, RecursiveMode, Watcher};
+use serde_json::{self, Value};
+use stainless_runtime::test_name;
+
+use package::{crate_roots, package_root, SOURCE_DIRS};
+use source::read_crate;
+
+/// Usage printed for `cargo stainless watch --help`.
+const USAGE: &str = "\
+Usage: cargo stainless watch [OPTIONS] [-- ARGS]
+
+Runs `cargo test` whenever files change, failed tests first. ARGS are passed to the test
+binaries, all other options to `cargo test`.
+
+Options:
+        --all           Run all tests, not only those in the changed files
+    -h, --help          Display this message
+";
+
+// How long to wait for more changes, editors often save a file in several steps.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// The tests to run after files changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Selection {
+    /// The test targets to run, passed to `cargo test` with `--test`. All targets if empty.
+    pub tests: Vec<String>,
+    /// The path filters passed to the test binaries, like `** > addition`. All tests if empty.
+    pub filters: Vec<String>
+}
+
+/// Options of `cargo stainless watch`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WatchOptions {
+    /// Run all tests after every change, instead of the tests in the changed files.
+    pub all: bool,
+    /// Passed to `cargo test`, e.g. `--test spec`.
+    pub cargo_args: Vec<String>,
+    /// Passed to the test binaries, given after `--`.
+    pub test_args: Vec<String>,
+    /// Print the usage and exit.
+    pub help: bool
+}
+
+impl WatchOptions {
+    /// Parse the options following `cargo stainless watch`.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<WatchOptions, String> {
+        let mut options = WatchOptions::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match &*arg {
+                "--all" => options.all = true,
+                "-h" | "--help" => options.help = true,
+                "--" => options.test_args.extend(args.by_ref()),
+                _ => options.cargo_args.push(arg)
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Run the tests of the package in the current directory, and again whenever its files change.
+pub fn watch(options: &WatchOptions) -> Result<(), String> {
+    if options.help {
+        print!("{}", USAGE);
+        return Ok(());
+    }
+
+    let root = package_root()?;
+    let (sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)
+        .map_err(|e| format!("failed to watch for changes: {}", e))?;
+    for dir in SOURCE_DIRS.iter().map(|dir| root.join(dir)).filter(|dir| dir.is_dir()) {
+        watcher.watch(&dir, RecursiveMode::Recursive)
+            .map_err(|e| format!("failed to watch `{}`: {}", dir.display(), e))?;
+    }
+    watcher.watch(&root.join("Cargo.toml"), RecursiveMode::NonRecursive)
+        .map_err(|e| format!("failed to watch `Cargo.toml`: {}", e))?;
+
+    // Test binaries run in the directory of their package, so the report needs an absolute path.
+    let report = env::temp_dir().join(format!("stainless-watch-{}.json", process::id()));
+    let mut failed = vec![];
+    let mut selection = Selection::default();
+    loop {
+        failed = run(options, &report, failed, &selection)?;
+
+        println!("\nWaiting for changes...");
+        let changed = wait_for_changes(&events)?;
+        selection = if options.all { Selection::default() } else { affected_tests(&root, &changed) };
+    }
+}
+
+/// The names of the `describe!` blocks in Rust source.
+///
+/// This only looks for `describe!` followed by a name, without parsing the source, so blocks in
+/// comments or strings are found as well.
+pub fn spec_names(source: &str) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for (at, _) in source.match_indices("describe!") {
+        let rest = source[at + "describe!".len()..].trim_start();
+        let name: String = rest.chars().take_while(|&c| c.is_alphanumeric() || c == '_').collect();
+        if !name.is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// The tests of the `describe!` blocks in the changed files of the package at `root`. Path
+/// filters are only understood by the runtime, so they are passed to the test targets in harness
+/// mode which contain the files. Everything runs when any of the files has no blocks, like
+/// changes to the code under test, or is not part of such a target.
+pub fn affected_tests(root: &Path, changed: &[PathBuf]) -> Selection {
+    // The test targets in harness mode, named after their root file or directory.
+    let targets: Vec<(String, Vec<PathBuf>)> = crate_roots(root).into_iter()
+        .filter(|file| file.starts_with(root.join("tests")))
+        .filter_map(|file| {
+            let krate = read_crate(&file).ok().filter(|krate| krate.harness)?;
+            let name = if file.ends_with("main.rs") { file.parent()?.file_name()? } else { file.file_stem()? };
+            Some((name.to_string_lossy().into_owned(), krate.files.iter().map(|file| canonical(file)).collect()))
+        })
+        .collect();
+
+    let mut selection = Selection::default();
+    for path in changed {
+        let names = fs::read_to_string(path).map(|source| spec_names(&source)).unwrap_or_default();
+        let path = canonical(path);
+        let tests: Vec<&String> = targets.iter()
+            .filter(|(_, files)| files.contains(&path))
+            .map(|(name, _)| name)
+            .collect();
+        if names.is_empty() || tests.is_empty() {
+            return Selection::default();
+        }
+        selection.tests.extend(tests.into_iter().cloned());
+        selection.filters.extend(names.iter().map(|name| format!("** > {}", name)));
+    }
+
+    for list in [&mut selection.tests, &mut selection.filters] {
+        list.sort();
+        list.dedup();
+    }
+    selection
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// The names of the failed tests in a report written by `--json`.
+pub fn failed_tests(report: &str) -> Vec<String> {
+    report.lines().filter_map(|line| {
+        let event: Value = serde_json::from_str(line).ok()?;
+        if event["type"] != "test_failed" {
+            return None;
+        }
+        let path: Vec<&str> = event["path"].as_array()?.iter().filter_map(Value::as_str).collect();
+        Some(test_name(&path, event["description"].as_str()?))
+    }).collect()
+}
+
+/// The names of the failed tests in the output of test binaries using the test harness of Rust,
+/// listed after the last `failures:` of every binary.
+pub fn libtest_failures(output: &str) -> Vec<String> {
+    let mut failed = vec![];
+    let mut lines = output.lines();
+    while let Some(line) = lines.next() {
+        // Only the last list has the names right below it, four spaces deep. The first one is
+        // followed by the output of the tests, and the one of harness mode by an empty line.
+        if line != "failures:" {
+            continue;
+        }
+        for line in lines.by_ref() {
+            match line.strip_prefix("    ") {
+                Some(name) if !name.starts_with(' ') && !name.is_empty() => failed.push(name.to_string()),
+                _ => break
+            }
+        }
+    }
+    failed
+}
+
+// Runs the previously failed tests, then the selected tests, and returns the tests which failed.
+// Keeps the previous failures if the tests could not be built.
+fn run(options: &WatchOptions, report: &Path, failed: Vec<String>, selection: &Selection)
+       -> Result<Vec<String>, String> {
+    let mut failing = vec![];
+    if !failed.is_empty() {
+        println!("Running {} previously failed tests", failed.len());
+        let mut args = vec!["--exact".to_string()];
+        args.extend(failed.iter().cloned());
+        match cargo_test(options, &[], report, &args)? {
+            Some(failures) => failing = failures,
+            None => return Ok(failed)
+        }
+    }
+
+    let mut args = vec!["--exact".to_string()];
+    for name in &failed {
+        args.push("--skip".to_string());
+        args.push(name.clone());
+    }
+    args.extend(selection.filters.iter().cloned());
+    match cargo_test(options, &selection.tests, report, &args)? {
+        Some(failures) => failing.extend(failures),
+        None => return Ok(failed)
+    }
+    Ok(failing)
+}
+
+// Runs the test targets `tests`, or all targets, and returns the failed tests, or `None` if the
+// tests could not be built. The tests are built first, so that build errors are told apart from
+// failed tests by the exit status of cargo.
+fn cargo_test(options: &WatchOptions, tests: &[String], report: &Path, args: &[String])
+              -> Result<Option<Vec<String>>, String> {
+    let built = cargo_command(options, tests)
+        .arg("--no-run")
+        .status()
+        .map_err(|e| format!("failed to run `cargo test`: {}", e))?;
+    if !built.success() {
+        return Ok(None);
+    }
+
+    // All test binaries run even if one fails, so that all failures are remembered.
+    let _ = fs::remove_file(report);
+    let mut child = cargo_command(options, tests)
+        .arg("--no-fail-fast")
+        .arg("--")
+        .args(args)
+        .args(&options.test_args)
+        .env("STAINLESS_JSON", report)
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run `cargo test`: {}", e))?;
+    let output = child.stdout.take().map(tee).unwrap_or_default();
+    child.wait().map_err(|e| format!("failed to run `cargo test`: {}", e))?;
+
+    // Harness mode reports its failures as JSON events, other test binaries only print them.
+    let mut failed = fs::read_to_string(report).map(|report| failed_tests(&report)).unwrap_or_default();
+    for name in libtest_failures(&output) {
+        if !failed.contains(&name) {
+            failed.push(name);
+        }
+    }
+    Ok(Some(failed))
+}
+
+// Prints the output of the tests as it comes, and returns it.
+fn tee<R: Read>(mut input: R) -> String {
+    let mut output = vec![];
+    let mut buffer = [0; 4096];
+    let stdout = io::stdout();
+    while let Ok(read) = input.read(&mut buffer) {
+        if read == 0 {
+            break;
+        }
+        let mut stdout = stdout.lock();
+        let _ = stdout.write_all(&buffer[..read]).and_then(|_| stdout.flush());
+        output.extend_from_slice(&buffer[..read]);
+    }
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+fn cargo_command(options: &WatchOptions, tests: &[String]) -> Command {
+    let mut command = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+    command.arg("test").args(&options.cargo_args);
+    for test in tests {
+        command.arg("--test").arg(test);
+    }
+    command
+}
+
+// Blocks until source files changed and returns them.
+fn wait_for_changes(events: &Receiver<notify::Result<Event>>) -> Result<Vec<PathBuf>, String> {
+    let mut changed = vec![];
+    while changed.is_empty() {
+        let event = events.recv().map_err(|_| "the file watcher stopped".to_string())?;
+        changed.extend(sources(event));
+    }
+    while let Ok(event) = events.recv_timeout(DEBOUNCE) {
+        changed.extend(sources(event));
+    }
+
+    changed.sort();
+    changed.dedup();
+    Ok(changed)
+}
+
+// The changed Rust sources and manifests of an event.
+fn sources(event: notify::Result<Event>) -> Vec<PathBuf> {
+    match event {
+        Ok(Event { kind: EventKind::Create(_), paths, .. }) |
+        Ok(Event { kind: EventKind::Modify(_), paths, .. }) |
+        Ok(Event { kind: EventKind::Remove(_), paths, .. }) => {
+            paths.into_iter().filter(|path| {
+                path.extension().is_some_and(|extension| extension == "rs") ||
+                    path.file_name().is_some_and(|name| name == "Cargo.toml")
+            }).collect()
+        },
+        _ => vec![]
+    }
+}
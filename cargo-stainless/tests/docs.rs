@@ -0,0 +1,156 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+extern crate cargo_stainless;
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use cargo_stainless::{docs, html, markdown, report_statuses, DocsFormat, DocsOptions, Kind, Spec, Status};
+
+fn spec(kind: Kind, name: &str, modifiers: &[&str], children: Vec<Spec>) -> Spec {
+    Spec {
+        kind,
+        name: name.to_string(),
+        file: "tests/spec.rs".to_string(),
+        line: 1,
+        tags: vec![],
+        modifiers: modifiers.iter().map(|modifier| modifier.to_string()).collect(),
+        children
+    }
+}
+
+fn specs() -> Vec<Spec> {
+    vec![spec(Kind::Describe, "shopping_cart", &[], vec![
+        spec(Kind::Test, "starts empty", &[], vec![]),
+        spec(Kind::Test, "rejects negative amounts", &["failing(\"negative\")"], vec![]),
+        spec(Kind::Test, "applies coupons", &["ignore"], vec![]),
+        spec(Kind::Bench, "adds items", &[], vec![]),
+        spec(Kind::Describe, "checkout", &[], vec![
+            spec(Kind::Test, "charges the <total>", &[], vec![])
+        ]),
+        spec(Kind::Describe, "gift_cards", &[], vec![
+            spec(Kind::Test, "can be redeemed", &["ignore"], vec![])
+        ])
+    ])]
+}
+
+#[test]
+fn writes_markdown() {
+    assert_eq!(markdown("Specification", &specs(), &HashMap::new()), "\
+# Specification
+
+## shopping cart
+
+- starts empty
+- rejects negative amounts
+
+### checkout
+
+- charges the <total>
+
+## Pending
+
+- shopping cart › applies coupons
+- shopping cart › gift cards › can be redeemed
+");
+}
+
+#[test]
+fn merges_the_latest_report() {
+    let report = r#"{"type":"run_started","schema":1,"seed":null}
+{"type":"test_failed","description":"starts empty","path":["shopping_cart"],"tags":[],"duration_ms":1.000,"message":"no","output":"","phases":[]}
+{"type":"run_finished","passed":0,"failed":1,"ignored":0,"not_run":0,"duration_ms":1.000}
+{"type":"run_started","schema":1,"seed":null}
+{"type":"test_passed","description":"starts empty","path":["shopping_cart"],"tags":[],"duration_ms":1.000,"output":"","phases":[]}
+{"type":"test_failed","description":"charges the <total>","path":["shopping_cart","checkout"],"tags":[],"duration_ms":1.000,"message":"no","output":"","phases":[]}
+{"type":"run_finished","passed":1,"failed":1,"ignored":0,"not_run":0,"duration_ms":2.000}
+"#;
+    let statuses = report_statuses(report);
+    assert_eq!(statuses.len(), 2);
+    assert_eq!(statuses["shopping_cart::starts_empty"], Status::Passed);
+
+    let document = markdown("Specification", &specs(), &statuses);
+    assert!(document.contains("- ✅ starts empty\n- rejects negative amounts\n"));
+    assert!(document.contains("- ❌ charges the <total>\n"));
+
+    let page = html("Cart & co", &specs(), &statuses);
+    assert!(page.contains("<title>Cart &amp; co</title>"));
+    assert!(page.contains("<h2>shopping cart</h2>\n<ul>\n<li>starts empty <span class=\"badge passed\">passed</span></li>\n"));
+    assert!(page.contains("<h3>checkout</h3>\n<ul>\n<li>charges the &lt;total&gt; <span class=\"badge failed\">failed</span></li>"));
+    assert!(page.contains("<h2>Pending</h2>\n<ul>\n<li>shopping cart › applies coupons</li>\n"));
+}
+
+#[test]
+fn parses_options() {
+    let options = DocsOptions::parse(vec!["--format".to_string(), "html".to_string(), "--report".to_string(),
+                                          "report.json".to_string(), "-o".to_string(), "spec.html".to_string()]).unwrap();
+    assert_eq!(options.format, DocsFormat::Html);
+    assert_eq!(options.report, Some("report.json".into()));
+    assert_eq!(options.output, Some("spec.html".into()));
+    assert_eq!(options.title, "Specification");
+    assert!(DocsOptions::parse(vec!["--format".to_string(), "pdf".to_string()]).is_err());
+}
+
+#[test]
+fn documents_the_specs_of_a_source_file() {
+    let dir = env::temp_dir().join(format!("cargo-stainless-docs-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let report = dir.join("report.json");
+    fs::write(&report, r#"{"type":"run_started","schema":1,"seed":null}
+{"type":"test_passed","description":"should run nested tests","path":["harness","nested"],"tags":[],"duration_ms":1.000,"output":"","phases":[]}
+{"type":"test_failed","description":"should run serial tests alone","path":["harness","serial"],"tags":[],"duration_ms":1.000,"message":"no","output":"","phases":[]}
+{"type":"run_finished","passed":1,"failed":1,"ignored":0,"not_run":0,"duration_ms":2.000}
+"#).unwrap();
+
+    // The harness mode tests of the plugin.
+    let options = DocsOptions {
+        files: vec![Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/harness.rs")],
+        format: DocsFormat::Markdown,
+        report: Some(report),
+        title: "Harness".to_string(),
+        output: Some(dir.join("spec.md")),
+        help: false
+    };
+    docs(&options).unwrap();
+    let document = fs::read_to_string(dir.join("spec.md")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(document, "\
+# Harness
+
+## harness
+
+- should run tests registered with the runtime
+- should handle failing tests
+- should not register configured out tests
+
+### nested
+
+- ✅ should run nested tests
+
+### serial
+
+- should run serial blocks one test at a time
+- should run tests of serial groups one at a time
+- ❌ should run serial tests alone
+
+### isolated
+
+- should run isolated tests in a process of their own
+- should combine isolated with other modifiers
+
+## listed by path
+
+- should run blocks listed by their path
+
+## Pending
+
+- harness › should handle ignored tests
+");
+}
@@ -0,0 +1,29 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+extern crate cargo_stainless;
+
+use cargo_stainless::{describe_path, ExpandOptions};
+
+// The expansions themselves are written by the plugin, see `tests/expand.rs` of the plugin.
+
+#[test]
+fn splits_paths() {
+    assert_eq!(describe_path("stack > empty").unwrap(), vec!["stack", "empty"]);
+    assert_eq!(describe_path("stack::empty").unwrap(), vec!["stack", "empty"]);
+    assert_eq!(describe_path("stack").unwrap(), vec!["stack"]);
+    assert!(describe_path("stack > ").is_err());
+}
+
+#[test]
+fn parses_options() {
+    let args = vec!["stack > empty", "--test", "spec"].into_iter().map(String::from);
+    let options = ExpandOptions::parse(args).unwrap();
+    assert_eq!(options.path, vec!["stack", "empty"]);
+    assert_eq!(options.cargo_args, vec!["--test", "spec"]);
+    assert!(ExpandOptions::parse(vec![]).is_err());
+    assert!(ExpandOptions::parse(vec!["--help".to_string()]).unwrap().help);
+}
@@ -0,0 +1,21 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+// A crate root whose `describe!` blocks are in modules, read by `tests/list.rs`.
+
+#![feature(plugin)]
+#![plugin(stainless(harness))]
+
+#[macro_use]
+extern crate stainless_runtime;
+
+mod specs;
+
+#[cfg(test)]
+#[path = "other/renamed.rs"]
+mod renamed;
+
+stainless_main!(specs::outer, specs::deeper::deeper, renamed::renamed);
@@ -0,0 +1,13 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+pub mod deeper;
+
+describe! outer {
+    it "is in a module" {
+        assert!(true);
+    }
+}
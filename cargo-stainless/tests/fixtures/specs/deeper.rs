@@ -0,0 +1,11 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+describe! deeper {
+    it "is in a nested module" serial {
+        assert!(true);
+    }
+}
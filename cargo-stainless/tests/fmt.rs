@@ -0,0 +1,131 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+extern crate cargo_stainless;
+
+use cargo_stainless::{format_source, FmtOptions};
+
+#[test]
+fn formats_bodies_and_headers() {
+    let source = r#"describe! stack {
+    before_each {
+      let mut stack:Vec<u32>=Vec::new();
+    }
+
+    it   "is empty"   { assert!(stack.is_empty()) ; }
+
+    failing ( "boom" ) "panics" tags( slow,db ) {
+        panic!("boom")
+    }
+
+    describe! nested {
+        bench "pushes"(b) { b.iter(|| stack.push(1)) }
+    }
+}
+"#;
+
+    assert_eq!(format_source(source, None).unwrap(), r#"describe! stack {
+    before_each {
+        let mut stack: Vec<u32> = Vec::new();
+    }
+
+    it "is empty" {
+        assert!(stack.is_empty());
+    }
+
+    failing("boom") "panics" tags(slow, db) {
+        panic!("boom")
+    }
+
+    describe! nested {
+        bench "pushes" (b) {
+            b.iter(|| stack.push(1))
+        }
+    }
+}
+"#);
+}
+
+#[test]
+fn formats_the_steps_of_scenarios() {
+    let source = r#"describe! stack {
+    scenario   "pushing"  tags( db ) {
+        given "a stack" { let mut stack=vec![]; }
+        when "pushing" { stack.push(1) ; }
+
+        then "it has one element" { assert_eq!(stack.len(),1) }
+        and "it is not empty" {}
+    }
+}
+"#;
+
+    assert_eq!(format_source(source, None).unwrap(), r#"describe! stack {
+    scenario "pushing" tags(db) {
+        given "a stack" {
+            let mut stack = vec![];
+        }
+        when "pushing" {
+            stack.push(1);
+        }
+
+        then "it has one element" {
+            assert_eq!(stack.len(), 1)
+        }
+        and "it is not empty" {}
+    }
+}
+"#);
+}
+
+#[test]
+fn keeps_items_comments_and_indentation() {
+    let source = r#"mod tests {
+    use std::fmt;
+
+    describe! items {
+        // Shared between the tests.
+        fn helper(x:u32)->u32{x+1}
+
+        /// The answer.
+        #[allow(unused)]
+        it "answers" {}
+    }
+}
+"#;
+
+    assert_eq!(format_source(source, None).unwrap(), r#"mod tests {
+    use std::fmt;
+
+    describe! items {
+        // Shared between the tests.
+        fn helper(x: u32) -> u32 {
+            x + 1
+        }
+
+        /// The answer.
+        #[allow(unused)]
+        it "answers" {}
+    }
+}
+"#);
+}
+
+#[test]
+fn is_idempotent() {
+    let source = "describe! a {\n    it \"b\" { let x=1; }\n}\n";
+    let formatted = format_source(source, None).unwrap();
+    assert_eq!(format_source(&formatted, None).unwrap(), formatted);
+}
+
+#[test]
+fn parses_options() {
+    let options = FmtOptions::parse(vec!["--check".to_string(), "--edition".to_string(), "2018".to_string(),
+                                         "tests/spec.rs".to_string()]).unwrap();
+    assert!(options.check);
+    assert_eq!(options.edition, Some("2018".to_string()));
+    assert_eq!(options.files, vec![std::path::PathBuf::from("tests/spec.rs")]);
+    assert!(FmtOptions::parse(vec!["--edition".to_string()]).is_err());
+}
@@ -0,0 +1,125 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+extern crate cargo_stainless;
+
+use std::path::{Path, PathBuf};
+
+use cargo_stainless::{parse_specs, print_json, print_text, Kind, ListFormat, ListOptions, Spec};
+
+fn spec(kind: Kind, name: &str, line: usize, tags: &[&str], modifiers: &[&str], children: Vec<Spec>) -> Spec {
+    Spec {
+        kind,
+        name: name.to_string(),
+        file: "tests/spec.rs".to_string(),
+        line,
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        modifiers: modifiers.iter().map(|modifier| modifier.to_string()).collect(),
+        children
+    }
+}
+
+fn specs() -> Vec<Spec> {
+    vec![spec(Kind::Describe, "arithmetic", 8, &[], &["serial(db)"], vec![
+        spec(Kind::Test, "adds", 9, &[], &[], vec![]),
+        spec(Kind::Test, "overflows", 13, &["slow"], &["failing(\"overflow\")"], vec![]),
+        spec(Kind::Describe, "nested", 17, &[], &[], vec![
+            spec(Kind::Bench, "multiplies", 18, &[], &[], vec![])
+        ])
+    ])]
+}
+
+#[test]
+fn prints_an_indented_tree() {
+    let mut out = vec![];
+    print_text(&specs(), &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+arithmetic (serial(db))                   tests/spec.rs:8
+  adds                                    tests/spec.rs:9
+  overflows [slow] (failing(\"overflow\"))  tests/spec.rs:13
+  nested                                  tests/spec.rs:17
+    multiplies (bench)                    tests/spec.rs:18
+");
+}
+
+#[test]
+fn prints_json() {
+    let mut out = vec![];
+    print_json(&specs()[0].children[2..], &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), concat!(
+        r#"[{"children":[{"children":[],"file":"tests/spec.rs","kind":"bench","line":18,"modifiers":[],"#,
+        r#""name":"multiplies","tags":[]}],"file":"tests/spec.rs","kind":"describe","line":17,"#,
+        r#""modifiers":[],"name":"nested","tags":[]}]"#, "\n"));
+}
+
+#[test]
+fn parses_options() {
+    let options = ListOptions::parse(vec!["--format".to_string(), "json".to_string(), "tests/spec.rs".to_string()]).unwrap();
+    assert_eq!(options.format, ListFormat::Json);
+    assert_eq!(options.files, vec![std::path::PathBuf::from("tests/spec.rs")]);
+    assert!(ListOptions::parse(vec!["--format".to_string(), "yaml".to_string()]).is_err());
+}
+
+fn text(specs: &[Spec]) -> String {
+    let mut out = vec![];
+    print_text(specs, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn reads_specs_from_the_source() {
+    // The specs of harness mode in the tests of the plugin.
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let specs = parse_specs(&root, &[root.join("tests/harness.rs")]).unwrap();
+    assert_eq!(text(&specs), "\
+harness [runtime]                                                      tests/harness.rs:13
+  should run tests registered with the runtime                         tests/harness.rs:22
+  should handle failing tests (failing(\"expected\"))                    tests/harness.rs:27
+  should handle ignored tests (ignore)                                 tests/harness.rs:31
+  should run benchmarks (bench)                                        tests/harness.rs:35
+  should not register configured out tests                             tests/harness.rs:40
+  nested                                                               tests/harness.rs:44
+    should run nested tests [nested]                                   tests/harness.rs:45
+  serial (serial)                                                      tests/harness.rs:50
+    should run serial blocks one test at a time                        tests/harness.rs:53
+    should run tests of serial groups one at a time [db] (serial(db))  tests/harness.rs:57
+    should run serial tests alone (serial)                             tests/harness.rs:61
+  isolated (isolated)                                                  tests/harness.rs:66
+    should run isolated tests in a process of their own                tests/harness.rs:69
+    should combine isolated with other modifiers (serial, isolated)    tests/harness.rs:73
+listed_by_path                                                         tests/harness.rs:80
+  should run blocks listed by their path                               tests/harness.rs:81
+");
+}
+
+#[test]
+fn follows_modules() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let specs = parse_specs(&root, &[root.join("tests/fixtures/modules.rs")]).unwrap();
+    assert_eq!(text(&specs), "\
+deeper                            tests/fixtures/specs/deeper.rs:7
+  is in a nested module (serial)  tests/fixtures/specs/deeper.rs:8
+outer                             tests/fixtures/specs.rs:9
+  is in a module                  tests/fixtures/specs.rs:10
+renamed                           tests/fixtures/other/renamed.rs:7
+  is in a module with a path      tests/fixtures/other/renamed.rs:8
+");
+}
+
+#[test]
+fn skips_invalid_blocks() {
+    let dir = std::env::temp_dir().join(format!("cargo-stainless-list-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("spec.rs");
+    std::fs::write(&file, "describe! invalid { it {} }\ndescribe! valid { ignore \"runs\" {} }\n").unwrap();
+
+    let specs = parse_specs(&dir, &[file]).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(text(&specs), "\
+valid            spec.rs:2
+  runs (ignore)  spec.rs:2
+");
+}
@@ -0,0 +1,171 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+extern crate cargo_stainless;
+
+use cargo_stainless::{migrate_source, MigrateOptions};
+
+const SOURCE: &str = r#"#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn helper() -> u32 { 2 }
+
+    /// Adding works.
+    #[test]
+    fn adds_two_numbers() {
+        let x = helper();
+        assert_eq!(x + 3, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "overflow")]
+    fn overflows() {
+        let x = helper();
+        x.checked_add(u32::MAX).expect("overflow");
+    }
+
+    #[test]
+    #[ignore]
+    fn is_slow() {
+        let x = helper();
+    }
+
+    #[test]
+    fn returns_a_result() -> Result<(), String> { Ok(()) }
+
+    mod nested {
+        #[test] fn works() { let x = helper(); assert!(x > 0) }
+    }
+}
+
+pub mod public {
+    #[test]
+    fn is_kept() {}
+}
+"#;
+
+#[test]
+fn converts_test_modules() {
+    assert_eq!(migrate_source(SOURCE, false).unwrap(), r#"#[cfg(test)]
+describe! tests {
+    fn helper() -> u32 { 2 }
+
+    /// Adding works.
+    it "adds two numbers" {
+        let x = helper();
+        assert_eq!(x + 3, 5);
+    }
+
+    failing("overflow") "overflows" {
+        let x = helper();
+        x.checked_add(u32::MAX).expect("overflow");
+    }
+
+    ignore "is slow" {
+        let x = helper();
+    }
+
+    #[test]
+    fn returns_a_result() -> Result<(), String> { Ok(()) }
+
+    describe! nested {
+        it "works" { let x = helper(); assert!(x > 0) }
+    }
+}
+
+pub mod public {
+    #[test]
+    fn is_kept() {}
+}
+"#);
+}
+
+#[test]
+fn hoists_common_statements() {
+    let source = SOURCE.replace("let x = helper();\n    }", "let x = helper();\n        sleep(x);\n    }");
+    assert_eq!(migrate_source(&source, true).unwrap(), r#"#[cfg(test)]
+describe! tests {
+    fn helper() -> u32 { 2 }
+
+    before_each {
+        let x = helper();
+    }
+
+    /// Adding works.
+    it "adds two numbers" {
+        assert_eq!(x + 3, 5);
+    }
+
+    failing("overflow") "overflows" {
+        x.checked_add(u32::MAX).expect("overflow");
+    }
+
+    ignore "is slow" {
+        sleep(x);
+    }
+
+    #[test]
+    fn returns_a_result() -> Result<(), String> { Ok(()) }
+
+    describe! nested {
+        it "works" { assert!(x > 0) }
+    }
+}
+
+pub mod public {
+    #[test]
+    fn is_kept() {}
+}
+"#);
+}
+
+#[test]
+fn keeps_statements_which_are_a_whole_test() {
+    // `is_slow` only consists of the common statement, so nothing is hoisted.
+    assert_eq!(migrate_source(SOURCE, true).unwrap(), migrate_source(SOURCE, false).unwrap());
+}
+
+#[test]
+fn keeps_attributes_which_have_no_modifier() {
+    let source = r#"mod tests {
+    #[test]
+    #[ignore]
+    #[should_panic]
+    fn both() {}
+
+    #[test]
+    #[ignore = "flaky"]
+    #[cfg(unix)]
+    fn with_reason() {}
+}
+"#;
+
+    assert_eq!(migrate_source(source, false).unwrap(), r#"describe! tests {
+    #[ignore]
+    failing "both" {}
+
+    #[ignore = "flaky"]
+    #[cfg(unix)]
+    it "with reason" {}
+}
+"#);
+}
+
+#[test]
+fn leaves_modules_without_tests() {
+    let source = "mod util {\n    fn helper() {}\n}\n";
+    assert_eq!(migrate_source(source, true).unwrap(), source);
+}
+
+#[test]
+fn parses_options() {
+    let options = MigrateOptions::parse(vec!["--hoist".to_string(), "src/lib.rs".to_string()]).unwrap();
+    assert!(options.hoist);
+    assert!(!options.dry_run);
+    assert_eq!(options.files, vec![std::path::PathBuf::from("src/lib.rs")]);
+    assert!(MigrateOptions::parse(vec!["--all".to_string()]).is_err());
+}
@@ -0,0 +1,113 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+extern crate cargo_stainless;
+
+use std::fs;
+use std::path::PathBuf;
+
+use cargo_stainless::{affected_tests, failed_tests, libtest_failures, spec_names, Selection, WatchOptions};
+
+#[test]
+fn finds_describe_blocks() {
+    let source = r#"
+        describe! stainless {
+            it "works" {}
+
+            describe!   nested_block {}
+        }
+
+        describe!stainless {}
+    "#;
+    assert_eq!(spec_names(source), vec!["stainless", "nested_block"]);
+    assert!(spec_names("fn main() {}").is_empty());
+}
+
+#[test]
+fn narrows_runs_to_changed_specs() {
+    let root = std::env::temp_dir().join(format!("stainless-watch-test-{}", std::process::id()));
+    fs::create_dir_all(root.join("tests/specs")).unwrap();
+    fs::create_dir_all(root.join("src")).unwrap();
+    let harness = "#![feature(plugin)]\n#![plugin(stainless(harness))]\n";
+    let spec = root.join("tests/spec.rs");
+    let nested = root.join("tests/specs/nested.rs");
+    let other = root.join("tests/other.rs");
+    let plain = root.join("tests/plain.rs");
+    let code = root.join("src/lib.rs");
+    fs::write(&spec, format!("{}mod specs {{ mod nested; }}\ndescribe! addition {{ describe! carry {{}} }}", harness)).unwrap();
+    fs::write(&nested, "describe! negative {}").unwrap();
+    fs::write(&other, format!("{}describe! subtraction {{}}", harness)).unwrap();
+    fs::write(&plain, "#![feature(plugin)]\n#![plugin(stainless)]\ndescribe! division {}").unwrap();
+    fs::write(&code, "pub fn add() {}").unwrap();
+
+    let selection = |changed: &[&PathBuf]| {
+        affected_tests(&root, &changed.iter().map(|path| path.to_path_buf()).collect::<Vec<_>>())
+    };
+    assert_eq!(selection(&[&spec, &other]), Selection {
+        tests: vec!["other".to_string(), "spec".to_string()],
+        filters: vec!["** > addition".to_string(), "** > carry".to_string(), "** > subtraction".to_string()]
+    });
+    // Files of modules select the target of their crate.
+    assert_eq!(selection(&[&nested]), Selection {
+        tests: vec!["spec".to_string()],
+        filters: vec!["** > negative".to_string()]
+    });
+    // Changes to code without specs, deleted files, or specs run by libtest may affect any test.
+    assert_eq!(selection(&[&spec, &code]), Selection::default());
+    assert_eq!(selection(&[&root.join("tests/deleted.rs")]), Selection::default());
+    assert_eq!(selection(&[&plain]), Selection::default());
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn reads_failed_tests_from_the_json_report() {
+    let report = r#"{"type":"run_started","schema":1,"seed":null}
+{"type":"test_passed","description":"adds","path":["arithmetic"],"tags":[],"duration_ms":1.000,"output":"","phases":[]}
+{"type":"test_failed","description":"carries the one","path":["arithmetic","nested"],"tags":[],"duration_ms":1.000,"message":"no","output":"","phases":[]}
+{"type":"run_finished","passed":1,"failed":1,"ignored":0,"not_run":0,"duration_ms":2.000}
+"#;
+    assert_eq!(failed_tests(report), vec!["arithmetic::nested::carries_the_one"]);
+}
+
+#[test]
+fn reads_failed_tests_from_the_test_harness_output() {
+    let output = "
+running 3 tests
+test arithmetic::adds ... ok
+test arithmetic::nested::carries_the_one ... FAILED
+test arithmetic::nested::borrows ... FAILED
+
+failures:
+
+---- arithmetic::nested::carries_the_one stdout ----
+\tthread 'arithmetic::nested::carries_the_one' panicked at 'no', tests/spec.rs:12:8
+
+failures:
+    arithmetic::nested::borrows
+    arithmetic::nested::carries_the_one
+
+test result: FAILED. 1 passed; 2 failed; 0 ignored; 0 measured; 0 filtered out
+
+arithmetic
+  \u{2717} carries the one
+
+failures:
+
+  1) arithmetic > carries the one
+     no
+";
+    assert_eq!(libtest_failures(output), vec!["arithmetic::nested::borrows", "arithmetic::nested::carries_the_one"]);
+}
+
+#[test]
+fn passes_other_arguments_on() {
+    let args = ["--all", "--test", "spec", "--", "--tags", "fast"];
+    let options = WatchOptions::parse(args.iter().map(|arg| arg.to_string())).unwrap();
+    assert!(options.all);
+    assert_eq!(options.cargo_args, vec!["--test", "spec"]);
+    assert_eq!(options.test_args, vec!["--tags", "fast"]);
+}
@@ -0,0 +1,498 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+#![deny(missing_docs)]
+
+//! The grammar of `describe!` blocks, shared by the `stainless` plugin and `cargo stainless`.
+//!
+//! The plugin reads `describe!` blocks from the tokens the compiler passes to the macro, and
+//! `cargo stainless` reads them from the source files of a crate without compiling it. Both
+//! implement `Tokens` for their tokens and read the blocks with `parse_describe`, so that they
+//! agree on the blocks, modifiers, inner attributes and lints of stainless.
+//!
+//! The crate is compiled with the nightly compiler of the plugin and has no dependencies.
+
+/// `empty_test`: a test without statements.
+pub const EMPTY_TEST: &str = "empty_test";
+/// `missing_assertion`: a test, including its hooks, without an assertion.
+pub const MISSING_ASSERTION: &str = "missing_assertion";
+/// `hook_without_tests`: `before_each` or `after_each` in a block without tests.
+pub const HOOK_WITHOUT_TESTS: &str = "hook_without_tests";
+/// `unused_before_each`: a binding of `before_each` which no test uses.
+pub const UNUSED_BEFORE_EACH: &str = "unused_before_each";
+/// `failing_without_panic`: a `failing` test which can not panic.
+pub const FAILING_WITHOUT_PANIC: &str = "failing_without_panic";
+/// `non_sentence_description`: a description which does not read as a sentence.
+pub const NON_SENTENCE_DESCRIPTION: &str = "non_sentence_description";
+
+/// The lints of stainless, which are allowed with `#![allow(...)]` on a `describe!` block.
+pub const LINTS: &[&str] = &[
+    EMPTY_TEST, MISSING_ASSERTION, HOOK_WITHOUT_TESTS, UNUSED_BEFORE_EACH, FAILING_WITHOUT_PANIC,
+    NON_SENTENCE_DESCRIPTION
+];
+
+const BEFORE_EACH: &str = "before_each";
+const GIVEN:       &str = "given";
+const AFTER_EACH:  &str = "after_each";
+const THEN:        &str = "then";
+const IT:          &str = "it";
+const IGNORE:      &str = "ignore";
+const WHEN:        &str = "when";
+const DESCRIBE:    &str = "describe";
+const FAILING:     &str = "failing";
+const BENCH:       &str = "bench";
+const SCENARIO:    &str = "scenario";
+const AND:         &str = "and";
+const BUT:         &str = "but";
+
+const NO_SUPER_GLOB: &str = "no_super_glob";
+const TAGS:          &str = "tags";
+const SERIAL:        &str = "serial";
+const ISOLATED:      &str = "isolated";
+const ALLOW:         &str = "allow";
+
+/// The names of the blocks of a `describe!` block. Anything else is an item, except for a
+/// nested `describe!`.
+pub const BLOCKS: &[&str] = &[BEFORE_EACH, GIVEN, AFTER_EACH, THEN, IT, WHEN, IGNORE, FAILING, BENCH, SCENARIO];
+
+/// The keywords of the steps of a `scenario`.
+pub const STEPS: &[&str] = &[GIVEN, WHEN, THEN, AND, BUT];
+
+/// The punctuation the grammar looks for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Punct {
+    /// `!`
+    Not,
+    /// `,`
+    Comma,
+    /// `(`
+    OpenParen,
+    /// `)`
+    CloseParen,
+    /// `{`
+    OpenBrace,
+    /// `}`
+    CloseBrace
+}
+
+/// The name of an attribute and the names in its list, if it has one. Items of the list which
+/// are not a plain name are `None`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Meta {
+    /// The name, like `serial` in `#![serial(db)]`.
+    pub name: String,
+    /// The list, like `[Some("db")]` in `#![serial(db)]`.
+    pub list: Option<Vec<Option<String>>>
+}
+
+/// Tokens the grammar reads `describe!` blocks from, with a cursor at the current token.
+///
+/// Blocks, attributes and items are read by the implementation, so that the plugin can use the
+/// parser of the compiler for them.
+pub trait Tokens {
+    /// A location, of the current token.
+    type Span: Copy;
+    /// An identifier.
+    type Ident: Clone;
+    /// An inner or outer attribute.
+    type Attribute;
+    /// A block in braces.
+    type Block;
+    /// An ordinary item, like `fn` or `use`.
+    type Item;
+    /// An error, at the current token.
+    type Error;
+
+    /// The location of the current token.
+    fn span(&self) -> Self::Span;
+
+    /// The identifier `ahead` tokens after the current one, if it is an identifier.
+    fn ident(&self, ahead: usize) -> Option<String>;
+
+    /// Whether the token `ahead` tokens after the current one is `punct`.
+    fn is(&self, ahead: usize, punct: Punct) -> bool;
+
+    /// Whether there are no more tokens.
+    fn at_end(&self) -> bool;
+
+    /// Move past the current token.
+    fn bump(&mut self);
+
+    /// Read an identifier.
+    fn parse_ident(&mut self) -> Result<Self::Ident, Self::Error>;
+
+    /// The name of an identifier.
+    fn name(&self, ident: &Self::Ident) -> String;
+
+    /// Read a string literal.
+    fn parse_str(&mut self) -> Result<String, Self::Error>;
+
+    /// Read a block in braces.
+    fn parse_block(&mut self) -> Result<Self::Block, Self::Error>;
+
+    /// Read any number of inner attributes, `#![...]`.
+    fn parse_inner_attributes(&mut self) -> Result<Vec<Self::Attribute>, Self::Error>;
+
+    /// Read any number of outer attributes and doc comments.
+    fn parse_outer_attributes(&mut self) -> Result<Vec<Self::Attribute>, Self::Error>;
+
+    /// Read an ordinary item, written after `attrs`.
+    fn parse_item(&mut self, attrs: Vec<Self::Attribute>) -> Result<Self::Item, Self::Error>;
+
+    /// The name and list of an attribute.
+    fn meta(&self, attr: &Self::Attribute) -> Meta;
+
+    /// The attribute without the names in `names` in its list, or `None` if the list is empty
+    /// then.
+    fn without(&self, attr: Self::Attribute, names: &[&str]) -> Option<Self::Attribute>;
+
+    /// An error at the current token.
+    fn error(&self, message: &str) -> Self::Error;
+}
+
+/// A `describe!` block.
+pub struct Describe<T: Tokens> {
+    /// The name of the block.
+    pub name: T::Ident,
+    /// The location of `describe!` for top-level blocks, and of the name for nested blocks.
+    pub span: T::Span,
+    /// The outer attributes of a nested block, followed by the inner attributes which are not
+    /// read by stainless.
+    pub attrs: Vec<T::Attribute>,
+    /// Unless `#![no_super_glob]` is given.
+    pub super_glob: bool,
+    /// `#![tags(...)]`.
+    pub tags: Vec<String>,
+    /// `#![serial]` (`Some(None)`) or `#![serial(group)]` (`Some(Some(group))`).
+    pub serial: Option<Option<String>>,
+    /// `#![isolated]`.
+    pub isolated: bool,
+    /// The lints of stainless allowed with `#![allow(...)]`.
+    pub allowed_lints: Vec<String>,
+    /// The `before_each` and `given` blocks, in the order they are written.
+    pub before_each: Vec<T::Block>,
+    /// The `after_each` and `then` blocks, in the order they are written.
+    pub after_each: Vec<T::Block>,
+    /// Everything else, in the order it is written.
+    pub children: Vec<Child<T>>
+}
+
+/// A test, benchmark, nested block or item of a `describe!` block.
+pub enum Child<T: Tokens> {
+    /// An `it`, `when`, `failing`, `ignore` or `scenario` test.
+    Test(Test<T>),
+    /// A `bench` block.
+    Bench(Bench<T>),
+    /// A nested `describe!` block.
+    Describe(Describe<T>),
+    /// An ordinary item.
+    Item(T::Item)
+}
+
+/// A test.
+pub struct Test<T: Tokens> {
+    /// `it`, `when`, `failing`, `ignore` or `scenario`.
+    pub keyword: String,
+    /// The description.
+    pub description: String,
+    /// The location of the description.
+    pub span: T::Span,
+    /// `failing` (`Some(None)`) or `failing("message")` (`Some(Some(message))`).
+    pub failing: Option<Option<String>>,
+    /// `tags(...)`.
+    pub tags: Vec<String>,
+    /// `serial` (`Some(None)`) or `serial(group)` (`Some(Some(group))`).
+    pub serial: Option<Option<String>>,
+    /// `isolated`.
+    pub isolated: bool,
+    /// The outer attributes.
+    pub attrs: Vec<T::Attribute>,
+    /// The statements.
+    pub body: Body<T>
+}
+
+impl<T: Tokens> Test<T> {
+    /// Whether the test is written with `ignore`.
+    pub fn ignored(&self) -> bool {
+        self.keyword == IGNORE
+    }
+}
+
+/// The statements of a test.
+pub enum Body<T: Tokens> {
+    /// The block of an ordinary test.
+    Block(T::Block),
+    /// The steps of a `scenario`.
+    Scenario(Vec<Step<T>>)
+}
+
+/// A `given`, `when`, `then`, `and` or `but` step of a `scenario`.
+pub struct Step<T: Tokens> {
+    /// The keyword.
+    pub keyword: String,
+    /// The text after the keyword.
+    pub text: String,
+    /// The location of the keyword.
+    pub span: T::Span,
+    /// The statements.
+    pub block: T::Block
+}
+
+/// A `bench` block.
+pub struct Bench<T: Tokens> {
+    /// The description.
+    pub description: String,
+    /// The location of the description.
+    pub span: T::Span,
+    /// The name of the `Bencher` argument.
+    pub bencher: T::Ident,
+    /// The outer attributes.
+    pub attrs: Vec<T::Attribute>,
+    /// The statements.
+    pub block: T::Block
+}
+
+/// Read the body of the `describe!` block `name` at `span`, up to the closing brace or the end
+/// of the tokens, which are left for the caller.
+pub fn parse_describe<T: Tokens>(tokens: &mut T, name: T::Ident, span: T::Span) -> Result<Describe<T>, T::Error> {
+    let mut describe = Describe {
+        name,
+        span,
+        attrs: vec![],
+        super_glob: true,
+        tags: vec![],
+        serial: None,
+        isolated: false,
+        allowed_lints: vec![],
+        before_each: vec![],
+        after_each: vec![],
+        children: vec![]
+    };
+
+    // Inner attributes configure the describe block itself, e.g. `#![no_super_glob]`.
+    for attr in tokens.parse_inner_attributes()? {
+        let meta = tokens.meta(&attr);
+        match &*meta.name {
+            NO_SUPER_GLOB => describe.super_glob = false,
+            TAGS => {
+                for tag in meta.list.unwrap_or_default() {
+                    match tag {
+                        Some(tag) => describe.tags.push(tag),
+                        None => return Err(tokens.error("expected a tag name in `#![tags(...)]`"))
+                    }
+                }
+            },
+            SERIAL => {
+                describe.serial = match meta.list {
+                    None => Some(None),
+                    Some(ref groups) if groups.len() == 1 && groups[0].is_some() => Some(groups[0].clone()),
+                    Some(_) => return Err(tokens.error("expected `#![serial]` or `#![serial(group)]`"))
+                };
+            },
+            ISOLATED => describe.isolated = true,
+            ALLOW => {
+                // The lints of stainless are taken out, the compiler would not know them.
+                let mut lints = vec![];
+                for lint in meta.list.unwrap_or_default() {
+                    if let Some(lint) = lint {
+                        if LINTS.contains(&&*lint) {
+                            lints.push(lint);
+                        }
+                    }
+                }
+                if lints.is_empty() {
+                    describe.attrs.push(attr);
+                } else {
+                    describe.allowed_lints.extend(lints);
+                    describe.attrs.extend(tokens.without(attr, LINTS));
+                }
+            },
+            _ => describe.attrs.push(attr)
+        }
+    }
+
+    // Now parse all tests, subsections and items.
+    while !tokens.at_end() && !tokens.is(0, Punct::CloseBrace) {
+        // Outer attributes and doc comments belong to the following block or item.
+        let attrs = tokens.parse_outer_attributes()?;
+
+        // Anything that does not start with one of the block names is an ordinary item (`use`,
+        // `fn`, `struct`, ...) which is emitted as-is into the generated module.
+        let keyword = match tokens.ident(0) {
+            Some(ref keyword) if is_block_name(tokens, keyword) => keyword.clone(),
+            _ => {
+                let item = tokens.parse_item(attrs)?;
+                describe.children.push(Child::Item(item));
+                continue;
+            }
+        };
+        tokens.bump();
+
+        let child = match &*keyword {
+            BEFORE_EACH | GIVEN | AFTER_EACH | THEN => {
+                // Hooks are spliced into tests, so there is nothing to put attributes on.
+                if !attrs.is_empty() {
+                    return Err(tokens.error(&format!("attributes are not allowed on `{}` blocks", keyword)));
+                }
+                let block = tokens.parse_block()?;
+                if keyword == BEFORE_EACH || keyword == GIVEN {
+                    describe.before_each.push(block);
+                } else {
+                    describe.after_each.push(block);
+                }
+                continue;
+            },
+
+            // Regular `#[test]`, `#[should_panic]` with an optional message, and `#[ignore]`.
+            IT | WHEN | FAILING | IGNORE => {
+                let failing = if keyword == FAILING { Some(parse_failing_message(tokens)?) } else { None };
+                let (description, span) = parse_description(tokens)?;
+                let Modifiers { tags, serial, isolated } = parse_modifiers(tokens)?;
+                let body = Body::Block(tokens.parse_block()?);
+                Child::Test(Test { keyword, description, span, failing, tags, serial, isolated, attrs, body })
+            },
+
+            // A test made of `given`, `when`, `then`, `and` and `but` steps.
+            SCENARIO => {
+                let (description, span) = parse_description(tokens)?;
+                let Modifiers { tags, serial, isolated } = parse_modifiers(tokens)?;
+                let body = Body::Scenario(parse_steps(tokens)?);
+                Child::Test(Test { keyword, description, span, failing: None, tags, serial, isolated, attrs, body })
+            },
+
+            // `#[bench]` benchmark.
+            BENCH => {
+                let (description, span) = parse_description(tokens)?;
+                expect(tokens, Punct::OpenParen, "`(bencher)` after the description of a benchmark")?;
+                let bencher = tokens.parse_ident()?;
+                expect(tokens, Punct::CloseParen, "`)` after the name of the bencher")?;
+                let block = tokens.parse_block()?;
+                Child::Bench(Bench { description, span, bencher, attrs, block })
+            },
+
+            // Nested `describe!` block.
+            _ => {
+                expect(tokens, Punct::Not, "`!` after `describe`")?;
+                let span = tokens.span();
+                let name = tokens.parse_ident()?;
+                expect(tokens, Punct::OpenBrace, "`{` after the name of a `describe!` block")?;
+                let mut nested = parse_describe(tokens, name, span)?;
+                expect(tokens, Punct::CloseBrace, "`}` to close `describe!`")?;
+                nested.attrs = attrs.into_iter().chain(nested.attrs).collect();
+                Child::Describe(nested)
+            }
+        };
+        describe.children.push(child);
+    }
+
+    Ok(describe)
+}
+
+// Whether `name` at the current token starts one of the stainless blocks rather than an item.
+fn is_block_name<T: Tokens>(tokens: &T, name: &str) -> bool {
+    // `describe` is only a block when used as `describe!`.
+    BLOCKS.contains(&name) || (name == DESCRIBE && tokens.is(1, Punct::Not))
+}
+
+// Reads the optional `("message")` after `failing`.
+fn parse_failing_message<T: Tokens>(tokens: &mut T) -> Result<Option<String>, T::Error> {
+    if !tokens.is(0, Punct::OpenParen) {
+        return Ok(None);
+    }
+    tokens.bump();
+    let message = tokens.parse_str()?;
+    expect(tokens, Punct::CloseParen, "`)` to close the message of `failing`")?;
+    Ok(Some(message))
+}
+
+// Reads the description of a test or benchmark, and where it is.
+fn parse_description<T: Tokens>(tokens: &mut T) -> Result<(String, T::Span), T::Error> {
+    let span = tokens.span();
+    let description = tokens.parse_str()?;
+    Ok((description, span))
+}
+
+// `tags(...)`, `serial` or `serial(group)` and `isolated` of a test.
+#[derive(Default)]
+struct Modifiers {
+    tags: Vec<String>,
+    serial: Option<Option<String>>,
+    isolated: bool
+}
+
+// Reads `tags(...)`, `serial` or `serial(group)` and `isolated` in any order.
+fn parse_modifiers<T: Tokens>(tokens: &mut T) -> Result<Modifiers, T::Error> {
+    let mut modifiers = Modifiers::default();
+    loop {
+        match tokens.ident(0) {
+            Some(ref name) if name == TAGS => {
+                tokens.bump();
+                expect(tokens, Punct::OpenParen, "`(` after `tags`")?;
+                while !tokens.is(0, Punct::CloseParen) {
+                    let tag = tokens.parse_ident()?;
+                    modifiers.tags.push(tokens.name(&tag));
+                    if !tokens.is(0, Punct::Comma) {
+                        break;
+                    }
+                    tokens.bump();
+                }
+                expect(tokens, Punct::CloseParen, "`)` to close `tags(...)`")?;
+            },
+            Some(ref name) if name == SERIAL => {
+                tokens.bump();
+                modifiers.serial = Some(None);
+                if tokens.is(0, Punct::OpenParen) {
+                    tokens.bump();
+                    let group = tokens.parse_ident()?;
+                    modifiers.serial = Some(Some(tokens.name(&group)));
+                    expect(tokens, Punct::CloseParen, "`)` to close `serial(...)`")?;
+                }
+            },
+            Some(ref name) if name == ISOLATED => {
+                tokens.bump();
+                modifiers.isolated = true;
+            },
+            _ => return Ok(modifiers)
+        }
+    }
+}
+
+// Reads the steps of a scenario, `{ given "..." { ... } when "..." { ... } ... }`.
+fn parse_steps<T: Tokens>(tokens: &mut T) -> Result<Vec<Step<T>>, T::Error> {
+    expect(tokens, Punct::OpenBrace, "`{` after the description of a scenario")?;
+
+    let mut steps = vec![];
+    while !tokens.at_end() && !tokens.is(0, Punct::CloseBrace) {
+        let span = tokens.span();
+        let keyword = match tokens.ident(0) {
+            Some(keyword) => keyword,
+            None => return Err(tokens.error("expected `given`, `when`, `then`, `and` or `but` in a scenario"))
+        };
+        if !STEPS.contains(&&*keyword) {
+            return Err(tokens.error(&format!(
+                "expected `given`, `when`, `then`, `and` or `but` in a scenario, but found `{}`", keyword)));
+        }
+        tokens.bump();
+        let text = tokens.parse_str()?;
+        let block = tokens.parse_block()?;
+        steps.push(Step { keyword, text, span, block });
+    }
+
+    if steps.is_empty() {
+        return Err(tokens.error("expected at least one step in a scenario"));
+    }
+    expect(tokens, Punct::CloseBrace, "`}` to close the scenario")?;
+    Ok(steps)
+}
+
+// Moves past `punct`, or fails with what was expected.
+fn expect<T: Tokens>(tokens: &mut T, punct: Punct, expected: &str) -> Result<(), T::Error> {
+    if !tokens.is(0, punct) {
+        return Err(tokens.error(&format!("expected {}", expected)));
+    }
+    tokens.bump();
+    Ok(())
+}
@@ -0,0 +1,88 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::mem;
+use std::ptr;
+use std::time::{Duration, Instant};
+
+const SAMPLES: usize = 50;
+const SAMPLE_TIME: u64 = 20;
+
+/// Passed to `bench` blocks in harness mode, like `test::Bencher` with libtest.
+pub struct Bencher {
+    measure: bool,
+    result: Option<BenchResult>
+}
+
+/// The measurement of a benchmark.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BenchResult {
+    /// Median time of one iteration in nanoseconds.
+    pub ns_per_iter: u64,
+    /// Difference between the fastest and the slowest sample in nanoseconds.
+    pub variance: u64
+}
+
+impl Bencher {
+    /// A bencher which measures when `measure` is set, and only runs the closure once otherwise
+    /// (as done for benchmarks during `cargo test`).
+    pub fn new(measure: bool) -> Bencher {
+        Bencher { measure, result: None }
+    }
+
+    /// Run `f` repeatedly and record how long one iteration takes.
+    pub fn iter<T, F: FnMut() -> T>(&mut self, mut f: F) {
+        if !self.measure {
+            black_box(f());
+            return;
+        }
+
+        // Find an iteration count which takes roughly SAMPLE_TIME milliseconds.
+        let target = Duration::from_millis(SAMPLE_TIME);
+        let mut iterations = 1u64;
+        loop {
+            let elapsed = time(iterations, &mut f);
+            if elapsed >= target || iterations >= 1 << 30 { break }
+            iterations *= 2;
+        }
+
+        let mut samples: Vec<u64> = (0..SAMPLES)
+            .map(|_| nanos(time(iterations, &mut f)) / iterations)
+            .collect();
+        samples.sort();
+
+        self.result = Some(BenchResult {
+            ns_per_iter: samples[SAMPLES / 2],
+            variance: samples[SAMPLES - 1] - samples[0]
+        });
+    }
+
+    /// The measurement of the last call to `iter`, if any.
+    pub fn result(&self) -> Option<BenchResult> {
+        self.result
+    }
+}
+
+fn time<T, F: FnMut() -> T>(iterations: u64, f: &mut F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(f());
+    }
+    start.elapsed()
+}
+
+// Keeps the optimizer from removing the computation of `value`.
+fn black_box<T>(value: T) -> T {
+    unsafe {
+        let copy = ptr::read_volatile(&value);
+        mem::forget(value);
+        copy
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000_000 + u64::from(duration.subsec_nanos())
+}
@@ -0,0 +1,42 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Capturing what a test prints, like libtest does.
+//!
+//! The standard library only exposes output capturing on nightly, so it requires the
+//! `nightly` feature. Without it tests print directly and no output is captured.
+
+#[cfg(feature = "nightly")]
+pub fn capture<R, F: FnOnce() -> R>(f: F) -> (R, String) {
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+
+    struct Sink(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Sink {
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let buffer = Arc::new(Mutex::new(Vec::new()));
+    let previous = io::set_print(Some(Box::new(Sink(buffer.clone()))));
+    let result = f();
+    io::set_print(previous);
+
+    let output = String::from_utf8_lossy(&buffer.lock().unwrap()).into_owned();
+    (result, output)
+}
+
+#[cfg(not(feature = "nightly"))]
+pub fn capture<R, F: FnOnce() -> R>(f: F) -> (R, String) {
+    (f(), String::new())
+}
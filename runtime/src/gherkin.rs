@@ -0,0 +1,339 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::any::Any;
+use std::fmt::{self, Debug};
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+
+use regex::Regex;
+
+/// A scenario of a `.feature` file, run as one test. `feature!` parses the file while compiling
+/// and builds the scenarios of the generated tests with `Scenario::new` and `Step::new`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scenario {
+    /// The name following `Scenario:`, with the values of the example filled in for outlines.
+    pub name: String,
+    /// The line of `Scenario:`, or of the example row for outlines.
+    pub line: usize,
+    /// The steps, starting with the steps of the `Background:` blocks.
+    pub steps: Vec<Step>
+}
+
+impl Scenario {
+    /// Create a scenario without steps.
+    pub fn new(name: &str, line: usize) -> Scenario {
+        Scenario { name: name.to_string(), line, steps: vec![] }
+    }
+
+    /// Add a step.
+    pub fn step(mut self, step: Step) -> Scenario {
+        self.steps.push(step);
+        self
+    }
+}
+
+/// The keyword of a step, with `And`, `But` and `*` resolved to the keyword they continue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepKind {
+    /// A `Given` step, which sets up the scenario.
+    Given,
+    /// A `When` step, which performs the action.
+    When,
+    /// A `Then` step, which checks the outcome.
+    Then
+}
+
+impl fmt::Display for StepKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            StepKind::Given => "given",
+            StepKind::When => "when",
+            StepKind::Then => "then"
+        })
+    }
+}
+
+/// A step of a scenario.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    /// The keyword as written, like `Given` or `And`.
+    pub keyword: String,
+    /// What the keyword stands for.
+    pub kind: StepKind,
+    /// The text following the keyword.
+    pub text: String,
+    /// The line of the step.
+    pub line: usize,
+    /// The rows of the data table following the step, if any.
+    pub table: Vec<Vec<String>>,
+    /// The doc string following the step, if any.
+    pub docstring: Option<String>
+}
+
+impl Step {
+    /// Create a step without a data table or doc string.
+    pub fn new(kind: StepKind, keyword: &str, text: &str, line: usize) -> Step {
+        Step { keyword: keyword.to_string(), kind, text: text.to_string(), line, table: vec![], docstring: None }
+    }
+
+    /// Add a row to the data table.
+    pub fn row(mut self, cells: &[&str]) -> Step {
+        self.table.push(cells.iter().map(|cell| cell.to_string()).collect());
+        self
+    }
+
+    /// Set the doc string.
+    pub fn docstring(mut self, docstring: &str) -> Step {
+        self.docstring = Some(docstring.to_string());
+        self
+    }
+}
+
+/// The step definitions of `feature!`, which run the steps of a scenario against a world of
+/// type `W`. Every scenario starts with `W::default()`.
+///
+/// Steps are matched by their text, whichever keyword they are written with. A pattern starting
+/// with `^` or ending with `$` is a regular expression, whose groups are the arguments of the
+/// step. Any other pattern is an expression matching the whole text, where `{int}`, `{float}`,
+/// `{word}`, `{string}` (in double quotes) and `{}` (anything) are arguments, `(s)` is optional
+/// text, and `\` escapes the next character.
+///
+/// ``` rust
+/// use stainless_runtime::Steps;
+///
+/// #[derive(Default)]
+/// struct Basket { cucumbers: i32 }
+///
+/// fn steps() -> Steps<Basket> {
+///     Steps::<Basket>::new()
+///         .given("there are {int} cucumbers", |basket, args| basket.cucumbers = args.get(0))
+///         .when(r"^I eat (\d+) cucumbers?$", |basket, args| basket.cucumbers -= args.get::<i32>(0))
+///         .then("I should have {int} cucumber(s)", |basket, args| {
+///             assert_eq!(basket.cucumbers, args.get::<i32>(0))
+///         })
+/// }
+/// # steps();
+/// ```
+pub struct Steps<W> {
+    definitions: Vec<Definition<W>>
+}
+
+struct Definition<W> {
+    pattern: String,
+    regex: Regex,
+    run: StepFn<W>
+}
+
+type StepFn<W> = Box<Fn(&mut W, &StepArgs)>;
+
+impl<W> Default for Steps<W> {
+    fn default() -> Steps<W> {
+        Steps { definitions: vec![] }
+    }
+}
+
+impl<W> Steps<W> {
+    /// No step definitions.
+    pub fn new() -> Steps<W> {
+        Steps::default()
+    }
+
+    /// Define a step, usually a `Given` step.
+    pub fn given<F>(self, pattern: &str, run: F) -> Steps<W> where F: Fn(&mut W, &StepArgs) + 'static {
+        self.step(pattern, run)
+    }
+
+    /// Define a step, usually a `When` step.
+    pub fn when<F>(self, pattern: &str, run: F) -> Steps<W> where F: Fn(&mut W, &StepArgs) + 'static {
+        self.step(pattern, run)
+    }
+
+    /// Define a step, usually a `Then` step.
+    pub fn then<F>(self, pattern: &str, run: F) -> Steps<W> where F: Fn(&mut W, &StepArgs) + 'static {
+        self.step(pattern, run)
+    }
+
+    /// Define a step with any keyword. Panics if the pattern is invalid.
+    pub fn step<F>(mut self, pattern: &str, run: F) -> Steps<W> where F: Fn(&mut W, &StepArgs) + 'static {
+        let regex = if pattern.starts_with('^') || pattern.ends_with('$') {
+            Ok(pattern.to_string())
+        } else {
+            expression_regex(pattern)
+        };
+        let regex = regex.and_then(|regex| Regex::new(&regex))
+            .unwrap_or_else(|e| panic!("invalid step pattern `{}`: {}", pattern, e));
+        self.definitions.push(Definition { pattern: pattern.to_string(), regex, run: Box::new(run) });
+        self
+    }
+
+    /// Run the steps of a scenario of `file` with a new world. This is what the tests generated
+    /// by `feature!` call.
+    ///
+    /// Nothing runs if any step is undefined: the panic lists all undefined steps with
+    /// definitions to start from. If a step panics, the panic names the step.
+    pub fn run(&self, scenario: &Scenario, file: &str) where W: Default {
+        let mut matched = vec![];
+        let mut undefined = vec![];
+        for step in &scenario.steps {
+            let matches: Vec<(&Definition<W>, Vec<Option<String>>)> = self.definitions.iter()
+                .filter_map(|definition| definition.regex.captures(&step.text).map(|captures| (definition, captures)))
+                .collect();
+            match matches.len() {
+                0 => undefined.push(step),
+                1 => {
+                    let (definition, ref captures) = matches[0];
+                    let captures = captures.iter().skip(1)
+                        .map(|capture| capture.clone().unwrap_or_default())
+                        .collect();
+                    matched.push((definition, StepArgs { step, captures }));
+                },
+                _ => {
+                    let patterns: Vec<String> = matches.iter()
+                        .map(|&(definition, _)| format!("`{}`", definition.pattern))
+                        .collect();
+                    panic!("the step `{} {}` ({}:{}) is ambiguous, it matches {}", step.keyword, step.text, file,
+                           step.line, patterns.join(", "));
+                }
+            }
+        }
+
+        if !undefined.is_empty() {
+            panic!("{}", undefined_steps(scenario, file, &undefined));
+        }
+
+        let mut world = W::default();
+        for (definition, args) in matched {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| (definition.run)(&mut world, &args)));
+            if let Err(payload) = result {
+                panic!("step failed: {} {} ({}:{})\n{}", args.step.keyword, args.step.text, file, args.step.line,
+                       panic_message(&*payload));
+            }
+        }
+    }
+}
+
+fn expression_regex(expression: &str) -> Result<String, String> {
+    let mut regex = String::from("^");
+    let mut rest = expression;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '{' => {
+                let end = rest.find('}').ok_or("`{` is not closed")?;
+                regex.push_str(match &rest[1..end] {
+                    "int" => r"(-?\d+)",
+                    "float" => r"(-?\d*\.?\d+)",
+                    "word" => r"(\S+)",
+                    "string" => r#""([^"]*)""#,
+                    "" => "(.*)",
+                    other => return Err(format!("unknown parameter type `{{{}}}`", other))
+                });
+                rest = &rest[end + 1..];
+            },
+            '(' => {
+                let end = rest.find(')').ok_or("`(` is not closed")?;
+                regex.push_str(&format!("(?:{})?", ::regex::escape(&rest[1..end])));
+                rest = &rest[end + 1..];
+            },
+            '\\' if rest.len() > 1 => {
+                let escaped = rest[1..].chars().next().unwrap();
+                regex.push_str(&::regex::escape(&escaped.to_string()));
+                rest = &rest[1 + escaped.len_utf8()..];
+            },
+            c => {
+                regex.push_str(&::regex::escape(&c.to_string()));
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    regex.push('$');
+    Ok(regex)
+}
+
+fn undefined_steps(scenario: &Scenario, file: &str, steps: &[&Step]) -> String {
+    let mut message = format!("{} undefined step{} in the scenario \"{}\" ({}:{}):\n", steps.len(),
+                              if steps.len() == 1 { "" } else { "s" }, scenario.name, file, scenario.line);
+    for step in steps {
+        message.push_str(&format!("    {} {} ({}:{})\n", step.keyword, step.text, file, step.line));
+    }
+    message.push_str("\nDefine them like this:\n\n");
+    for step in steps {
+        message.push_str(&format!("    .{}({:?}, |_world, _args| unimplemented!())\n", step.kind, snippet(&step.text)));
+    }
+    message
+}
+
+// An expression matching the text of an undefined step, with arguments for numbers and quoted
+// strings.
+fn snippet(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('{', "\\{").replace('(', "\\(");
+    let arguments = Regex::new(r#""[^"]*"|-?\b\d*\.\d+\b|-?\b\d+\b"#).unwrap();
+    arguments.replace_all(&escaped, |argument| {
+        if argument.starts_with('"') {
+            "{string}".to_string()
+        } else if argument.contains('.') {
+            "{float}".to_string()
+        } else {
+            "{int}".to_string()
+        }
+    })
+}
+
+fn panic_message(payload: &(Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "Box<Any>".to_string()
+        }
+    }
+}
+
+/// The arguments of a step: the captures of its pattern, its data table and its doc string.
+pub struct StepArgs<'s> {
+    step: &'s Step,
+    captures: Vec<String>
+}
+
+impl<'s> StepArgs<'s> {
+    /// Parse capture `index`, counting from 0. Panics if there is no such capture or it does not
+    /// parse.
+    pub fn get<T>(&self, index: usize) -> T where T: FromStr, T::Err: Debug {
+        self.str(index).parse().unwrap_or_else(|e| {
+            panic!("argument {} of `{}` does not parse: {:?}", index, self.step.text, e)
+        })
+    }
+
+    /// Capture `index`, counting from 0, or the empty string for an optional group which did not
+    /// match. Panics if there is no such capture.
+    pub fn str(&self, index: usize) -> &str {
+        match self.captures.get(index) {
+            Some(capture) => capture,
+            None => panic!("`{}` has {} arguments, there is no argument {}", self.step.text, self.captures.len(),
+                           index)
+        }
+    }
+
+    /// All captures.
+    pub fn captures(&self) -> &[String] {
+        &self.captures
+    }
+
+    /// The rows of the data table of the step, empty if it has none.
+    pub fn table(&self) -> &[Vec<String>] {
+        &self.step.table
+    }
+
+    /// The doc string of the step.
+    pub fn docstring(&self) -> Option<&str> {
+        self.step.docstring.as_ref().map(|docstring| &**docstring)
+    }
+
+    /// The step itself.
+    pub fn step(&self) -> &Step {
+        self.step
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+// Running tests in child processes.
+//
+// The runner re-executes the test binary with `STAINLESS_ISOLATED_TEST` set to the name of a
+// single test. The child runs just that test and writes a marker line followed by the failure
+// message to stderr, so that a test exiting the process early can be told apart from one which
+// finished.
+
+use std::env;
+use std::io::{self, Write};
+use std::process::{self, Command, ExitStatus, Stdio};
+
+use options::{Options, RunIgnored};
+use outcome::Outcome;
+
+/// Set for child processes, to the name of the test to run.
+pub const CHILD_ENV: &str = "STAINLESS_ISOLATED_TEST";
+
+const PASSED: &str = "---- stainless: test passed ----";
+const FAILED: &str = "---- stainless: test failed ----";
+
+/// The name of the test to run, if this process is the child of an isolated test.
+pub fn child_test() -> Option<String> {
+    env::var(CHILD_ENV).ok()
+}
+
+/// The options selecting exactly the test in the child.
+pub fn child_options(name: &str) -> Options {
+    Options {
+        filters: vec![name.to_string()],
+        exact: true,
+        run_ignored: RunIgnored::Yes,
+        test_threads: 1,
+        nocapture: true,
+        isolate: false,
+        shuffle: None,
+        max_failures: None,
+        ..Options::default()
+    }
+}
+
+/// Report the outcome of the test to the parent and exit.
+pub fn exit_child(outcome: &Outcome) -> ! {
+    let _ = io::stdout().flush();
+    match *outcome {
+        Outcome::Failed(ref message) => {
+            eprintln!("{}\n{}", FAILED, message);
+            process::exit(101)
+        },
+        _ => {
+            eprintln!("{}", PASSED);
+            process::exit(0)
+        }
+    }
+}
+
+/// Run the named test in a child process, returning its outcome and everything it printed.
+pub fn run(name: &str) -> (Outcome, String) {
+    let child = env::current_exe().and_then(|exe| {
+        Command::new(exe)
+            .env(CHILD_ENV, name)
+            .stdin(Stdio::null())
+            .output()
+    });
+    let child = match child {
+        Ok(child) => child,
+        Err(e) => return (Outcome::Failed(format!("failed to start the test process: {}", e)), String::new())
+    };
+
+    let stdout = String::from_utf8_lossy(&child.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&child.stderr).into_owned();
+
+    // Split off the report of the child at the end of stderr.
+    let (stderr, report) = match stderr.rfind(PASSED).into_iter().chain(stderr.rfind(FAILED)).max() {
+        Some(at) => (&stderr[..at], Some(&stderr[at..])),
+        None => (&stderr[..], None)
+    };
+
+    let outcome = match report {
+        Some(report) if report.starts_with(FAILED) => {
+            Outcome::Failed(report[FAILED.len()..].trim().to_string())
+        },
+        Some(_) if child.status.success() => Outcome::Passed,
+        _ => Outcome::Failed(describe_exit(child.status))
+    };
+
+    let mut output = stdout;
+    output.push_str(stderr);
+    (outcome, output)
+}
+
+// Why a test process ended without reporting an outcome.
+fn describe_exit(status: ExitStatus) -> String {
+    if let Some(signal) = signal(status) {
+        return format!("test process was killed by signal {}{}", signal, signal_name(signal));
+    }
+
+    match status.code() {
+        Some(code) => format!("test process exited with code {} before the test finished", code),
+        None => format!("test process ended unexpectedly: {}", status)
+    }
+}
+
+#[cfg(unix)]
+fn signal(status: ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn signal(_status: ExitStatus) -> Option<i32> {
+    None
+}
+
+fn signal_name(signal: i32) -> &'static str {
+    match signal {
+        4 => " (SIGILL)",
+        6 => " (SIGABRT)",
+        8 => " (SIGFPE)",
+        9 => " (SIGKILL)",
+        11 => " (SIGSEGV)",
+        15 => " (SIGTERM)",
+        _ => ""
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+use bench::BenchResult;
+use outcome::{secs_f64, Outcome, Summary};
+use phase::Phase;
+use reporter::{DescribeInfo, Reporter, RunInfo, TestInfo, TestResult};
+
+/// The version of the JSON event schema, bumped on incompatible changes.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Streams one JSON object per line for every event of a run.
+///
+/// Every object has a `type` field, one of:
+///
+/// * `run_started`: `schema`, the `JSON_SCHEMA_VERSION`, and the `seed` of a shuffled run or
+///   `null`.
+/// * `suite_started`, `suite_finished`: `name`, `path` and `tags` of a `describe!` block.
+///   `path` lists the names of the enclosing blocks and the block itself. `suite_finished` also
+///   has the summed `duration_ms` of the tests reported inside of it.
+/// * `test_started`: `description`, `path` and `tags` of a test. `path` lists the names of the
+///   enclosing `describe!` blocks.
+/// * `hook_failed`: the fields of `test_started`, plus the `hook` (`before_each` or
+///   `after_each`), the `scope` it belongs to and the `message`. Followed by `test_failed`.
+/// * `test_passed`, `test_failed`, `test_skipped`, `test_not_run`: the fields of `test_started`,
+///   plus `duration_ms`, the captured `output` and the time spent in every `phase`. `test_failed`
+///   also has a `message`. `phases` lists objects with the `phase` (`before_each`, `test` or
+///   `after_each`), the `scope` of hooks and `duration_ms`; it is empty unless the test ran in
+///   harness mode in the runner's process.
+/// * `bench_result`: the fields of `test_started`, plus `ns_per_iter` and `variance`.
+/// * `run_finished`: the number of `passed`, `failed` and `ignored` tests, the number of tests
+///   `not_run` because the run stopped early, and `duration_ms`.
+///
+/// New fields may be added without changing the schema version.
+pub struct JsonReporter<W: Write> {
+    out: W,
+    // The summed duration of the tests of each open `describe!` block.
+    durations: Vec<Duration>
+}
+
+impl<W: Write> JsonReporter<W> {
+    /// Create a reporter writing to `out`.
+    pub fn new(out: W) -> JsonReporter<W> {
+        JsonReporter { out, durations: vec![] }
+    }
+}
+
+impl<W: Write> Reporter for JsonReporter<W> {
+    fn run_started(&mut self, run: &RunInfo) -> io::Result<()> {
+        let seed = run.seed.map(|seed| seed.to_string()).unwrap_or_else(|| "null".to_string());
+        writeln!(self.out, r#"{{"type":"run_started","schema":{},"seed":{}}}"#, JSON_SCHEMA_VERSION, seed)
+    }
+
+    fn describe_started(&mut self, describe: &DescribeInfo) -> io::Result<()> {
+        self.durations.push(Duration::default());
+        writeln!(self.out, r#"{{"type":"suite_started",{}}}"#, describe_fields(describe))
+    }
+
+    fn describe_finished(&mut self, describe: &DescribeInfo) -> io::Result<()> {
+        let duration = self.durations.pop().unwrap_or_default();
+        writeln!(self.out, r#"{{"type":"suite_finished",{},"duration_ms":{}}}"#,
+                 describe_fields(describe), millis(duration))
+    }
+
+    fn test_started(&mut self, test: &TestInfo) -> io::Result<()> {
+        writeln!(self.out, r#"{{"type":"test_started",{}}}"#, test_fields(test))
+    }
+
+    fn hook_failed(&mut self, test: &TestInfo, hook: Phase, message: &str) -> io::Result<()> {
+        let (hook, scope) = match hook {
+            Phase::BeforeEach(scope) => ("before_each", scope),
+            Phase::AfterEach(scope) => ("after_each", scope),
+            Phase::Test => return Ok(())
+        };
+        writeln!(self.out, r#"{{"type":"hook_failed",{},"hook":"{}","scope":{},"message":{}}}"#,
+                 test_fields(test), hook, string(scope), string(message))
+    }
+
+    fn test_finished(&mut self, test: &TestInfo, result: &TestResult) -> io::Result<()> {
+        for duration in &mut self.durations {
+            *duration += result.duration;
+        }
+
+        let (kind, message) = match *result.outcome {
+            Outcome::Passed => ("test_passed", String::new()),
+            Outcome::Failed(ref message) => ("test_failed", format!(r#","message":{}"#, string(message))),
+            Outcome::Ignored => ("test_skipped", String::new()),
+            Outcome::NotRun => ("test_not_run", String::new())
+        };
+
+        writeln!(self.out, r#"{{"type":"{}",{},"duration_ms":{}{},"output":{},"phases":{}}}"#, kind,
+                 test_fields(test), millis(result.duration), message, string(result.output),
+                 phases(result.phases))
+    }
+
+    fn bench_finished(&mut self, bench: &TestInfo, result: &BenchResult) -> io::Result<()> {
+        writeln!(self.out, r#"{{"type":"bench_result",{},"ns_per_iter":{},"variance":{}}}"#,
+                 test_fields(bench), result.ns_per_iter, result.variance)
+    }
+
+    fn run_finished(&mut self, summary: &Summary) -> io::Result<()> {
+        writeln!(self.out, r#"{{"type":"run_finished","passed":{},"failed":{},"ignored":{},"not_run":{},"duration_ms":{}}}"#,
+                 summary.passed, summary.failed, summary.ignored, summary.not_run, millis(summary.duration))?;
+        self.out.flush()
+    }
+}
+
+fn describe_fields(describe: &DescribeInfo) -> String {
+    format!(r#""name":{},"path":{},"tags":{}"#,
+            string(describe.name), strings(describe.path), strings(describe.tags))
+}
+
+fn test_fields(test: &TestInfo) -> String {
+    format!(r#""description":{},"path":{},"tags":{}"#,
+            string(test.description), strings(test.path), strings(test.tags))
+}
+
+fn phases(phases: &[(Phase, Duration)]) -> String {
+    let phases: Vec<String> = phases.iter().map(|&(phase, duration)| {
+        let (name, scope) = match phase {
+            Phase::BeforeEach(scope) => ("before_each", format!(r#","scope":{}"#, string(scope))),
+            Phase::Test => ("test", String::new()),
+            Phase::AfterEach(scope) => ("after_each", format!(r#","scope":{}"#, string(scope)))
+        };
+        format!(r#"{{"phase":"{}"{},"duration_ms":{}}}"#, name, scope, millis(duration))
+    }).collect();
+    format!("[{}]", phases.join(","))
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.3}", secs_f64(duration) * 1000.0)
+}
+
+/// A JSON string literal.
+pub fn string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c)
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// A JSON array of strings.
+pub fn strings(texts: &[&str]) -> String {
+    let texts: Vec<String> = texts.iter().map(|text| string(text)).collect();
+    format!("[{}]", texts.join(","))
+}
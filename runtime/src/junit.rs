@@ -0,0 +1,156 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+use outcome::{secs_f64, Outcome, Summary};
+use reporter::{DescribeInfo, Reporter, TestInfo, TestResult};
+
+/// Writes a JUnit XML report with one `<testsuite>` per `describe!` block and one `<testcase>`
+/// per test.
+///
+/// The `classname` of a test case is the path of its `describe!` blocks joined with dots, and
+/// its `name` is the original description. The report is written when the run is finished.
+pub struct JUnitReporter<W: Write> {
+    out: W,
+    suites: Vec<TestSuite>,
+    // Indices into `suites` of the `describe!` blocks currently entered.
+    open: Vec<usize>
+}
+
+struct TestSuite {
+    name: String,
+    cases: Vec<TestCase>
+}
+
+struct TestCase {
+    name: String,
+    outcome: Outcome,
+    duration: Duration,
+    output: String
+}
+
+impl<W: Write> JUnitReporter<W> {
+    /// Create a reporter writing the report to `out`.
+    pub fn new(out: W) -> JUnitReporter<W> {
+        JUnitReporter { out, suites: vec![], open: vec![] }
+    }
+}
+
+impl<W: Write> Reporter for JUnitReporter<W> {
+    fn describe_started(&mut self, describe: &DescribeInfo) -> io::Result<()> {
+        self.open.push(self.suites.len());
+        self.suites.push(TestSuite { name: describe.path.join("."), cases: vec![] });
+        Ok(())
+    }
+
+    fn describe_finished(&mut self, _describe: &DescribeInfo) -> io::Result<()> {
+        self.open.pop();
+        Ok(())
+    }
+
+    fn test_finished(&mut self, test: &TestInfo, result: &TestResult) -> io::Result<()> {
+        if let Some(&index) = self.open.last() {
+            self.suites[index].cases.push(TestCase {
+                name: test.description.to_string(),
+                outcome: result.outcome.clone(),
+                duration: result.duration,
+                output: result.output.to_string()
+            });
+        }
+        Ok(())
+    }
+
+    fn run_finished(&mut self, summary: &Summary) -> io::Result<()> {
+        // Only test cases are counted, measured benchmarks are not part of the report.
+        let (mut failures, mut skipped) = (0, 0);
+        for suite in &self.suites {
+            let (suite_failures, suite_skipped) = count(&suite.cases);
+            failures += suite_failures;
+            skipped += suite_skipped;
+        }
+        let tests: usize = self.suites.iter().map(|suite| suite.cases.len()).sum();
+
+        writeln!(self.out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(self.out, r#"<testsuites tests="{}" failures="{}" skipped="{}" time="{}">"#,
+                 tests, failures, skipped, seconds(summary.duration))?;
+
+        for suite in &self.suites {
+            let (failures, skipped) = count(&suite.cases);
+            let time = suite.cases.iter().map(|c| c.duration).sum();
+
+            writeln!(self.out, r#"  <testsuite name="{}" tests="{}" failures="{}" skipped="{}" time="{}">"#,
+                     escape(&suite.name), suite.cases.len(), failures, skipped, seconds(time))?;
+
+            for case in &suite.cases {
+                write!(self.out, r#"    <testcase classname="{}" name="{}" time="{}""#,
+                       escape(&suite.name), escape(&case.name), seconds(case.duration))?;
+
+                if case.outcome == Outcome::Passed && case.output.is_empty() {
+                    writeln!(self.out, "/>")?;
+                    continue;
+                }
+
+                writeln!(self.out, ">")?;
+                match case.outcome {
+                    Outcome::Passed => {},
+                    Outcome::Failed(ref message) => {
+                        let first_line = message.lines().next().unwrap_or("");
+                        writeln!(self.out, r#"      <failure message="{}" type="panic">{}</failure>"#,
+                                 escape(first_line), escape(message))?;
+                    },
+                    Outcome::Ignored => writeln!(self.out, "      <skipped/>")?,
+                    Outcome::NotRun => writeln!(self.out, r#"      <skipped message="not run"/>"#)?
+                }
+                if !case.output.is_empty() {
+                    writeln!(self.out, "      <system-out>{}</system-out>", escape(&case.output))?;
+                }
+                writeln!(self.out, "    </testcase>")?;
+            }
+
+            writeln!(self.out, "  </testsuite>")?;
+        }
+
+        writeln!(self.out, "</testsuites>")?;
+        self.out.flush()
+    }
+}
+
+/// The number of failed and skipped test cases.
+fn count(cases: &[TestCase]) -> (usize, usize) {
+    let (mut failures, mut skipped) = (0, 0);
+    for case in cases {
+        match case.outcome {
+            Outcome::Passed => {},
+            Outcome::Failed(_) => failures += 1,
+            Outcome::Ignored | Outcome::NotRun => skipped += 1
+        }
+    }
+    (failures, skipped)
+}
+
+fn seconds(duration: Duration) -> String {
+    format!("{:.3}", secs_f64(duration))
+}
+
+/// Escape text for use in XML attributes and text nodes.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters are not allowed in XML 1.0.
+            c if c < ' ' && c != '\n' && c != '\t' && c != '\r' => {},
+            c => escaped.push(c)
+        }
+    }
+    escaped
+}
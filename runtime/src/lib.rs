@@ -0,0 +1,138 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+#![deny(missing_docs)]
+// Trait objects are written without `dyn`, which the compiler of the plugin does not know.
+#![allow(unknown_lints, bare_trait_objects)]
+#![cfg_attr(feature = "nightly", feature(set_stdio))]
+
+//! Runtime support for [stainless](https://github.com/reem/stainless).
+//!
+//! The `describe!` syntax extension lives in the `stainless` compiler plugin, which can not
+//! be linked into test binaries. Everything that has to exist while the tests are running
+//! lives in this crate instead.
+//!
+//! ## Harness mode
+//!
+//! By default `describe!` generates plain `#[test]` functions run by libtest. In harness mode
+//! it instead registers the whole `describe!` tree with this crate, which runs the tests with
+//! its own scheduler and reporters. To use it for an integration test, disable the libtest
+//! harness in `Cargo.toml`:
+//!
+//! ``` toml
+//! [dev-dependencies]
+//! stainless = "*"
+//! stainless_runtime = "*"
+//!
+//! [[test]]
+//! name = "spec"
+//! harness = false
+//! ```
+//!
+//! and enable harness mode in `tests/spec.rs`, listing the top-level `describe!` blocks:
+//!
+//! ``` rust,ignore
+//! #![feature(plugin)]
+//! #![plugin(stainless(harness))]
+//!
+//! #[macro_use]
+//! extern crate stainless_runtime;
+//!
+//! describe! addition {
+//!     it "adds" {
+//!         assert_eq!(2 + 2, 4);
+//!     }
+//! }
+//!
+//! stainless_main!(addition);
+//! ```
+//!
+//! The runner accepts the usual libtest flags (`cargo test -- --help` lists them) as well as
+//! `--tags` and `--exclude-tags`.
+//!
+//! Like libtest, the runner captures what tests print and shows it for failed tests. This uses
+//! an unstable API of the standard library and requires the `nightly` feature.
+//!
+//! ## Reports
+//!
+//! `--format` chooses the output printed while running: the `describe!` tree (`spec`, the
+//! default), [TAP version 14](https://testanything.org/tap-version-14-specification.html)
+//! (`tap`) or a stream of JSON objects (`json`, see `JsonReporter` for the schema).
+//!
+//! `--junit PATH` writes a JUnit XML report with one `<testsuite>` per `describe!` block.
+//! `--json PATH`, or the `STAINLESS_JSON` environment variable, appends the JSON events to a
+//! file, which is how `cargo stainless watch` finds the failed tests.
+//!
+//! All of these implement the `Reporter` trait, which can also be implemented to send results
+//! elsewhere. Additional reporters are added with `Runner::reporter`, or listed in
+//! `stainless_main!`:
+//!
+//! ``` rust,ignore
+//! stainless_main!(addition; reporters: Dashboard::connect("/tmp/dashboard.sock"));
+//! ```
+
+pub use bench::{BenchResult, Bencher};
+pub use gherkin::{Scenario, Step, StepArgs, StepKind, Steps};
+pub use json::{JsonReporter, JSON_SCHEMA_VERSION};
+pub use junit::JUnitReporter;
+pub use options::{Format, Options, RunIgnored, ShuffleScope};
+pub use outcome::{format_duration, Outcome, Summary};
+pub use path::PathPattern;
+pub use phase::{Phase, __enter_phase};
+pub use reporter::{DescribeInfo, Reporter, RunInfo, TestInfo, TestResult};
+pub use runner::{run_suites, Runner};
+pub use spec::SpecReporter;
+pub use suite::{test_name, Bench, Node, Serial, ShouldPanic, Suite, Test};
+pub use tap::TapReporter;
+pub use tags::TagExpr;
+
+mod bench;
+mod capture;
+mod gherkin;
+mod isolate;
+mod json;
+mod junit;
+mod options;
+mod outcome;
+mod path;
+mod phase;
+mod regex;
+mod reporter;
+mod runner;
+mod shuffle;
+mod spec;
+mod suite;
+mod tags;
+mod tap;
+
+/// Generates a `main` function running the given top-level `describe!` blocks, optionally
+/// with additional reporters. The plugin rejects crates where a block is missing from the list.
+///
+/// ``` rust,ignore
+/// stainless_main!(addition, nested::subtraction);
+/// stainless_main!(addition; reporters: Dashboard::new(), Notifier::new());
+/// ```
+#[macro_export]
+macro_rules! stainless_main {
+    ($($($module:ident)::+),* ; reporters: $($reporter:expr),* $(,)*) => {
+        fn main() {
+            $crate::Runner::new(vec![$($($module)::+::__stainless_suite()),*])
+                $(.reporter($reporter))*
+                .run();
+        }
+    };
+    ($($($module:ident)::+),* $(,)*) => {
+        fn main() {
+            $crate::Runner::new(vec![$($($module)::+::__stainless_suite()),*]).run();
+        }
+    };
+}
+
+/// Run the suites with the options given on the command line and exit with the libtest exit
+/// codes.
+pub fn run(suites: Vec<Suite>) {
+    Runner::new(suites).run()
+}
@@ -0,0 +1,377 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::cell::Cell;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use path::PathPattern;
+use suite::test_name;
+use tags::TagExpr;
+
+/// Usage printed for `--help`.
+pub const USAGE: &str = "\
+Usage: [OPTIONS] [FILTERS...]
+
+Filters select tests by name, e.g. `nesting::makes_it`, or by describe path and description
+when they contain `>`, e.g. `stainless > nesting > makes it`. Each level of a path is a glob
+or a regular expression between slashes, and `**` matches any number of levels. A glob on the
+last level only has to match the start of a name.
+
+Options taking a value accept it as the next argument or after `=`, e.g. `--format=json`.
+
+Options:
+        --include-ignored   Run ignored and not ignored tests
+        --ignored           Run only ignored tests
+        --exact             Exactly match filters rather than by substring
+        --skip FILTER       Skip tests whose names contain FILTER (may be repeated)
+        --list              List the selected tests and benchmarks without running them
+        --test              Run tests and not benchmarks
+        --bench             Run benchmarks instead of tests
+    -j, --jobs N            Maximum number of tests running in parallel
+        --test-threads N    Same as --jobs
+        --fail-fast         Stop starting tests after the first failure
+        --max-failures N    Stop starting tests after N failures
+        --isolate           Run every test in its own process, like tests marked
+                            `isolated`
+        --shuffle           Run tests in a random order, printing the seed
+        --shuffle-scope all|describe
+                            Shuffle all tests, or only within each describe! block
+                            (implies --shuffle)
+        --seed N            Shuffle with the given seed, to reproduce an order
+                            (implies --shuffle)
+        --nocapture         Do not capture the output of tests (capturing requires the
+                            `nightly` feature of stainless_runtime)
+        --color auto|always|never
+                            Configure coloring of output
+    -q, --quiet             Accepted for compatibility
+        --tags EXPR         Only run tests whose tags match EXPR, e.g. `slow and not db`
+        --exclude-tags EXPR Do not run tests whose tags match EXPR
+        --format spec|terse|tap|json
+                            Configure the output: the describe! tree, a character
+                            per test, TAP version 14 or a JSON object per line
+        --junit PATH        Write a JUnit XML report to PATH
+        --json PATH         Append the events of --format json to PATH, also set
+                            with STAINLESS_JSON
+        --slowest N         List the N slowest tests and describe! blocks
+        --slow-threshold DURATION
+                            Mark tests taking longer than DURATION as slow, e.g.
+                            `500ms` or `2s`
+    -h, --help              Display this message
+";
+
+/// Whether ignored tests are run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunIgnored {
+    /// Run only tests which are not ignored.
+    No,
+    /// Run all tests, `--include-ignored`.
+    Yes,
+    /// Run only ignored tests, `--ignored`.
+    Only
+}
+
+/// Which tests are shuffled with each other, chosen with `--shuffle-scope`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShuffleScope {
+    /// All selected tests run in a random order. Reporters see a `describe!` block once for
+    /// every run of consecutive tests inside of it.
+    All,
+    /// The tests and nested blocks of each `describe!` block are shuffled, but every block
+    /// still runs all of its tests together.
+    Describe
+}
+
+/// The format of the output, chosen with `--format`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// The `describe!` tree, printed by `SpecReporter`.
+    Spec,
+    /// A character per test, printed by `SpecReporter::terse`. `--list` prints libtest-style
+    /// names in this format.
+    Terse,
+    /// TAP version 14, printed by `TapReporter`.
+    Tap,
+    /// JSON lines, printed by `JsonReporter`.
+    Json
+}
+
+/// Command line options of the stainless runner.
+///
+/// These are compatible with the flags of the libtest harness, so `cargo test -- <options>`
+/// keeps working when switching to harness mode.
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Substring filters on test names, any of which or of the `paths` has to match.
+    pub filters: Vec<String>,
+    /// Filters on the describe path and description of tests, given as filters containing `>`.
+    pub paths: Vec<PathPattern>,
+    /// Match the filters exactly instead of by substring.
+    pub exact: bool,
+    /// Tests whose names contain any of these are skipped.
+    pub skip: Vec<String>,
+    /// Whether to run ignored tests.
+    pub run_ignored: RunIgnored,
+    /// Only list the tests.
+    pub list: bool,
+    /// Measure benchmarks instead of running tests.
+    pub bench: bool,
+    /// Let tests print directly instead of capturing their output.
+    pub nocapture: bool,
+    /// Maximum number of tests to run in parallel, from `--jobs` or `--test-threads`.
+    pub test_threads: usize,
+    /// Run every test in its own process.
+    pub isolate: bool,
+    /// Stop starting new tests after this many failures.
+    pub max_failures: Option<usize>,
+    /// How to shuffle the tests, if at all.
+    pub shuffle: Option<ShuffleScope>,
+    /// The seed to shuffle with, a random one is chosen if not given.
+    pub seed: Option<u64>,
+    /// The format of the output.
+    pub format: Format,
+    /// Whether to use ANSI colors in the output.
+    pub color: bool,
+    /// Only run tests matching this tag expression.
+    pub tags: Option<TagExpr>,
+    /// Do not run tests matching this tag expression.
+    pub exclude_tags: Option<TagExpr>,
+    /// Where to write a JUnit XML report.
+    pub junit: Option<PathBuf>,
+    /// Where to append the events of `--format json`, so that all test binaries run by
+    /// `cargo test` can write to the same file.
+    pub json: Option<PathBuf>,
+    /// How many of the slowest tests and `describe!` blocks to list after the run.
+    pub slowest: usize,
+    /// Tests taking longer than this are marked as slow.
+    pub slow_threshold: Option<Duration>,
+    /// Print the usage and exit.
+    pub help: bool
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            filters: vec![],
+            paths: vec![],
+            exact: false,
+            skip: vec![],
+            run_ignored: RunIgnored::No,
+            list: false,
+            bench: false,
+            nocapture: ::std::env::var_os("RUST_TEST_NOCAPTURE").is_some(),
+            test_threads: default_test_threads(),
+            isolate: false,
+            max_failures: None,
+            shuffle: default_shuffle(),
+            seed: ::std::env::var("RUST_TEST_SHUFFLE_SEED").ok().and_then(|seed| seed.parse().ok()),
+            format: Format::Spec,
+            color: stdout_is_terminal(),
+            tags: None,
+            exclude_tags: None,
+            junit: None,
+            json: ::std::env::var_os("STAINLESS_JSON").map(PathBuf::from),
+            slowest: 0,
+            slow_threshold: None,
+            help: false
+        }
+    }
+}
+
+impl Options {
+    /// Parse the options from the command line arguments, not including the program name.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            // `--flag=value` is the same as `--flag value`.
+            let (arg, inline) = match arg.find('=') {
+                Some(index) if arg.starts_with("--") => {
+                    (arg[..index].to_string(), Cell::new(Some(arg[index + 1..].to_string())))
+                },
+                _ => (arg, Cell::new(None))
+            };
+
+            let mut value = |name: &str| match inline.take() {
+                Some(value) => Ok(value),
+                None => args.next().ok_or_else(|| format!("missing argument for `{}`", name))
+            };
+
+            match &*arg {
+                "--include-ignored" => options.run_ignored = RunIgnored::Yes,
+                "--ignored" => options.run_ignored = RunIgnored::Only,
+                "--exact" => options.exact = true,
+                "--skip" => options.skip.push(value("--skip")?),
+                "--list" => options.list = true,
+                "--test" => options.bench = false,
+                "--bench" => options.bench = true,
+                "--nocapture" => options.nocapture = true,
+                "-q" | "--quiet" => {},
+                "-j" | "--jobs" | "--test-threads" => {
+                    options.test_threads = parse_count(&arg, &value(&arg)?)?;
+                },
+                "--isolate" => options.isolate = true,
+                "--fail-fast" => options.max_failures = Some(1),
+                "--max-failures" => {
+                    options.max_failures = Some(parse_count("--max-failures", &value("--max-failures")?)?);
+                },
+                "--shuffle" => {
+                    options.shuffle = options.shuffle.or(Some(ShuffleScope::All));
+                },
+                "--shuffle-scope" => {
+                    options.shuffle = match &*value("--shuffle-scope")? {
+                        "all" => Some(ShuffleScope::All),
+                        "describe" => Some(ShuffleScope::Describe),
+                        other => return Err(format!("invalid `--shuffle-scope` value `{}`", other))
+                    };
+                },
+                "--seed" | "--shuffle-seed" => {
+                    let seed = value(&arg)?;
+                    options.seed = Some(seed.parse().map_err(|_| {
+                        format!("`{}` expects a number, got `{}`", arg, seed)
+                    })?);
+                    options.shuffle = options.shuffle.or(Some(ShuffleScope::All));
+                },
+                "--color" => {
+                    options.color = match &*value("--color")? {
+                        "auto" => stdout_is_terminal(),
+                        "always" => true,
+                        "never" => false,
+                        other => return Err(format!("invalid `--color` value `{}`", other))
+                    };
+                },
+                "--format" => {
+                    options.format = match &*value("--format")? {
+                        "spec" | "pretty" => Format::Spec,
+                        "terse" => Format::Terse,
+                        "tap" => Format::Tap,
+                        "json" => Format::Json,
+                        other => return Err(format!("invalid `--format` value `{}`", other))
+                    };
+                },
+                "--tags" => options.tags = Some(TagExpr::parse(&value("--tags")?)?),
+                "--exclude-tags" => {
+                    options.exclude_tags = Some(TagExpr::parse(&value("--exclude-tags")?)?);
+                },
+                "--junit" => options.junit = Some(PathBuf::from(value("--junit")?)),
+                "--json" => options.json = Some(PathBuf::from(value("--json")?)),
+                "--slowest" => options.slowest = parse_count("--slowest", &value("--slowest")?)?,
+                "--slow-threshold" => {
+                    options.slow_threshold = Some(parse_duration("--slow-threshold", &value("--slow-threshold")?)?);
+                },
+                "-h" | "--help" => options.help = true,
+                flag if flag.starts_with('-') => return Err(format!("unrecognized option `{}`", flag)),
+                filter if filter.contains('>') => options.paths.push(PathPattern::parse(filter)?),
+                filter => options.filters.push(filter.to_string())
+            }
+
+            if let Some(value) = inline.take() {
+                return Err(format!("`{}` does not take a value, got `{}`", arg, value));
+            }
+        }
+
+        Ok(options)
+    }
+
+    /// Whether a test with the given describe path and description passes the name and path
+    /// filters.
+    pub fn matches_name(&self, path: &[&str], description: &str) -> bool {
+        let name = test_name(path, description);
+        let matches = |filter: &String| {
+            if self.exact { name == *filter } else { name.contains(&**filter) }
+        };
+
+        let selected = (self.filters.is_empty() && self.paths.is_empty()) ||
+            self.filters.iter().any(&matches) ||
+            self.paths.iter().any(|pattern| pattern.matches(path, description));
+        selected && !self.skip.iter().any(&matches)
+    }
+
+    /// Whether a test with the given tags passes `--tags` and `--exclude-tags`.
+    pub fn matches_tags(&self, tags: &[&str]) -> bool {
+        self.tags.as_ref().map_or(true, |expr| expr.matches(tags)) &&
+            !self.exclude_tags.as_ref().map_or(false, |expr| expr.matches(tags))
+    }
+}
+
+/// Parse a positive count given to a flag.
+pub fn parse_count(flag: &str, value: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(0) | Err(_) => Err(format!("`{}` expects a positive number, got `{}`", flag, value)),
+        Ok(n) => Ok(n)
+    }
+}
+
+/// Parse a duration given to a flag, in milliseconds (`500`, `500ms`) or seconds (`1.5s`).
+pub fn parse_duration(flag: &str, value: &str) -> Result<Duration, String> {
+    let (number, scale) = if value.ends_with("ms") {
+        (&value[..value.len() - 2], 0.001)
+    } else if value.ends_with('s') {
+        (&value[..value.len() - 1], 1.0)
+    } else {
+        (value, 0.001)
+    };
+
+    match number.parse::<f64>() {
+        Ok(number) if number >= 0.0 && number.is_finite() => {
+            let secs = number * scale;
+            Ok(Duration::new(secs.trunc() as u64, (secs.fract() * 1e9) as u32))
+        },
+        _ => Err(format!("`{}` expects a duration like `500ms` or `2s`, got `{}`", flag, value))
+    }
+}
+
+fn default_shuffle() -> Option<ShuffleScope> {
+    let shuffle = ::std::env::var_os("RUST_TEST_SHUFFLE").is_some() ||
+        ::std::env::var_os("RUST_TEST_SHUFFLE_SEED").is_some();
+    if shuffle { Some(ShuffleScope::All) } else { None }
+}
+
+fn default_test_threads() -> usize {
+    ::std::env::var("RUST_TEST_THREADS").ok()
+        .and_then(|threads| threads.parse().ok())
+        .unwrap_or_else(available_parallelism)
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn available_parallelism() -> usize {
+    use std::os::raw::{c_int, c_long};
+
+    extern "C" {
+        fn sysconf(name: c_int) -> c_long;
+    }
+    #[cfg(target_os = "linux")]
+    const _SC_NPROCESSORS_ONLN: c_int = 84;
+    #[cfg(target_os = "macos")]
+    const _SC_NPROCESSORS_ONLN: c_int = 58;
+
+    match unsafe { sysconf(_SC_NPROCESSORS_ONLN) } {
+        n if n > 0 => n as usize,
+        _ => 1
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn available_parallelism() -> usize {
+    1
+}
+
+#[cfg(unix)]
+fn stdout_is_terminal() -> bool {
+    use std::os::raw::c_int;
+
+    extern "C" {
+        fn isatty(fd: c_int) -> c_int;
+    }
+    unsafe { isatty(1) == 1 }
+}
+
+// Colors are only enabled with `--color always` elsewhere.
+#[cfg(not(unix))]
+fn stdout_is_terminal() -> bool {
+    false
+}
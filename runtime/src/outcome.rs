@@ -0,0 +1,68 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::time::Duration;
+
+/// The outcome of running a single test.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The test ran to completion, or panicked as expected for `failing`.
+    Passed,
+    /// The test failed, with a message describing why.
+    Failed(String),
+    /// The test was not run because it is marked `ignore`.
+    Ignored,
+    /// The test was not run because the run stopped early, after `--fail-fast` or
+    /// `--max-failures`.
+    NotRun
+}
+
+/// The totals of a finished run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// Number of passed tests.
+    pub passed: usize,
+    /// Number of failed tests.
+    pub failed: usize,
+    /// Number of ignored tests.
+    pub ignored: usize,
+    /// Number of tests which were not run because the run stopped early.
+    pub not_run: usize,
+    /// Wall clock time of the whole run.
+    pub duration: Duration
+}
+
+impl Summary {
+    /// Count one more test with the given outcome.
+    pub fn record(&mut self, outcome: &Outcome) {
+        match *outcome {
+            Outcome::Passed => self.passed += 1,
+            Outcome::Failed(_) => self.failed += 1,
+            Outcome::Ignored => self.ignored += 1,
+            Outcome::NotRun => self.not_run += 1
+        }
+    }
+
+    /// Whether the run should be considered successful.
+    pub fn is_success(&self) -> bool {
+        self.failed == 0
+    }
+}
+
+/// A duration in seconds.
+pub fn secs_f64(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1e9
+}
+
+/// Format a duration the way all reporters print it, e.g. `12ms` or `1.52s`.
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_secs() * 1000 + u64::from(duration.subsec_nanos()) / 1_000_000;
+    if millis < 1000 {
+        format!("{}ms", millis)
+    } else {
+        format!("{}.{:02}s", millis / 1000, (millis % 1000) / 10)
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::fmt;
+
+use regex::{self, Regex};
+
+/// A filter on the `describe!` path and description of tests, such as
+/// `stainless > nesting > makes it simple`.
+///
+/// The levels are separated by `>` and matched from the top-level `describe!` block downwards.
+/// Each level is a glob, where `*` matches any text and `?` one character, or a regular
+/// expression between slashes, like `/^makes (it|them)/`. A glob on the last level only needs to
+/// match the start of a name, so `makes it simple` selects `makes it simple to categorize tests`.
+/// A level of `**` matches any number of levels, so `** > *simple*` selects tests with `simple`
+/// in their description anywhere in the tree. A pattern matching a `describe!` block selects
+/// everything inside of it.
+#[derive(Clone, Debug)]
+pub struct PathPattern {
+    source: String,
+    levels: Vec<Level>
+}
+
+#[derive(Clone, Debug)]
+enum Level {
+    // `**`
+    Any,
+    // A glob or regular expression, globs are translated to anchored regular expressions.
+    // Only the start of the last level is anchored.
+    Pattern(Regex)
+}
+
+impl PathPattern {
+    /// Parse a pattern like `stainless > * > /simple$/`.
+    pub fn parse(input: &str) -> Result<PathPattern, String> {
+        let count = input.split('>').count();
+        let levels = input.split('>').enumerate().map(|(index, level)| {
+            let level = level.trim();
+            if level.is_empty() {
+                return Err(format!("empty level in path pattern `{}`", input));
+            }
+
+            if level == "**" {
+                Ok(Level::Any)
+            } else if level.len() > 1 && level.starts_with('/') && level.ends_with('/') {
+                Regex::new(&level[1..level.len() - 1])
+                    .map(Level::Pattern)
+                    .map_err(|e| format!("invalid regular expression in path pattern `{}`: {}", input, e))
+            } else {
+                Ok(Level::Pattern(glob(level, index + 1 == count)))
+            }
+        }).collect::<Result<_, _>>()?;
+
+        Ok(PathPattern { source: input.to_string(), levels })
+    }
+
+    /// Whether a test with the given `describe!` path and description is selected.
+    pub fn matches(&self, path: &[&str], description: &str) -> bool {
+        let mut full = path.to_vec();
+        full.push(description);
+        matches(&self.levels, &full)
+    }
+}
+
+impl fmt::Display for PathPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+// Whether the levels match a prefix of the names.
+fn matches(levels: &[Level], names: &[&str]) -> bool {
+    let (level, rest) = match levels.split_first() {
+        Some(split) => split,
+        None => return true
+    };
+    match *level {
+        Level::Any => (0..names.len() + 1).any(|skip| matches(rest, &names[skip..])),
+        Level::Pattern(ref pattern) => match names.split_first() {
+            Some((name, names)) => pattern.is_match(name) && matches(rest, names),
+            None => false
+        }
+    }
+}
+
+fn glob(glob: &str, prefix: bool) -> Regex {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string()))
+        }
+    }
+    if !prefix {
+        pattern.push('$');
+    }
+    Regex::new(&pattern).expect("escaped globs are valid regular expressions")
+}
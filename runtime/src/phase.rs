@@ -0,0 +1,86 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::mem;
+use std::time::{Duration, Instant};
+
+/// The part of a test which is currently running.
+///
+/// `before_each` and `after_each` blocks are spliced into the test function, so in harness mode
+/// `describe!` also splices in calls to `__enter_phase` to let the runner tell hook failures
+/// from test failures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// The `before_each` block of the named `describe!` block.
+    BeforeEach(&'static str),
+    /// The body of the test itself.
+    Test,
+    /// The `after_each` block of the named `describe!` block.
+    AfterEach(&'static str)
+}
+
+impl Phase {
+    /// Whether this is a `before_each` or `after_each` block.
+    pub fn is_hook(&self) -> bool {
+        *self != Phase::Test
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Phase::BeforeEach(scope) => write!(f, "`before_each` of `{}`", scope),
+            Phase::Test => write!(f, "test"),
+            Phase::AfterEach(scope) => write!(f, "`after_each` of `{}`", scope)
+        }
+    }
+}
+
+thread_local!(static PHASE: Cell<Phase> = Cell::new(Phase::Test));
+thread_local!(static TIMELINE: RefCell<Vec<(Phase, Instant)>> = RefCell::new(Vec::new()));
+
+/// Called by the code generated by `describe!` when entering a hook or the test body.
+#[doc(hidden)]
+pub fn __enter_phase(phase: Phase) {
+    PHASE.with(|current| current.set(phase));
+    TIMELINE.with(|timeline| timeline.borrow_mut().push((phase, Instant::now())));
+}
+
+/// Start timing the phases of a test on the current thread.
+pub fn start() {
+    TIMELINE.with(|timeline| timeline.borrow_mut().clear());
+    __enter_phase(Phase::Test);
+}
+
+/// How long each phase took since `start`: the `before_each` blocks, the test and the
+/// `after_each` blocks, in that order.
+pub fn finish() -> Vec<(Phase, Duration)> {
+    let end = Instant::now();
+    let timeline = TIMELINE.with(|timeline| mem::replace(&mut *timeline.borrow_mut(), Vec::new()));
+
+    let mut phases: Vec<(Phase, Duration)> = vec![];
+    for (i, &(phase, started)) in timeline.iter().enumerate() {
+        let ended = timeline.get(i + 1).map_or(end, |&(_, next)| next);
+        match phases.iter().position(|&(seen, _)| seen == phase) {
+            Some(index) => phases[index].1 += ended - started,
+            None => phases.push((phase, ended - started))
+        }
+    }
+
+    phases.sort_by_key(|&(phase, _)| match phase {
+        Phase::BeforeEach(_) => 0,
+        Phase::Test => 1,
+        Phase::AfterEach(_) => 2
+    });
+    phases
+}
+
+/// The phase the current thread is in.
+pub fn current() -> Phase {
+    PHASE.with(|current| current.get())
+}
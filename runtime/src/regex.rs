@@ -0,0 +1,419 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+//! The regular expressions of path filters and step definitions.
+//!
+//! The runtime is built with the nightly compiler of the plugin, which no published version of
+//! the `regex` crate and its dependencies supports anymore, so it comes with a small
+//! backtracking matcher of its own. It supports literals, `.`, classes like `[a-z]` and `[^"]`,
+//! `\d`, `\w`, `\s` and their negations, `^`, `$`, `\b`, capturing and `(?:...)` groups, `|` and
+//! the greedy and lazy quantifiers `*`, `+`, `?` and `{n,m}`.
+
+use std::fmt;
+
+/// A compiled regular expression.
+#[derive(Clone)]
+pub struct Regex {
+    source: String,
+    node: Node,
+    groups: usize
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Char(char),
+    // `.`, anything but a newline.
+    Any,
+    Class(Class),
+    Start,
+    End,
+    // `\b`, or `\B` when false.
+    WordBoundary(bool),
+    // A group, with its index when it captures.
+    Group(Option<usize>, Box<Node>),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat(Repetition)
+}
+
+#[derive(Clone, Debug)]
+struct Repetition {
+    node: Box<Node>,
+    min: usize,
+    max: Option<usize>,
+    greedy: bool
+}
+
+#[derive(Clone, Debug)]
+struct Class {
+    negated: bool,
+    items: Vec<ClassItem>
+}
+
+#[derive(Clone, Debug)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool)
+}
+
+// Captures as start and end indices into the characters of the text.
+type Spans = Vec<Option<(usize, usize)>>;
+
+impl Regex {
+    /// Compile a regular expression, or describe why it is invalid.
+    pub fn new(pattern: &str) -> Result<Regex, String> {
+        let mut parser = Parser { chars: pattern.chars().collect(), pos: 0, groups: 0 };
+        let node = parser.alternate()?;
+        if parser.pos < parser.chars.len() {
+            return Err(format!("unopened `)` at {}", parser.pos));
+        }
+        Ok(Regex { source: pattern.to_string(), node, groups: parser.groups })
+    }
+
+    /// Whether the expression matches anywhere in the text.
+    pub fn is_match(&self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+        self.find_at(&chars, 0).is_some()
+    }
+
+    /// The first match and its groups, starting with the whole match. Groups which did not
+    /// take part in the match are `None`.
+    pub fn captures(&self, text: &str) -> Option<Vec<Option<String>>> {
+        let chars: Vec<char> = text.chars().collect();
+        self.find_at(&chars, 0).map(|spans| {
+            spans.iter().map(|span| span.map(|(start, end)| chars[start..end].iter().collect())).collect()
+        })
+    }
+
+    /// Replace every match, from left to right, with what `replace` returns for its text.
+    pub fn replace_all<F>(&self, text: &str, mut replace: F) -> String where F: FnMut(&str) -> String {
+        let chars: Vec<char> = text.chars().collect();
+        let mut result = String::new();
+        let mut pos = 0;
+        let mut last = 0;
+        while pos <= chars.len() {
+            let (start, end) = match self.find_at(&chars, pos) {
+                Some(spans) => spans[0].expect("the whole match is always captured"),
+                None => break
+            };
+            result.extend(&chars[last..start]);
+            let matched: String = chars[start..end].iter().collect();
+            result.push_str(&replace(&matched));
+            last = end;
+            pos = if end > start { end } else { end + 1 };
+        }
+        result.extend(&chars[last..]);
+        result
+    }
+
+    // The leftmost match starting at or after `from`.
+    fn find_at(&self, chars: &[char], from: usize) -> Option<Spans> {
+        let matcher = Matcher { chars };
+        for start in from..chars.len() + 1 {
+            let mut spans = vec![None; self.groups + 1];
+            let mut end = None;
+            if matcher.node(&self.node, start, &mut spans, &mut |pos, _| {
+                end = Some(pos);
+                true
+            }) {
+                spans[0] = end.map(|end| (start, end));
+                return Some(spans);
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Debug for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Regex({:?})", self.source)
+    }
+}
+
+/// Escape the characters of `text` which have a meaning in regular expressions.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$#&-~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    groups: usize
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next(&mut self) -> Result<char, String> {
+        let c = self.peek().ok_or_else(|| "unexpected end of the expression".to_string())?;
+        self.pos += 1;
+        Ok(c)
+    }
+
+    fn alternate(&mut self) -> Result<Node, String> {
+        let mut branches = vec![self.concat()?];
+        while self.eat('|') {
+            branches.push(self.concat()?);
+        }
+        Ok(if branches.len() == 1 { branches.pop().unwrap() } else { Node::Alternate(branches) })
+    }
+
+    fn concat(&mut self) -> Result<Node, String> {
+        let mut nodes = vec![];
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let start = self.pos;
+        Ok(match self.next()? {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '[' => Node::Class(self.class()?),
+            '(' => {
+                let index = if self.eat('?') {
+                    if !self.eat(':') {
+                        return Err(format!("unsupported group at {}", start));
+                    }
+                    None
+                } else {
+                    self.groups += 1;
+                    Some(self.groups)
+                };
+                let node = self.alternate()?;
+                if !self.eat(')') {
+                    return Err(format!("unclosed `(` at {}", start));
+                }
+                Node::Group(index, Box::new(node))
+            },
+            '\\' => match self.next()? {
+                'b' => Node::WordBoundary(true),
+                'B' => Node::WordBoundary(false),
+                c => match escape_class(c) {
+                    Some(item) => Node::Class(Class { negated: false, items: vec![item] }),
+                    None => Node::Char(escaped_char(c, start)?)
+                }
+            },
+            c @ '*' | c @ '+' | c @ '?' => return Err(format!("`{}` without anything to repeat at {}", c, start)),
+            c => Node::Char(c)
+        })
+    }
+
+    fn class(&mut self) -> Result<Class, String> {
+        let start = self.pos - 1;
+        let negated = self.eat('^');
+        let mut items = vec![];
+        let mut first = true;
+        loop {
+            let c = self.next().map_err(|_| format!("unclosed `[` at {}", start))?;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let low = if c == '\\' {
+                let escaped = self.next()?;
+                if let Some(item) = escape_class(escaped) {
+                    items.push(item);
+                    continue;
+                }
+                escaped_char(escaped, self.pos - 2)?
+            } else {
+                c
+            };
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).map_or(false, |&c| c != ']') {
+                self.pos += 1;
+                let high = match self.next()? {
+                    '\\' => {
+                        let escaped = self.next()?;
+                        escaped_char(escaped, self.pos - 2)?
+                    },
+                    high => high
+                };
+                if high < low {
+                    return Err(format!("invalid range `{}-{}` at {}", low, high, start));
+                }
+                items.push(ClassItem::Range(low, high));
+            } else {
+                items.push(ClassItem::Range(low, low));
+            }
+        }
+        Ok(Class { negated, items })
+    }
+
+    fn quantified(&mut self, atom: Node) -> Result<Node, String> {
+        let start = self.pos;
+        let (min, max) = if self.eat('*') {
+            (0, None)
+        } else if self.eat('+') {
+            (1, None)
+        } else if self.eat('?') {
+            (0, Some(1))
+        } else if self.eat('{') {
+            let min = self.number().ok_or_else(|| format!("invalid repetition at {}", start))?;
+            let max = if !self.eat(',') {
+                Some(min)
+            } else if self.peek() == Some('}') {
+                None
+            } else {
+                Some(self.number().ok_or_else(|| format!("invalid repetition at {}", start))?)
+            };
+            if !self.eat('}') || max.map_or(false, |max| max < min) {
+                return Err(format!("invalid repetition at {}", start));
+            }
+            (min, max)
+        } else {
+            return Ok(atom);
+        };
+        match atom {
+            Node::Start | Node::End | Node::WordBoundary(_) =>
+                return Err(format!("an assertion can not be repeated at {}", start)),
+            _ => {}
+        }
+        let greedy = !self.eat('?');
+        Ok(Node::Repeat(Repetition { node: Box::new(atom), min, max, greedy }))
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().map_or(false, |c| c.is_digit(10)) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect::<String>().parse().ok()
+    }
+}
+
+fn escape_class(c: char) -> Option<ClassItem> {
+    match c {
+        'd' => Some(ClassItem::Digit(true)),
+        'D' => Some(ClassItem::Digit(false)),
+        'w' => Some(ClassItem::Word(true)),
+        'W' => Some(ClassItem::Word(false)),
+        's' => Some(ClassItem::Space(true)),
+        'S' => Some(ClassItem::Space(false)),
+        _ => None
+    }
+}
+
+fn escaped_char(c: char, pos: usize) -> Result<char, String> {
+    match c {
+        'n' => Ok('\n'),
+        'r' => Ok('\r'),
+        't' => Ok('\t'),
+        c if !c.is_alphanumeric() => Ok(c),
+        c => Err(format!("unsupported escape `\\{}` at {}", c, pos))
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Class {
+    fn matches(&self, c: char) -> bool {
+        let found = self.items.iter().any(|item| match *item {
+            ClassItem::Range(low, high) => low <= c && c <= high,
+            ClassItem::Digit(positive) => c.is_digit(10) == positive,
+            ClassItem::Word(positive) => is_word(c) == positive,
+            ClassItem::Space(positive) => c.is_whitespace() == positive
+        });
+        found != self.negated
+    }
+}
+
+struct Matcher<'t> {
+    chars: &'t [char]
+}
+
+impl<'t> Matcher<'t> {
+    // Whether `node` matches at `pos` followed by whatever `next` accepts, trying the
+    // alternatives in order and backtracking into `node` when `next` fails.
+    fn node(&self, node: &Node, pos: usize, spans: &mut Spans, next: &mut FnMut(usize, &mut Spans) -> bool) -> bool {
+        match *node {
+            Node::Char(c) => self.chars.get(pos) == Some(&c) && next(pos + 1, spans),
+            Node::Any => self.chars.get(pos).map_or(false, |&c| c != '\n') && next(pos + 1, spans),
+            Node::Class(ref class) => self.chars.get(pos).map_or(false, |&c| class.matches(c)) && next(pos + 1, spans),
+            Node::Start => pos == 0 && next(pos, spans),
+            Node::End => pos == self.chars.len() && next(pos, spans),
+            Node::WordBoundary(expected) => {
+                let before = pos > 0 && is_word(self.chars[pos - 1]);
+                let after = self.chars.get(pos).map_or(false, |&c| is_word(c));
+                (before != after) == expected && next(pos, spans)
+            },
+            Node::Group(index, ref node) => match index {
+                Some(index) => self.node(node, pos, spans, &mut |end, spans| {
+                    let previous = spans[index];
+                    spans[index] = Some((pos, end));
+                    if next(end, spans) {
+                        true
+                    } else {
+                        spans[index] = previous;
+                        false
+                    }
+                }),
+                None => self.node(node, pos, spans, next)
+            },
+            Node::Concat(ref nodes) => self.concat(nodes, pos, spans, next),
+            Node::Alternate(ref branches) => branches.iter().any(|branch| self.node(branch, pos, spans, next)),
+            Node::Repeat(ref repetition) => self.repeat(repetition, 0, pos, spans, next)
+        }
+    }
+
+    fn concat(&self, nodes: &[Node], pos: usize, spans: &mut Spans, next: &mut FnMut(usize, &mut Spans) -> bool)
+              -> bool {
+        match nodes.split_first() {
+            Some((first, rest)) => self.node(first, pos, spans, &mut |pos, spans| self.concat(rest, pos, spans, next)),
+            None => next(pos, spans)
+        }
+    }
+
+    // With `count` repetitions matched so far. A repetition which matches nothing ends the loop,
+    // as it would repeat forever.
+    fn repeat(&self, repetition: &Repetition, count: usize, pos: usize, spans: &mut Spans,
+              next: &mut FnMut(usize, &mut Spans) -> bool) -> bool {
+        let done = count >= repetition.min;
+        let more = repetition.max.map_or(true, |max| count < max);
+        let again = |spans: &mut Spans, next: &mut FnMut(usize, &mut Spans) -> bool| {
+            more && self.node(&repetition.node, pos, spans, &mut |end, spans| {
+                (end != pos || !done) && self.repeat(repetition, count + 1, end, spans, next)
+            })
+        };
+        // Greedy repetitions try one more first, lazy ones stopping.
+        if repetition.greedy && again(spans, next) {
+            return true;
+        }
+        if done && next(pos, spans) {
+            return true;
+        }
+        !repetition.greedy && again(spans, next)
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::io;
+use std::time::Duration;
+
+use bench::BenchResult;
+use outcome::{Outcome, Summary};
+use phase::Phase;
+
+/// The configuration of a run, as seen by a `Reporter`.
+#[derive(Clone, Copy, Debug)]
+pub struct RunInfo {
+    /// The seed tests are shuffled with, if they are. Passing it to `--seed` reproduces the
+    /// order of the run.
+    pub seed: Option<u64>
+}
+
+/// A `describe!` block, as seen by a `Reporter`.
+#[derive(Clone, Copy, Debug)]
+pub struct DescribeInfo<'a> {
+    /// The name of the block.
+    pub name: &'a str,
+    /// The names of the enclosing blocks and this one, outermost first.
+    pub path: &'a [&'a str],
+    /// The tags of the block, including inherited ones.
+    pub tags: &'a [&'a str]
+}
+
+/// A test or benchmark, as seen by a `Reporter`.
+#[derive(Clone, Copy, Debug)]
+pub struct TestInfo<'a> {
+    /// The original description.
+    pub description: &'a str,
+    /// The names of the enclosing `describe!` blocks, outermost first.
+    pub path: &'a [&'a str],
+    /// The tags, including the ones inherited from `describe!` blocks.
+    pub tags: &'a [&'a str]
+}
+
+/// The result of a finished test.
+#[derive(Clone, Copy, Debug)]
+pub struct TestResult<'a> {
+    /// Whether the test passed.
+    pub outcome: &'a Outcome,
+    /// How long the test took.
+    pub duration: Duration,
+    /// What the test printed, if output is captured.
+    pub output: &'a str,
+    /// How long each `before_each` block, the test itself and each `after_each` block took,
+    /// in that order. Empty for tests run in a process of their own.
+    pub phases: &'a [(Phase, Duration)]
+}
+
+/// Receives the events of a run, to print or record them.
+///
+/// Events are delivered in declaration order, or in the shuffled order with `--shuffle`, nested
+/// between the `describe_started` and `describe_finished` of their `describe!` blocks. When tests
+/// run in parallel a test may already have finished by the time `test_started` is delivered.
+///
+/// All methods do nothing by default, so implementations only need the ones they care about.
+pub trait Reporter {
+    /// The run is about to start.
+    fn run_started(&mut self, _run: &RunInfo) -> io::Result<()> { Ok(()) }
+
+    /// A `describe!` block containing selected tests starts.
+    fn describe_started(&mut self, _describe: &DescribeInfo) -> io::Result<()> { Ok(()) }
+
+    /// All selected tests of a `describe!` block have been reported.
+    fn describe_finished(&mut self, _describe: &DescribeInfo) -> io::Result<()> { Ok(()) }
+
+    /// A test or benchmark starts.
+    fn test_started(&mut self, _test: &TestInfo) -> io::Result<()> { Ok(()) }
+
+    /// A `before_each` or `after_each` block panicked while running a test. The test is then
+    /// reported as failed with `test_finished`.
+    fn hook_failed(&mut self, _test: &TestInfo, _hook: Phase, _message: &str) -> io::Result<()> { Ok(()) }
+
+    /// A test finished, or an unmeasured benchmark ran once.
+    fn test_finished(&mut self, _test: &TestInfo, _result: &TestResult) -> io::Result<()> { Ok(()) }
+
+    /// A benchmark was measured.
+    fn bench_finished(&mut self, _bench: &TestInfo, _result: &BenchResult) -> io::Result<()> { Ok(()) }
+
+    /// The run is over.
+    fn run_finished(&mut self, _summary: &Summary) -> io::Result<()> { Ok(()) }
+}
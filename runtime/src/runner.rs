@@ -0,0 +1,740 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::{env, process};
+use std::panic::{self, AssertUnwindSafe, UnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex, Once};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bench::{BenchResult, Bencher};
+use capture::capture;
+use isolate;
+use json::JsonReporter;
+use junit::JUnitReporter;
+use options::{Format, Options, RunIgnored, ShuffleScope, USAGE};
+use outcome::{Outcome, Summary};
+use phase::{self, Phase};
+use reporter::{DescribeInfo, Reporter, RunInfo, TestInfo, TestResult};
+use shuffle::{random_seed, Rng};
+use spec::SpecReporter;
+use tap::TapReporter;
+use suite::{test_name, Bench, Node, Serial, ShouldPanic, Suite, Test};
+
+/// One step of a run, in the order it is reported.
+enum Step<'a> {
+    Enter(&'a Suite),
+    Exit,
+    Run(usize)
+}
+
+/// Something to execute on a worker thread.
+#[derive(Clone)]
+enum Job {
+    Test(Test),
+    Bench(Bench)
+}
+
+/// A job with the information reporters need about it.
+struct Planned {
+    path: Vec<&'static str>,
+    tags: Vec<&'static str>,
+    job: Job,
+    // Jobs sharing one of these groups do not run at the same time.
+    groups: Vec<String>,
+    // Whether no other job may run at the same time.
+    alone: bool,
+    // Whether to run the job in a child process.
+    isolated: bool
+}
+
+impl Planned {
+    fn description(&self) -> &'static str {
+        match self.job {
+            Job::Test(ref test) => test.description,
+            Job::Bench(ref bench) => bench.description
+        }
+    }
+
+    fn info<'s>(&'s self) -> TestInfo<'s> {
+        TestInfo { description: self.description(), path: &self.path, tags: &self.tags }
+    }
+}
+
+/// The result of executing a `Job`.
+struct Finished {
+    outcome: Outcome,
+    duration: Duration,
+    bench: Option<BenchResult>,
+    output: String,
+    // The hook which panicked, if any.
+    failed_hook: Option<(Phase, String)>,
+    // How long the hooks and the test took.
+    phases: Vec<(Phase, Duration)>
+}
+
+/// A run of a set of suites, as planned from the `Options`.
+struct Plan<'a> {
+    steps: Vec<Step<'a>>,
+    jobs: Vec<Planned>
+}
+
+/// Runs suites with the built-in reporters chosen by the options and any number of custom ones.
+///
+/// ``` rust,ignore
+/// fn main() {
+///     Runner::new(vec![addition::__stainless_suite()])
+///         .reporter(Dashboard::connect("/tmp/dashboard.sock"))
+///         .run();
+/// }
+/// ```
+///
+/// `stainless_main!(addition; reporters: Dashboard::connect("/tmp/dashboard.sock"))` generates
+/// the same `main`.
+pub struct Runner<'a> {
+    suites: Vec<Suite>,
+    reporters: Vec<Box<Reporter + 'a>>
+}
+
+impl<'a> Runner<'a> {
+    /// A runner for the given top-level `describe!` blocks.
+    pub fn new(suites: Vec<Suite>) -> Runner<'a> {
+        Runner { suites, reporters: vec![] }
+    }
+
+    /// Add a reporter, which receives all events in addition to the built-in reporters.
+    pub fn reporter<R: Reporter + 'a>(mut self, reporter: R) -> Runner<'a> {
+        self.reporters.push(Box::new(reporter));
+        self
+    }
+
+    /// Run with the options given on the command line, and exit with the exit codes of libtest.
+    pub fn run(self) {
+        let options = match Options::parse(env::args().skip(1)) {
+            Ok(options) => options,
+            Err(message) => {
+                eprintln!("error: {}\n\n{}", message, USAGE);
+                process::exit(101);
+            }
+        };
+
+        if options.help {
+            print!("{}", USAGE);
+            return;
+        }
+
+        match self.run_with(&options, io::stdout()) {
+            Ok(ref summary) if summary.is_success() => {},
+            Ok(_) => process::exit(101),
+            Err(e) => {
+                eprintln!("error: failed to write the test report: {}", e);
+                process::exit(101);
+            }
+        }
+    }
+
+    /// Run all selected tests and return the totals.
+    ///
+    /// Results are printed to `out` in the format chosen by `--format`, written to the report
+    /// files given in the `options` and passed to the added reporters.
+    pub fn run_with<W: Write + 'a>(self, options: &Options, out: W) -> io::Result<Summary> {
+        let Runner { mut suites, reporters: custom } = self;
+
+        // Started by `isolate::run` for a single test.
+        if let Some(name) = isolate::child_test() {
+            let plan = plan(&suites, &isolate::child_options(&name));
+            let outcome = match plan.jobs.first() {
+                Some(planned) => {
+                    install_panic_hook();
+                    run_job(&planned.job, false).outcome
+                },
+                None => Outcome::Failed(format!("no test named `{}` in the test process", name))
+            };
+            isolate::exit_child(&outcome);
+        }
+
+        let seed = options.shuffle.map(|_| options.seed.unwrap_or_else(random_seed));
+        let mut rng = Rng::new(seed.unwrap_or(0));
+        if options.shuffle == Some(ShuffleScope::Describe) {
+            shuffle_suites(&mut suites, &mut rng);
+        }
+
+        let mut plan = plan(&suites, options);
+        if options.shuffle == Some(ShuffleScope::All) {
+            shuffle_plan(&mut plan, &mut rng);
+        }
+
+        if options.list {
+            if options.format == Format::Terse {
+                list_names(&plan, out)?;
+            } else {
+                list_tree(&plan, out)?;
+            }
+            return Ok(Summary::default());
+        }
+
+        let mut reporters: Vec<Box<Reporter + 'a>> = vec![match options.format {
+            Format::Spec => Box::new(SpecReporter::new(out, options.color)
+                .slowest(options.slowest)
+                .slow_threshold(options.slow_threshold)),
+            Format::Terse => Box::new(SpecReporter::terse(out, options.color)
+                .slowest(options.slowest)
+                .slow_threshold(options.slow_threshold)),
+            Format::Tap => Box::new(TapReporter::new(out)),
+            Format::Json => Box::new(JsonReporter::new(out))
+        }];
+        if let Some(ref path) = options.junit {
+            reporters.push(Box::new(JUnitReporter::new(BufWriter::new(File::create(path)?))));
+        }
+        if let Some(ref path) = options.json {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            reporters.push(Box::new(JsonReporter::new(BufWriter::new(file))));
+        }
+        reporters.extend(custom);
+
+        report(&plan, options, RunInfo { seed }, &mut reporters)
+    }
+}
+
+/// Run all selected tests of `suites` with the built-in reporters and return the totals.
+///
+/// Results are printed to `out` in the format chosen by `--format`, and written to the report
+/// files given in the `options`.
+pub fn run_suites<W: Write>(suites: &[Suite], options: &Options, out: W) -> io::Result<Summary> {
+    Runner::new(suites.to_vec()).run_with(options, out)
+}
+
+// Executes the plan, delivering the events to the reporters in the order of its steps.
+fn report<'a>(plan: &Plan, options: &Options, run: RunInfo,
+              reporters: &mut [Box<Reporter + 'a>]) -> io::Result<Summary> {
+    let start = Instant::now();
+    let mut summary = Summary::default();
+    for reporter in reporters.iter_mut() { reporter.run_started(&run)?; }
+
+    let results = execute(plan, options);
+    let mut finished = HashMap::new();
+    let mut path = vec![];
+    let mut open: Vec<&Suite> = vec![];
+
+    for step in &plan.steps {
+        match *step {
+            Step::Enter(suite) => {
+                path.push(suite.name);
+                open.push(suite);
+                let describe = DescribeInfo { name: suite.name, path: &path, tags: &suite.tags };
+                for reporter in reporters.iter_mut() { reporter.describe_started(&describe)?; }
+            },
+            Step::Exit => {
+                let suite = open.pop().unwrap();
+                {
+                    let describe = DescribeInfo { name: suite.name, path: &path, tags: &suite.tags };
+                    for reporter in reporters.iter_mut() { reporter.describe_finished(&describe)?; }
+                }
+                path.pop();
+            },
+            Step::Run(index) => {
+                let planned = &plan.jobs[index];
+                let info = planned.info();
+                for reporter in reporters.iter_mut() { reporter.test_started(&info)?; }
+
+                while !finished.contains_key(&index) {
+                    let (done, result) = results.recv()
+                        .expect("stainless worker threads exited early");
+                    finished.insert(done, result);
+                }
+                let result: Finished = finished.remove(&index).unwrap();
+
+                summary.record(&result.outcome);
+                for reporter in reporters.iter_mut() {
+                    if let Some((hook, ref message)) = result.failed_hook {
+                        reporter.hook_failed(&info, hook, message)?;
+                    }
+                    match result.bench {
+                        Some(ref bench) => reporter.bench_finished(&info, bench)?,
+                        None => reporter.test_finished(&info, &TestResult {
+                            outcome: &result.outcome,
+                            duration: result.duration,
+                            output: &result.output,
+                            phases: &result.phases
+                        })?
+                    }
+                }
+            }
+        }
+    }
+
+    summary.duration = start.elapsed();
+    for reporter in reporters.iter_mut() { reporter.run_finished(&summary)?; }
+    Ok(summary)
+}
+
+fn plan<'a>(suites: &'a [Suite], options: &Options) -> Plan<'a> {
+    let mut plan = Plan { steps: vec![], jobs: vec![] };
+    for suite in suites {
+        plan_suite(suite, &mut vec![], &mut vec![], options.isolate, options, &mut plan);
+    }
+    plan
+}
+
+// Adds the steps of `suite` to the plan, returning false if nothing in it was selected.
+//
+// `groups` are the serial groups of the enclosing blocks, and `isolated` is set if one of them
+// is `#![isolated]`.
+fn plan_suite<'a>(suite: &'a Suite, path: &mut Vec<&'static str>, groups: &mut Vec<String>,
+                  isolated: bool, options: &Options, plan: &mut Plan<'a>) -> bool {
+    let isolated = isolated || suite.isolated;
+    let steps_before = plan.steps.len();
+    let mut selected = false;
+
+    path.push(suite.name);
+    plan.steps.push(Step::Enter(suite));
+
+    let groups_before = groups.len();
+    match suite.serial {
+        Some(Serial::Block) => groups.push(path.join("::")),
+        Some(Serial::Group(group)) => groups.push(group.to_string()),
+        Some(Serial::Alone) | None => {}
+    }
+
+    for child in &suite.children {
+        match *child {
+            Node::Describe(ref nested) => {
+                selected |= plan_suite(nested, path, groups, isolated, options, plan);
+            },
+            Node::Test(ref test) => {
+                let run_ignored = options.run_ignored;
+                if options.bench ||
+                   (run_ignored == RunIgnored::Only && !test.ignored) ||
+                   !options.matches_name(path, test.description) ||
+                   !options.matches_tags(&test.tags) {
+                    continue;
+                }
+
+                let mut test = test.clone();
+                if run_ignored != RunIgnored::No { test.ignored = false; }
+
+                let mut test_groups = groups.clone();
+                if let Some(Serial::Group(group)) = test.serial {
+                    test_groups.push(group.to_string());
+                }
+
+                plan.steps.push(Step::Run(plan.jobs.len()));
+                plan.jobs.push(Planned { path: path.clone(), tags: test.tags.clone(), groups: test_groups,
+                                         alone: test.serial == Some(Serial::Alone),
+                                         isolated: isolated || test.isolated, job: Job::Test(test) });
+                selected = true;
+            },
+            Node::Bench(ref bench) => {
+                if options.run_ignored == RunIgnored::Only ||
+                   !options.matches_name(path, bench.description) ||
+                   !options.matches_tags(&suite.tags) {
+                    continue;
+                }
+
+                plan.steps.push(Step::Run(plan.jobs.len()));
+                plan.jobs.push(Planned { path: path.clone(), tags: suite.tags.clone(), groups: groups.clone(),
+                                         alone: false, isolated: false, job: Job::Bench(bench.clone()) });
+                selected = true;
+            }
+        }
+    }
+
+    path.pop();
+    groups.truncate(groups_before);
+    if selected {
+        plan.steps.push(Step::Exit);
+    } else {
+        plan.steps.truncate(steps_before);
+    }
+    selected
+}
+
+// Shuffles the children of every suite, keeping the tests of each `describe!` block together.
+fn shuffle_suites(suites: &mut [Suite], rng: &mut Rng) {
+    rng.shuffle(suites);
+    for suite in suites {
+        shuffle_children(suite, rng);
+    }
+}
+
+fn shuffle_children(suite: &mut Suite, rng: &mut Rng) {
+    rng.shuffle(&mut suite.children);
+    for child in &mut suite.children {
+        if let Node::Describe(ref mut nested) = *child {
+            shuffle_children(nested, rng);
+        }
+    }
+}
+
+// Shuffles all jobs of the plan, entering and exiting their `describe!` blocks as needed.
+fn shuffle_plan(plan: &mut Plan, rng: &mut Rng) {
+    let mut runs = vec![];
+    let mut open = vec![];
+    for step in &plan.steps {
+        match *step {
+            Step::Enter(suite) => open.push(suite),
+            Step::Exit => { open.pop(); },
+            Step::Run(index) => runs.push((index, open.clone()))
+        }
+    }
+    rng.shuffle(&mut runs);
+
+    let mut steps = vec![];
+    let mut open: Vec<&Suite> = vec![];
+    for (index, suites) in runs {
+        let common = open.iter().zip(&suites)
+            .take_while(|&(a, b)| ::std::ptr::eq(*a, *b))
+            .count();
+        for _ in common..open.len() { steps.push(Step::Exit); }
+        steps.extend(suites[common..].iter().map(|&suite| Step::Enter(suite)));
+        steps.push(Step::Run(index));
+        open = suites;
+    }
+    steps.extend(open.iter().map(|_| Step::Exit));
+    plan.steps = steps;
+}
+
+// Prints the selected part of the `describe!` tree, like the spec reporter.
+fn list_tree<W: Write>(plan: &Plan, mut out: W) -> io::Result<()> {
+    let (mut depth, mut tests, mut benches) = (0, 0, 0);
+    for step in &plan.steps {
+        match *step {
+            Step::Enter(suite) => {
+                write!(out, "{}{}", "  ".repeat(depth), suite.name)?;
+                if !suite.tags.is_empty() {
+                    write!(out, " [{}]", suite.tags.join(", "))?;
+                }
+                writeln!(out)?;
+                depth += 1;
+            },
+            Step::Exit => depth -= 1,
+            Step::Run(index) => {
+                let planned = &plan.jobs[index];
+                write!(out, "{}{}", "  ".repeat(depth), planned.description())?;
+                match planned.job {
+                    Job::Test(ref test) => {
+                        tests += 1;
+                        if test.ignored { write!(out, " (ignored)")?; }
+                    },
+                    Job::Bench(_) => {
+                        benches += 1;
+                        write!(out, " (bench)")?;
+                    }
+                }
+                if !planned.tags.is_empty() {
+                    write!(out, " [{}]", planned.tags.join(", "))?;
+                }
+                writeln!(out)?;
+            }
+        }
+    }
+    writeln!(out, "\n{} tests, {} benchmarks", tests, benches)
+}
+
+// Prints the names of the selected tests like libtest, for tools running `--list --format terse`.
+fn list_names<W: Write>(plan: &Plan, mut out: W) -> io::Result<()> {
+    let (mut tests, mut benches) = (0, 0);
+    for planned in &plan.jobs {
+        let name = test_name(&planned.path, planned.description());
+        match planned.job {
+            Job::Test(_) => {
+                tests += 1;
+                writeln!(out, "{}: test", name)?;
+            },
+            Job::Bench(_) => {
+                benches += 1;
+                writeln!(out, "{}: bench", name)?;
+            }
+        }
+    }
+    writeln!(out, "\n{} tests, {} benchmarks", tests, benches)
+}
+
+// Starts the workers executing the jobs of `plan`, returning the channel their results arrive
+// on, tagged with the index of the job.
+//
+// Once `--max-failures` tests failed, the remaining jobs are not started but still sent back as
+// `NotRun`, while tests which already started run to the end, including their `after_each`.
+fn execute(plan: &Plan, options: &Options) -> mpsc::Receiver<(usize, Finished)> {
+    install_panic_hook();
+
+    let threads = if options.bench { 1 } else { options.test_threads };
+    let (measure, capture_output) = (options.bench, !options.nocapture);
+    let max_failures = options.max_failures.unwrap_or(::std::usize::MAX);
+    let failures = Arc::new(AtomicUsize::new(0));
+
+    // Jobs are started in the order they are reported in.
+    let queue: VecDeque<Queued> = plan.steps.iter()
+        .filter_map(|step| match *step {
+            Step::Run(index) => {
+                let planned = &plan.jobs[index];
+                let isolated = if planned.isolated {
+                    Some(test_name(&planned.path, planned.description()))
+                } else {
+                    None
+                };
+                Some(Queued { index, job: planned.job.clone(), groups: planned.groups.clone(),
+                              alone: planned.alone, isolated })
+            },
+            _ => None
+        })
+        .collect();
+    let scheduler = Arc::new(Scheduler::new(queue));
+    let (sender, receiver) = mpsc::channel();
+
+    for _ in 0..threads.max(1) {
+        let scheduler = scheduler.clone();
+        let sender = sender.clone();
+        let failures = failures.clone();
+        thread::spawn(move || loop {
+            let next = scheduler.next();
+            match next {
+                Some(queued) => {
+                    let Queued { index, ref job, .. } = queued;
+                    if failures.load(Ordering::SeqCst) >= max_failures {
+                        scheduler.done(&queued);
+                        let finished = Finished { outcome: Outcome::NotRun, duration: Duration::from_secs(0),
+                                                  bench: None, output: String::new(), failed_hook: None, phases: vec![] };
+                        if sender.send((index, finished)).is_err() { break }
+                        continue;
+                    }
+
+                    let finished = if let Some(ref name) = queued.isolated {
+                        run_isolated(job, name)
+                    } else if capture_output {
+                        let (mut finished, output) = capture(|| run_job(job, measure));
+                        finished.output = output;
+                        finished
+                    } else {
+                        run_job(job, measure)
+                    };
+                    if let Outcome::Failed(_) = finished.outcome {
+                        failures.fetch_add(1, Ordering::SeqCst);
+                    }
+                    scheduler.done(&queued);
+                    if sender.send((index, finished)).is_err() { break }
+                },
+                None => break
+            }
+        });
+    }
+
+    receiver
+}
+
+/// A job waiting for a worker.
+struct Queued {
+    index: usize,
+    job: Job,
+    groups: Vec<String>,
+    alone: bool,
+    // The name of the test, if it runs in a child process.
+    isolated: Option<String>
+}
+
+/// Hands out jobs to the workers, making sure that jobs of the same serial group and jobs
+/// marked `serial` do not run alongside each other.
+struct Scheduler {
+    state: Mutex<SchedulerState>,
+    changed: Condvar
+}
+
+struct SchedulerState {
+    queue: VecDeque<Queued>,
+    running: usize,
+    alone: bool,
+    busy: HashSet<String>
+}
+
+impl Scheduler {
+    fn new(queue: VecDeque<Queued>) -> Scheduler {
+        let state = SchedulerState { queue, running: 0, alone: false, busy: HashSet::new() };
+        Scheduler { state: Mutex::new(state), changed: Condvar::new() }
+    }
+
+    // The first queued job which may start now, waiting for running jobs to finish if none
+    // can. Returns `None` once the queue is empty.
+    fn next(&self) -> Option<Queued> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if state.queue.is_empty() {
+                return None;
+            }
+
+            let ready = {
+                let state = &*state;
+                state.queue.iter().position(|queued| {
+                    !state.alone &&
+                        (!queued.alone || state.running == 0) &&
+                        !queued.groups.iter().any(|group| state.busy.contains(group))
+                })
+            };
+
+            if let Some(position) = ready {
+                let queued = state.queue.remove(position).unwrap();
+                state.running += 1;
+                state.alone = queued.alone;
+                state.busy.extend(queued.groups.iter().cloned());
+                return Some(queued);
+            }
+
+            state = self.changed.wait(state).unwrap();
+        }
+    }
+
+    // Called when a job handed out by `next` has finished.
+    fn done(&self, queued: &Queued) {
+        let mut state = self.state.lock().unwrap();
+        state.running -= 1;
+        if queued.alone { state.alone = false; }
+        for group in &queued.groups {
+            state.busy.remove(group);
+        }
+        self.changed.notify_all();
+    }
+}
+
+// Runs a test in a child process, see `isolate`.
+fn run_isolated(job: &Job, name: &str) -> Finished {
+    if let Job::Test(ref test) = *job {
+        if test.ignored {
+            return Finished { outcome: Outcome::Ignored, duration: Duration::from_secs(0),
+                              bench: None, output: String::new(), failed_hook: None, phases: vec![] };
+        }
+    }
+
+    let start = Instant::now();
+    let (outcome, output) = isolate::run(name);
+    Finished { outcome, duration: start.elapsed(), bench: None, output, failed_hook: None, phases: vec![] }
+}
+
+fn run_job(job: &Job, measure: bool) -> Finished {
+    let start = Instant::now();
+    match *job {
+        Job::Test(ref test) => {
+            if test.ignored {
+                return Finished { outcome: Outcome::Ignored, duration: Duration::from_secs(0),
+                                  bench: None, output: String::new(), failed_hook: None, phases: vec![] };
+            }
+
+            phase::start();
+            let result = catch_panic(test.run);
+            let duration = start.elapsed();
+            let phases = phase::finish();
+            let panic = result.err().map(|_| take_panic());
+
+            // A panicking hook fails the test, even if it is expected to panic.
+            let hook = phase::current();
+            match panic {
+                Some(ref panic) if hook.is_hook() => {
+                    let message = panic.to_string();
+                    Finished { outcome: Outcome::Failed(format!("{} failed: {}", hook, message)),
+                               duration, bench: None, output: String::new(),
+                               failed_hook: Some((hook, message)), phases }
+                },
+                panic => Finished { outcome: test_outcome(test.should_panic, panic), duration,
+                                    bench: None, output: String::new(), failed_hook: None, phases }
+            }
+        },
+        Job::Bench(ref bench) => {
+            let mut bencher = Bencher::new(measure);
+            let run = bench.run;
+            let result = catch_panic(AssertUnwindSafe(|| run(&mut bencher)));
+            let duration = start.elapsed();
+            match result {
+                Ok(()) => Finished { outcome: Outcome::Passed, duration, bench: bencher.result(),
+                                     output: String::new(), failed_hook: None, phases: vec![] },
+                Err(_) => Finished { outcome: Outcome::Failed(take_panic().to_string()), duration,
+                                     bench: None, output: String::new(), failed_hook: None, phases: vec![] }
+            }
+        }
+    }
+}
+
+fn test_outcome(should_panic: ShouldPanic, panic: Option<Panic>) -> Outcome {
+    match (should_panic, panic) {
+        (ShouldPanic::No, None) => Outcome::Passed,
+        (ShouldPanic::No, Some(panic)) => Outcome::Failed(panic.to_string()),
+        (ShouldPanic::Yes, Some(_)) => Outcome::Passed,
+        (ShouldPanic::WithMessage(expected), Some(ref panic)) if panic.message.contains(expected) => {
+            Outcome::Passed
+        },
+        (ShouldPanic::WithMessage(expected), Some(panic)) => {
+            Outcome::Failed(format!("panic did not include expected string '{}'\n{}", expected, panic))
+        },
+        (_, None) => Outcome::Failed("test did not panic as expected".to_string())
+    }
+}
+
+/// A panic caught while running a test.
+struct Panic {
+    message: String,
+    location: String
+}
+
+impl ::std::fmt::Display for Panic {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "panicked at '{}', {}", self.message, self.location)
+    }
+}
+
+thread_local!(static LAST_PANIC: RefCell<Option<Panic>> = RefCell::new(None));
+
+thread_local!(static IN_JOB: Cell<bool> = Cell::new(false));
+
+// Runs a test or benchmark, catching its panic.
+fn catch_panic<R, F: FnOnce() -> R + UnwindSafe>(f: F) -> thread::Result<R> {
+    IN_JOB.with(|in_job| in_job.set(true));
+    let result = panic::catch_unwind(f);
+    IN_JOB.with(|in_job| in_job.set(false));
+    result
+}
+
+// Wraps the default hook, which would print every expected panic of a `failing` test, with one
+// that remembers the panics of tests for the reporter. Panics outside of tests still go to the
+// previous hook.
+fn install_panic_hook() {
+    #[allow(deprecated)]
+    static INSTALL: Once = ::std::sync::ONCE_INIT;
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if IN_JOB.with(|in_job| in_job.get()) {
+                record_panic(info);
+            } else {
+                previous(info);
+            }
+        }));
+    });
+}
+
+#[allow(deprecated)]
+fn record_panic(info: &panic::PanicInfo) {
+    let message = match info.payload().downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match info.payload().downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "Box<Any>".to_string()
+        }
+    };
+    let location = info.location()
+        .map(|l| format!("{}:{}", l.file(), l.line()))
+        .unwrap_or_default();
+
+    LAST_PANIC.with(|last| *last.borrow_mut() = Some(Panic { message, location }));
+}
+
+fn take_panic() -> Panic {
+    LAST_PANIC.with(|last| last.borrow_mut().take()).unwrap_or(Panic {
+        message: "unknown panic".to_string(),
+        location: String::new()
+    })
+}
@@ -0,0 +1,44 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A small deterministic random number generator (SplitMix64), so that a seed reproduces the
+/// same order on every platform and version of Rust.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Shuffle the slice in place (Fisher-Yates).
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+/// A seed for runs without `--seed`.
+pub fn random_seed() -> u64 {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs() ^ u64::from(time.subsec_nanos()))
+        .unwrap_or(0);
+    // The stack address differs between processes started at the same time.
+    let stack = &nanos as *const u64 as u64;
+    // Seeds are printed for reproducing a run, keep them short enough to type.
+    Rng::new(nanos ^ stack).next_u64() % 1_000_000_000
+}
@@ -0,0 +1,251 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use bench::BenchResult;
+use outcome::{format_duration, Outcome, Summary};
+use reporter::{DescribeInfo, Reporter, RunInfo, TestInfo, TestResult};
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// Prints the `describe!` tree with the original descriptions, like:
+///
+/// ```text
+/// stainless
+///   ✓ makes organizing tests easy (1ms)
+///   nesting
+///     ✗ makes it simple to categorize tests (0ms)
+///     - is not written yet
+/// ```
+///
+/// followed by every failure with its full describe path and the totals.
+///
+/// Optionally the slowest tests and `describe!` blocks are listed before the totals, and tests
+/// taking longer than a threshold are marked `slow`.
+pub struct SpecReporter<W: Write> {
+    out: W,
+    color: bool,
+    terse: bool,
+    seed: Option<u64>,
+    slowest: usize,
+    slow_threshold: Option<Duration>,
+    failures: Vec<Failure>,
+    // The path, duration and time spent in hooks of every test.
+    timings: Vec<(String, Duration, Duration)>,
+    // The total duration of the tests of every `describe!` block, by path.
+    scopes: BTreeMap<String, Duration>
+}
+
+struct Failure {
+    path: String,
+    message: String,
+    output: String
+}
+
+impl<W: Write> SpecReporter<W> {
+    /// Create a reporter writing to `out`, using ANSI colors if `color` is set.
+    pub fn new(out: W, color: bool) -> SpecReporter<W> {
+        SpecReporter {
+            out,
+            color,
+            terse: false,
+            seed: None,
+            slowest: 0,
+            slow_threshold: None,
+            failures: vec![],
+            timings: vec![],
+            scopes: BTreeMap::new()
+        }
+    }
+
+    /// Create a reporter printing a `.`, `F` or `i` per test instead of the tree, followed by
+    /// the same failures and totals.
+    pub fn terse(out: W, color: bool) -> SpecReporter<W> {
+        SpecReporter { terse: true, ..SpecReporter::new(out, color) }
+    }
+
+    /// List the `count` slowest tests and `describe!` blocks after the run.
+    pub fn slowest(mut self, count: usize) -> SpecReporter<W> {
+        self.slowest = count;
+        self
+    }
+
+    /// Mark tests taking longer than `threshold` as slow.
+    pub fn slow_threshold(mut self, threshold: Option<Duration>) -> SpecReporter<W> {
+        self.slow_threshold = threshold;
+        self
+    }
+
+    fn print_slowest(&mut self) -> io::Result<()> {
+        let mut timings = self.timings.clone();
+        timings.sort_by_key(|&(_, duration, _)| Reverse(duration));
+        writeln!(self.out, "\nslowest tests:")?;
+        for &(ref path, duration, hooks) in timings.iter().take(self.slowest) {
+            write!(self.out, "  {:>7}  {}", format_duration(duration), path)?;
+            if hooks > Duration::from_secs(0) {
+                write!(self.out, " (hooks {})", format_duration(hooks))?;
+            }
+            writeln!(self.out)?;
+        }
+
+        let mut scopes: Vec<(&String, &Duration)> = self.scopes.iter().collect();
+        scopes.sort_by_key(|&(_, duration)| Reverse(*duration));
+        writeln!(self.out, "\nslowest describe! blocks:")?;
+        for &(path, duration) in scopes.iter().take(self.slowest) {
+            writeln!(self.out, "  {:>7}  {}", format_duration(*duration), path)?;
+        }
+        Ok(())
+    }
+}
+
+// `text` in `color`, if colors are `enabled`.
+fn paint(enabled: bool, color: &str, text: &str) -> String {
+    if enabled {
+        format!("{}{}{}", color, text, RESET)
+    } else {
+        text.to_string()
+    }
+}
+
+fn indent(depth: usize) -> String {
+    "  ".repeat(depth)
+}
+
+impl<W: Write> Reporter for SpecReporter<W> {
+    fn run_started(&mut self, run: &RunInfo) -> io::Result<()> {
+        self.seed = run.seed;
+        match run.seed {
+            Some(seed) => writeln!(self.out, "Shuffled with seed {}\n", seed),
+            None => Ok(())
+        }
+    }
+
+    fn describe_started(&mut self, describe: &DescribeInfo) -> io::Result<()> {
+        if self.terse { return Ok(()) }
+        writeln!(self.out, "{}{}", indent(describe.path.len() - 1), describe.name)
+    }
+
+    fn test_finished(&mut self, test: &TestInfo, result: &TestResult) -> io::Result<()> {
+        let (marker, color) = match *result.outcome {
+            Outcome::Passed => ("✓", GREEN),
+            Outcome::Failed(_) => ("✗", RED),
+            Outcome::Ignored => ("-", YELLOW),
+            Outcome::NotRun => ("-", DIM)
+        };
+
+        let mut path = test.path.to_vec();
+        path.push(test.description);
+        if let Outcome::Failed(ref message) = *result.outcome {
+            self.failures.push(Failure {
+                path: path.join(" > "),
+                message: message.clone(),
+                output: result.output.to_string()
+            });
+        }
+
+        let ran = match *result.outcome {
+            Outcome::Passed | Outcome::Failed(_) => true,
+            Outcome::Ignored | Outcome::NotRun => false
+        };
+        if ran {
+            let hooks = result.phases.iter()
+                .filter(|&&(phase, _)| phase.is_hook())
+                .map(|&(_, duration)| duration)
+                .sum();
+            self.timings.push((path.join(" > "), result.duration, hooks));
+            for depth in 1..test.path.len() + 1 {
+                *self.scopes.entry(test.path[..depth].join(" > ")).or_insert(Duration::new(0, 0)) += result.duration;
+            }
+        }
+        let slow = ran && self.slow_threshold.map_or(false, |threshold| result.duration > threshold);
+
+        if self.terse {
+            let marker = match *result.outcome {
+                Outcome::Passed => ".",
+                Outcome::Failed(_) => "F",
+                Outcome::Ignored => "i",
+                Outcome::NotRun => "-"
+            };
+            let marker = paint(self.color, if slow { YELLOW } else { color }, marker);
+            write!(self.out, "{}", marker)?;
+            return self.out.flush();
+        }
+
+        write!(self.out, "{}{} {}", indent(test.path.len()), paint(self.color, color, marker), test.description)?;
+        match *result.outcome {
+            Outcome::Ignored => {},
+            Outcome::NotRun => write!(self.out, " {}", paint(self.color, DIM, "(not run)"))?,
+            _ if slow => {
+                let timing = format!("({}, slow)", format_duration(result.duration));
+                write!(self.out, " {}", paint(self.color, YELLOW, &timing))?;
+            },
+            _ => {
+                let timing = format!("({})", format_duration(result.duration));
+                write!(self.out, " {}", paint(self.color, DIM, &timing))?;
+            }
+        }
+        writeln!(self.out)
+    }
+
+    fn bench_finished(&mut self, bench: &TestInfo, result: &BenchResult) -> io::Result<()> {
+        if self.terse {
+            return writeln!(self.out, "\n{}: {} ns/iter (+/- {})", bench.description,
+                            result.ns_per_iter, result.variance);
+        }
+        writeln!(self.out, "{}{} {}: {} ns/iter (+/- {})", indent(bench.path.len()),
+                 paint(self.color, GREEN, "✓"), bench.description, result.ns_per_iter, result.variance)
+    }
+
+    fn run_finished(&mut self, summary: &Summary) -> io::Result<()> {
+        if self.terse { writeln!(self.out)?; }
+        if !self.failures.is_empty() {
+            writeln!(self.out, "\nfailures:")?;
+            for (i, failure) in self.failures.iter().enumerate() {
+                writeln!(self.out, "\n  {}) {}", i + 1, failure.path)?;
+                for line in failure.message.lines() {
+                    writeln!(self.out, "     {}", line)?;
+                }
+                if !failure.output.is_empty() {
+                    writeln!(self.out, "     ---- output ----")?;
+                    for line in failure.output.lines() {
+                        writeln!(self.out, "     {}", line)?;
+                    }
+                }
+            }
+
+            if let Some(seed) = self.seed {
+                writeln!(self.out, "\nShuffled with seed {}, rerun with `--seed {}` to reproduce the order.",
+                         seed, seed)?;
+            }
+        }
+
+        if self.slowest > 0 && !self.timings.is_empty() {
+            self.print_slowest()?;
+        }
+
+        let passed = format!("{} passing", summary.passed);
+        let failed = format!("{} failing", summary.failed);
+        let ignored = format!("{} pending", summary.ignored);
+        write!(self.out, "\n{}, {}, {}",
+               paint(self.color, GREEN, &passed),
+               paint(self.color, if summary.failed > 0 { RED } else { DIM }, &failed),
+               paint(self.color, if summary.ignored > 0 { YELLOW } else { DIM }, &ignored))?;
+        if summary.not_run > 0 {
+            let not_run = format!("{} not run", summary.not_run);
+            write!(self.out, ", {}", paint(self.color, DIM, &not_run))?;
+        }
+        writeln!(self.out, " ({})", format_duration(summary.duration))?;
+        self.out.flush()
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::fmt;
+
+use bench::Bencher;
+
+/// A `describe!` block registered with the runtime.
+///
+/// In harness mode every `describe!` generates a `__stainless_suite()` function returning
+/// its `Suite`, and `stainless_main!` hands the top-level ones to the runner.
+#[derive(Clone, Debug)]
+pub struct Suite {
+    /// The name of the `describe!` block.
+    pub name: &'static str,
+    /// The tags of the block, including inherited ones.
+    pub tags: Vec<&'static str>,
+    /// Whether the tests of the block must not run concurrently, from `#![serial]`.
+    pub serial: Option<Serial>,
+    /// Whether all tests of the block run in their own process, from `#![isolated]`.
+    pub isolated: bool,
+    /// Tests, benchmarks and nested blocks in declaration order.
+    pub children: Vec<Node>
+}
+
+/// An entry of a `Suite`.
+#[derive(Clone, Debug)]
+pub enum Node {
+    /// A nested `describe!` block.
+    Describe(Suite),
+    /// An `it`, `failing` or `ignore` block.
+    Test(Test),
+    /// A `bench` block.
+    Bench(Bench)
+}
+
+/// Whether a test is expected to panic, from `failing`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShouldPanic {
+    /// A regular test.
+    No,
+    /// `failing`, passes on any panic.
+    Yes,
+    /// `failing("message")`, passes on a panic whose message contains `message`.
+    WithMessage(&'static str)
+}
+
+/// Which tests must not run at the same time, from `serial` or `serial(group)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Serial {
+    /// `serial` on a test: no other test runs at the same time.
+    Alone,
+    /// `#![serial]` on a `describe!` block: its tests, including the ones of nested blocks, run
+    /// one at a time, but may run alongside tests of other blocks.
+    Block,
+    /// `serial(group)`: no two tests of the group run at the same time.
+    Group(&'static str)
+}
+
+/// A test registered with the runtime.
+#[derive(Clone, Debug)]
+pub struct Test {
+    /// The original description of the test.
+    pub description: &'static str,
+    /// The tags of the test, including the ones inherited from `describe!` blocks.
+    pub tags: Vec<&'static str>,
+    /// Whether the test is marked `ignore`.
+    pub ignored: bool,
+    /// Whether the test is expected to panic.
+    pub should_panic: ShouldPanic,
+    /// Whether the test must not run concurrently with other tests.
+    pub serial: Option<Serial>,
+    /// Whether the test runs in its own process, from `isolated`.
+    pub isolated: bool,
+    /// The generated test function, with hooks spliced in.
+    pub run: fn()
+}
+
+/// A benchmark registered with the runtime.
+#[derive(Clone)]
+pub struct Bench {
+    /// The original description of the benchmark.
+    pub description: &'static str,
+    /// The generated benchmark function.
+    pub run: fn(&mut Bencher)
+}
+
+impl fmt::Debug for Bench {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Bench").field("description", &self.description).finish()
+    }
+}
+
+impl Suite {
+    /// An empty `describe!` block.
+    pub fn new(name: &'static str) -> Suite {
+        Suite { name, tags: vec![], serial: None, isolated: false, children: vec![] }
+    }
+
+    /// Add a tag to the block.
+    pub fn tag(mut self, tag: &'static str) -> Suite {
+        self.tags.push(tag);
+        self
+    }
+
+    /// Mark the block `#![serial]`, or `#![serial(group)]` to put all of its tests in a group.
+    pub fn serial(mut self, group: Option<&'static str>) -> Suite {
+        self.serial = Some(group.map_or(Serial::Block, Serial::Group));
+        self
+    }
+
+    /// Mark the block `#![isolated]`.
+    pub fn isolated(mut self) -> Suite {
+        self.isolated = true;
+        self
+    }
+
+    /// Register a nested `describe!` block.
+    pub fn describe(&mut self, suite: Suite) {
+        self.children.push(Node::Describe(suite));
+    }
+
+    /// Register a test.
+    pub fn test(&mut self, test: Test) {
+        self.children.push(Node::Test(test));
+    }
+
+    /// Register a benchmark.
+    pub fn bench(&mut self, bench: Bench) {
+        self.children.push(Node::Bench(bench));
+    }
+}
+
+impl Test {
+    /// A regular test running `run`.
+    pub fn new(description: &'static str, run: fn()) -> Test {
+        Test {
+            description,
+            tags: vec![],
+            ignored: false,
+            should_panic: ShouldPanic::No,
+            serial: None,
+            isolated: false,
+            run
+        }
+    }
+
+    /// Add a tag to the test.
+    pub fn tag(mut self, tag: &'static str) -> Test {
+        self.tags.push(tag);
+        self
+    }
+
+    /// Mark the test as `ignore`.
+    pub fn ignored(mut self) -> Test {
+        self.ignored = true;
+        self
+    }
+
+    /// Mark the test `serial`, or `serial(group)` to put it in a group.
+    pub fn serial(mut self, group: Option<&'static str>) -> Test {
+        self.serial = Some(group.map_or(Serial::Alone, Serial::Group));
+        self
+    }
+
+    /// Mark the test `isolated`.
+    pub fn isolated(mut self) -> Test {
+        self.isolated = true;
+        self
+    }
+
+    /// Mark the test as `failing`, optionally with the expected panic message.
+    pub fn should_panic(mut self, message: Option<&'static str>) -> Test {
+        self.should_panic = match message {
+            Some(message) => ShouldPanic::WithMessage(message),
+            None => ShouldPanic::Yes
+        };
+        self
+    }
+}
+
+impl Bench {
+    /// A benchmark running `run`.
+    pub fn new(description: &'static str, run: fn(&mut Bencher)) -> Bench {
+        Bench { description, run }
+    }
+}
+
+/// The libtest-style name of a test: the module path followed by the mangled description.
+pub fn test_name(path: &[&str], description: &str) -> String {
+    let mut name = path.join("::");
+    name.push_str("::");
+    name.push_str(&description.replace(" ", "_"));
+    name
+}
@@ -0,0 +1,171 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::fmt;
+
+/// A boolean expression over test tags, as given to `--tags` and `--exclude-tags`.
+///
+/// Tags are combined with `and`, `or` and `not` (or `&`, `|` and `!`), grouped with
+/// parentheses. A comma is the same as `or`, so `--tags slow,db` selects tests tagged with
+/// either tag. `not` binds tighter than `and`, which binds tighter than `or`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TagExpr {
+    /// Matches tests with this tag.
+    Tag(String),
+    /// Matches tests not matched by the inner expression.
+    Not(Box<TagExpr>),
+    /// Matches tests matched by both expressions.
+    And(Box<TagExpr>, Box<TagExpr>),
+    /// Matches tests matched by either expression.
+    Or(Box<TagExpr>, Box<TagExpr>)
+}
+
+impl TagExpr {
+    /// Parse a tag expression such as `slow and not (db or network)`.
+    pub fn parse(input: &str) -> Result<TagExpr, String> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens: &tokens, pos: 0 };
+
+        let expr = parser.parse_or()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected `{}` in tag expression `{}`", token, input))
+        }
+    }
+
+    /// Whether a test with the given tags is matched by this expression.
+    pub fn matches<S: AsRef<str>>(&self, tags: &[S]) -> bool {
+        match *self {
+            TagExpr::Tag(ref tag) => tags.iter().any(|t| t.as_ref() == tag),
+            TagExpr::Not(ref inner) => !inner.matches(tags),
+            TagExpr::And(ref left, ref right) => left.matches(tags) && right.matches(tags),
+            TagExpr::Or(ref left, ref right) => left.matches(tags) || right.matches(tags)
+        }
+    }
+}
+
+impl fmt::Display for TagExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TagExpr::Tag(ref tag) => write!(f, "{}", tag),
+            TagExpr::Not(ref inner) => write!(f, "not {}", inner),
+            TagExpr::And(ref left, ref right) => write!(f, "({} and {})", left, right),
+            TagExpr::Or(ref left, ref right) => write!(f, "({} or {})", left, right)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Tag(String),
+    And,
+    Or,
+    Not,
+    Open,
+    Close
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Token::Tag(ref tag) => write!(f, "{}", tag),
+            Token::And => write!(f, "and"),
+            Token::Or => write!(f, "or"),
+            Token::Not => write!(f, "not"),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")")
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => { chars.next(); },
+            '(' => { chars.next(); tokens.push(Token::Open) },
+            ')' => { chars.next(); tokens.push(Token::Close) },
+            '&' => { chars.next(); tokens.push(Token::And) },
+            '|' | ',' => { chars.next(); tokens.push(Token::Or) },
+            '!' => { chars.next(); tokens.push(Token::Not) },
+            c if is_tag_char(c) => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !is_tag_char(c) { break }
+                    word.push(c);
+                    chars.next();
+                }
+
+                tokens.push(match &*word {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Tag(word)
+                });
+            },
+            other => return Err(format!("unexpected character `{}` in tag expression `{}`", other, input))
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<TagExpr, String> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            expr = TagExpr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<TagExpr, String> {
+        let mut expr = self.parse_not()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            expr = TagExpr::And(Box::new(expr), Box::new(self.parse_not()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<TagExpr, String> {
+        match self.next().cloned() {
+            Some(Token::Not) => Ok(TagExpr::Not(Box::new(self.parse_not()?))),
+            Some(Token::Tag(tag)) => Ok(TagExpr::Tag(tag)),
+            Some(Token::Open) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(&Token::Close) => Ok(expr),
+                    _ => Err("unclosed `(` in tag expression".to_string())
+                }
+            },
+            Some(token) => Err(format!("expected a tag, found `{}`", token)),
+            None => Err("expected a tag, found the end of the expression".to_string())
+        }
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+use bench::BenchResult;
+use outcome::{secs_f64, Outcome, Summary};
+use reporter::{DescribeInfo, Reporter, RunInfo, TestInfo, TestResult};
+
+/// Streams [TAP version 14](https://testanything.org/tap-version-14-specification.html), with
+/// every `describe!` block as a nested subtest:
+///
+/// ```text
+/// TAP version 14
+/// # Subtest: stainless
+///     ok 1 - makes organizing tests easy
+///     not ok 2 - fails
+///       ---
+///       message: |-
+///         panicked at 'assertion failed: false', tests/spec.rs:12:8
+///       duration_ms: 0.052
+///       ...
+///     1..2
+/// not ok 1 - stainless
+/// 1..1
+/// ```
+///
+/// Ignored tests are reported with a `# SKIP` directive and measured benchmarks carry their
+/// measurement in a YAML block.
+pub struct TapReporter<W: Write> {
+    out: W,
+    // The number of test points and whether any failed, for each open subtest.
+    frames: Vec<(usize, bool)>
+}
+
+impl<W: Write> TapReporter<W> {
+    /// Create a reporter writing to `out`.
+    pub fn new(out: W) -> TapReporter<W> {
+        TapReporter { out, frames: vec![(0, false)] }
+    }
+
+    fn indent(&self) -> String {
+        "    ".repeat(self.frames.len() - 1)
+    }
+
+    // Writes a test point into the innermost subtest.
+    fn point(&mut self, ok: bool, description: &str, directive: &str) -> io::Result<()> {
+        let indent = self.indent();
+        let frame = self.frames.last_mut().unwrap();
+        frame.0 += 1;
+        frame.1 |= !ok;
+
+        writeln!(self.out, "{}{}ok {} - {}{}", indent, if ok { "" } else { "not " }, frame.0,
+                 escape(description), directive)
+    }
+
+    // Writes a YAML diagnostic block for the last test point.
+    fn yaml(&mut self, fields: &[(&str, String)]) -> io::Result<()> {
+        let indent = format!("{}  ", self.indent());
+        writeln!(self.out, "{}---", indent)?;
+        for &(key, ref value) in fields {
+            if value.contains('\n') || value.is_empty() {
+                writeln!(self.out, "{}{}: |-", indent, key)?;
+                for line in value.lines() {
+                    writeln!(self.out, "{}  {}", indent, line)?;
+                }
+            } else {
+                writeln!(self.out, "{}{}: {}", indent, key, value)?;
+            }
+        }
+        writeln!(self.out, "{}...", indent)
+    }
+}
+
+impl<W: Write> Reporter for TapReporter<W> {
+    fn run_started(&mut self, run: &RunInfo) -> io::Result<()> {
+        writeln!(self.out, "TAP version 14")?;
+        match run.seed {
+            Some(seed) => writeln!(self.out, "# Shuffled with seed {}", seed),
+            None => Ok(())
+        }
+    }
+
+    fn describe_started(&mut self, describe: &DescribeInfo) -> io::Result<()> {
+        let indent = self.indent();
+        self.frames.push((0, false));
+        writeln!(self.out, "{}# Subtest: {}", indent, describe.name)
+    }
+
+    fn describe_finished(&mut self, describe: &DescribeInfo) -> io::Result<()> {
+        let indent = self.indent();
+        let (count, failed) = self.frames.pop().unwrap();
+        writeln!(self.out, "{}1..{}", indent, count)?;
+        self.point(!failed, describe.name, "")
+    }
+
+    fn test_finished(&mut self, test: &TestInfo, result: &TestResult) -> io::Result<()> {
+        match *result.outcome {
+            Outcome::Passed => self.point(true, test.description, ""),
+            Outcome::Ignored => self.point(true, test.description, " # SKIP ignored"),
+            Outcome::NotRun => self.point(true, test.description, " # SKIP not run"),
+            Outcome::Failed(ref message) => {
+                self.point(false, test.description, "")?;
+
+                let mut fields = vec![
+                    ("message", quote(message)),
+                    ("duration_ms", millis(result.duration))
+                ];
+                if !result.output.is_empty() {
+                    fields.push(("output", result.output.to_string()));
+                }
+                self.yaml(&fields)
+            }
+        }
+    }
+
+    fn bench_finished(&mut self, bench: &TestInfo, result: &BenchResult) -> io::Result<()> {
+        self.point(true, bench.description, "")?;
+        self.yaml(&[
+            ("ns_per_iter", result.ns_per_iter.to_string()),
+            ("variance", result.variance.to_string())
+        ])
+    }
+
+    fn run_finished(&mut self, _summary: &Summary) -> io::Result<()> {
+        let count = self.frames[0].0;
+        writeln!(self.out, "1..{}", count)?;
+        self.out.flush()
+    }
+}
+
+/// Escape a description for use in a test point.
+fn escape(description: &str) -> String {
+    description.replace('\\', "\\\\").replace('#', "\\#").replace('\n', " ")
+}
+
+// Single line messages are quoted so that YAML does not interpret them.
+fn quote(message: &str) -> String {
+    if message.contains('\n') {
+        message.to_string()
+    } else {
+        format!("\"{}\"", message.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.3}", secs_f64(duration) * 1000.0)
+}
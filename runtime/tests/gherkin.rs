@@ -0,0 +1,128 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+extern crate stainless_runtime;
+
+use std::panic;
+
+use stainless_runtime::{Scenario, Step, StepKind, Steps};
+
+// "Eating one" of
+//
+//     Feature: Eating cucumbers
+//       Background:
+//         Given a basket
+//
+//       Scenario: Eating one
+//         Given there are 5 cucumbers
+//         ...
+fn eating_one() -> Scenario {
+    Scenario::new("Eating one", 6)
+        .step(Step::new(StepKind::Given, "Given", "a basket", 4))
+        .step(Step::new(StepKind::Given, "Given", "there are 5 cucumbers", 7))
+        .step(Step::new(StepKind::When, "When", "I eat 1 cucumber", 8))
+        .step(Step::new(StepKind::Then, "Then", "I should have 4 cucumbers", 9))
+        .step(Step::new(StepKind::Then, "But", "the basket should not be empty", 10))
+}
+
+#[derive(Default)]
+struct Basket {
+    cucumbers: i32,
+    log: Vec<String>
+}
+
+fn steps() -> Steps<Basket> {
+    Steps::<Basket>::new()
+        .given("a basket", |basket, _| basket.log.push("basket".to_string()))
+        .given("there are {int} cucumbers", |basket, args| basket.cucumbers = args.get(0))
+        .when(r"^I eat (\d+) cucumbers?$", |basket, args| basket.cucumbers -= args.get::<i32>(0))
+        .then("I should have {int} cucumber(s)", |basket, args| assert_eq!(basket.cucumbers, args.get::<i32>(0)))
+        .then("the basket should not be empty", |basket, _| assert!(basket.cucumbers > 0))
+}
+
+fn panic_message(result: std::thread::Result<()>) -> String {
+    let payload = result.unwrap_err();
+    payload.downcast_ref::<String>().cloned().unwrap_or_default()
+}
+
+#[test]
+fn runs_scenarios_with_a_new_world() {
+    steps().run(&eating_one(), "eating.feature");
+
+    let logging = Steps::<Basket>::new().step("{}", |basket, args| {
+        basket.log.push(args.str(0).to_string());
+        assert!(basket.log.len() <= 5);
+    });
+    logging.run(&eating_one(), "eating.feature");
+    logging.run(&eating_one(), "eating.feature");
+}
+
+#[test]
+fn passes_tables_and_doc_strings() {
+    let scenario = Scenario::new("Prices", 1)
+        .step(Step::new(StepKind::Given, "Given", "the recipe:", 2).docstring("Slice\n  thinly"))
+        .step(Step::new(StepKind::Given, "And", "the prices", 6).row(&["kind", "price"]).row(&["pickled", "2"]));
+    Steps::<Basket>::new()
+        .given("the recipe:", |_, args| {
+            assert_eq!(args.docstring(), Some("Slice\n  thinly"));
+            assert!(args.table().is_empty());
+        })
+        .given("the prices", |_, args| {
+            assert_eq!(args.table(), &[vec!["kind".to_string(), "price".to_string()],
+                                       vec!["pickled".to_string(), "2".to_string()]]);
+            assert_eq!(args.docstring(), None);
+        })
+        .run(&scenario, "prices.feature");
+}
+
+#[test]
+fn labels_failures_with_the_step() {
+    let scenario = Scenario::new("S", 2)
+        .step(Step::new(StepKind::Given, "Given", "there are 5 cucumbers", 3))
+        .step(Step::new(StepKind::Then, "Then", "I should have 3 cucumbers", 4));
+    let message = panic_message(panic::catch_unwind(|| steps().run(&scenario, "f.feature")));
+    assert!(message.starts_with("step failed: Then I should have 3 cucumbers (f.feature:4)\n"), "{}", message);
+    assert!(message.contains("assertion") && message.contains("left"), "{}", message);
+}
+
+#[test]
+fn reports_undefined_steps_before_running() {
+    let scenario = Scenario::new("S", 2)
+        .step(Step::new(StepKind::Given, "Given", "there are 5 cucumbers", 3))
+        .step(Step::new(StepKind::When, "When", "I sell 2 \"pickled\" jars", 4))
+        .step(Step::new(StepKind::Then, "Then", "I earn 3.5 (euros)", 5));
+    let message = panic_message(panic::catch_unwind(|| {
+        Steps::<Basket>::new().given("there are {int} cucumbers", |_, _| panic!("ran")).run(&scenario, "f.feature")
+    }));
+    assert_eq!(message, r#"2 undefined steps in the scenario "S" (f.feature:2):
+    When I sell 2 "pickled" jars (f.feature:4)
+    Then I earn 3.5 (euros) (f.feature:5)
+
+Define them like this:
+
+    .when("I sell {int} {string} jars", |_world, _args| unimplemented!())
+    .then("I earn {float} \\(euros)", |_world, _args| unimplemented!())
+"#);
+}
+
+#[test]
+fn rejects_ambiguous_steps() {
+    let scenario = Scenario::new("S", 2).step(Step::new(StepKind::Given, "Given", "a basket", 3));
+    let message = panic_message(panic::catch_unwind(|| {
+        steps().given("a {word}", |_, _| {}).run(&scenario, "f.feature")
+    }));
+    assert_eq!(message, "the step `Given a basket` (f.feature:3) is ambiguous, it matches `a basket`, `a {word}`");
+}
+
+#[test]
+fn matches_expressions() {
+    let scenario = Scenario::new("S", 2)
+        .step(Step::new(StepKind::Given, "Given", "the \"red\" word is -1.5 and {escaped}", 3));
+    steps().given(r#"the {string} {} is {float} and \{escaped}"#, |_, args| {
+        assert_eq!(args.captures(), &["red".to_string(), "word".to_string(), "-1.5".to_string()]);
+        assert_eq!(args.get::<f64>(2), -1.5);
+    }).run(&scenario, "f.feature");
+}
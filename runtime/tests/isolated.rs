@@ -0,0 +1,73 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+// Runs without libtest: the runner re-executes this binary for every isolated test.
+
+extern crate stainless_runtime;
+
+use std::process;
+
+use stainless_runtime::{run_suites, Options, Suite, Test};
+
+fn passes() { println!("to stdout"); eprintln!("to stderr"); }
+fn panics() { panic!("inside of the child"); }
+fn exits() { process::exit(3); }
+fn exits_successfully() { process::exit(0); }
+fn panics_as_expected() { panic!("expected"); }
+#[cfg(unix)]
+fn aborts() { process::abort(); }
+fn is_a_child() { assert!(::std::env::var_os("STAINLESS_ISOLATED_TEST").is_some()); }
+fn is_not_a_child() { assert!(::std::env::var_os("STAINLESS_ISOLATED_TEST").is_none()); }
+
+fn suites() -> Vec<Suite> {
+    let mut isolated = Suite::new("isolated").isolated();
+    isolated.test(Test::new("passes", passes));
+    isolated.test(Test::new("panics", panics));
+    isolated.test(Test::new("exits", exits));
+    isolated.test(Test::new("exits successfully", exits_successfully));
+    isolated.test(Test::new("panics as expected", panics_as_expected).should_panic(Some("expected")));
+    #[cfg(unix)]
+    isolated.test(Test::new("aborts", aborts));
+
+    let mut mixed = Suite::new("mixed");
+    mixed.test(Test::new("is a child", is_a_child).isolated());
+    mixed.test(Test::new("is not a child", is_not_a_child));
+
+    vec![isolated, mixed]
+}
+
+fn run(args: &[&str]) -> (usize, usize, String) {
+    let mut args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    args.extend(vec!["--color".to_string(), "never".to_string()]);
+
+    let mut out = vec![];
+    let summary = run_suites(&suites(), &Options::parse(args).unwrap(), &mut out).unwrap();
+    (summary.passed, summary.failed, String::from_utf8(out).unwrap())
+}
+
+fn main() {
+    // In the child processes `run_suites` runs the single test and exits.
+    let (passed, failed, out) = run(&["--jobs", "4"]);
+
+    assert_eq!((passed, failed), (4, if cfg!(unix) { 4 } else { 3 }), "{}", out);
+    assert!(out.contains("     panicked at 'inside of the child'"), "{}", out);
+    assert!(out.contains("     test process exited with code 3 before the test finished"), "{}", out);
+    assert!(out.contains("     test process exited with code 0 before the test finished"), "{}", out);
+    if cfg!(unix) {
+        assert!(out.contains("     test process was killed by signal 6 (SIGABRT)"), "{}", out);
+    }
+    assert!(out.contains("  ✓ is not a child"), "{}", out);
+
+    // `--isolate` runs every test in its own process.
+    let (passed, failed, out) = run(&["--isolate", "mixed::"]);
+    assert_eq!((passed, failed), (1, 1), "{}", out);
+    assert!(out.contains("  ✗ is not a child"), "{}", out);
+
+    let (_, _, out) = run(&["--format", "json", "isolated::passes"]);
+    assert!(out.contains(r#""output":"to stdout\nto stderr\n""#), "{}", out);
+
+    println!("isolated tests: ok");
+}
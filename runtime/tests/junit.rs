@@ -0,0 +1,64 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+extern crate stainless_runtime;
+
+use std::time::Duration;
+
+use stainless_runtime::{BenchResult, DescribeInfo, JUnitReporter, Outcome, Reporter, Summary, TestInfo, TestResult};
+
+#[test]
+fn writes_a_testsuite_per_describe_block() {
+    let mut out = vec![];
+    {
+        let mut reporter = JUnitReporter::new(&mut out);
+        let test = |reporter: &mut JUnitReporter<_>, path: &[&str], description, outcome: Outcome,
+                        millis, output| {
+            let info = TestInfo { description, path, tags: &[] };
+            let result = TestResult { outcome: &outcome, duration: Duration::from_millis(millis), output,
+                                      phases: &[] };
+            reporter.test_finished(&info, &result).unwrap();
+        };
+
+        let outer = DescribeInfo { name: "stainless", path: &["stainless"], tags: &[] };
+        let inner = DescribeInfo { name: "nesting", path: &["stainless", "nesting"], tags: &[] };
+
+        reporter.describe_started(&outer).unwrap();
+        test(&mut reporter, outer.path, "passes", Outcome::Passed, 1500, "");
+        reporter.describe_started(&inner).unwrap();
+        test(&mut reporter, inner.path, "fails <badly>",
+             Outcome::Failed("panicked at 'a & b'\nat line 3".to_string()), 2, "printed \"this\"\n");
+        test(&mut reporter, inner.path, "is pending", Outcome::Ignored, 0, "");
+        let bench = TestInfo { description: "is fast", path: inner.path, tags: &[] };
+        reporter.bench_finished(&bench, &BenchResult { ns_per_iter: 10, variance: 1 }).unwrap();
+        reporter.describe_finished(&inner).unwrap();
+        reporter.describe_finished(&outer).unwrap();
+
+        // The measured benchmark is counted as passed, but is not a test case of the report.
+        let summary = Summary { passed: 2, failed: 1, ignored: 1, not_run: 0,
+                                duration: Duration::from_millis(1502) };
+        reporter.run_finished(&summary).unwrap();
+    }
+
+    assert_eq!(String::from_utf8(out).unwrap(), r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites tests="3" failures="1" skipped="1" time="1.502">
+  <testsuite name="stainless" tests="1" failures="0" skipped="0" time="1.500">
+    <testcase classname="stainless" name="passes" time="1.500"/>
+  </testsuite>
+  <testsuite name="stainless.nesting" tests="2" failures="1" skipped="1" time="0.002">
+    <testcase classname="stainless.nesting" name="fails &lt;badly&gt;" time="0.002">
+      <failure message="panicked at &apos;a &amp; b&apos;" type="panic">panicked at &apos;a &amp; b&apos;
+at line 3</failure>
+      <system-out>printed &quot;this&quot;
+</system-out>
+    </testcase>
+    <testcase classname="stainless.nesting" name="is pending" time="0.000">
+      <skipped/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#);
+}
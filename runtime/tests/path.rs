@@ -0,0 +1,62 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+extern crate stainless_runtime;
+
+use stainless_runtime::PathPattern;
+
+fn matches(pattern: &str, path: &[&str], description: &str) -> bool {
+    PathPattern::parse(pattern).unwrap().matches(path, description)
+}
+
+#[test]
+fn last_level_matches_the_start_of_a_name() {
+    let path = &["stainless", "nesting"];
+    assert!(matches("stainless > nesting > makes it simple", path, "makes it simple to categorize tests"));
+    assert!(matches("stainless > nest", path, "makes it simple to categorize tests"));
+    assert!(!matches("stainless > nesting > it simple", path, "makes it simple to categorize tests"));
+}
+
+#[test]
+fn other_levels_match_whole_names() {
+    assert!(!matches("stain > nesting > makes", &["stainless", "nesting"], "makes it simple"));
+    assert!(matches("stain* > nesting > makes", &["stainless", "nesting"], "makes it simple"));
+    assert!(matches("stainless", &["stainless", "nesting"], "makes it simple"));
+}
+
+#[test]
+fn globs_and_regular_expressions() {
+    let path = &["stainless", "nesting"];
+    assert!(matches("** > makes?it", path, "makes it simple"));
+    assert!(matches("** > *simple", path, "makes it simple"));
+    assert!(matches("stainless > ** > /simple$/", path, "makes it simple"));
+    assert!(!matches("stainless > ** > /^simple/", path, "makes it simple"));
+    assert!(!matches("stainless > nesting > makes it simple > more", path, "makes it simple"));
+}
+
+#[test]
+fn regular_expression_syntax() {
+    let path = &["stainless"];
+    assert!(matches("** > /^(adds|panics)$/", path, "panics"));
+    assert!(!matches("** > /^(adds|panics)$/", path, "panics twice"));
+    assert!(matches(r"** > /^\w+ [0-9]{2,3}\.\d?$/", path, "adds 100."));
+    assert!(!matches(r"** > /^\w+ [0-9]{2,3}\.\d?$/", path, "adds 1000.5"));
+    assert!(matches(r"** > /\bit\b/", path, "makes it simple"));
+    assert!(!matches(r"** > /\bit\b/", path, "submits simple"));
+    assert!(matches(r"** > /^[^\s]+?(?:s|ed) .*e$/", path, "makes it simple"));
+    assert!(matches("** > /^a*a*b?a+$/", path, "aaaa"));
+}
+
+#[test]
+fn errors() {
+    assert!(PathPattern::parse("stainless > > simple").is_err());
+    assert!(PathPattern::parse("stainless > /(/").is_err());
+    assert!(PathPattern::parse("stainless > /a)/").is_err());
+    assert!(PathPattern::parse("stainless > /[a/").is_err());
+    assert!(PathPattern::parse("stainless > /*a/").is_err());
+    assert!(PathPattern::parse("stainless > /a{3,1}/").is_err());
+    assert!(PathPattern::parse("stainless > /(?i)a/").is_err());
+}
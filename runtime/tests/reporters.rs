@@ -0,0 +1,134 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+extern crate stainless_runtime;
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use stainless_runtime::{__enter_phase, DescribeInfo, Options, Outcome, Phase, Reporter, Runner,
+                        Suite, Summary, Test, TestInfo, TestResult};
+
+// What `describe!` generates in harness mode for tests with hooks, written out by hand.
+fn setup_fails() {
+    __enter_phase(Phase::BeforeEach("hooks"));
+    panic!("no database");
+}
+
+fn teardown_fails() {
+    __enter_phase(Phase::BeforeEach("hooks"));
+    __enter_phase(Phase::Test);
+    __enter_phase(Phase::AfterEach("hooks"));
+    panic!("cannot clean up");
+}
+
+fn expected_panic_in_hook() {
+    __enter_phase(Phase::BeforeEach("hooks"));
+    panic!("expected");
+}
+
+fn passes() {
+    __enter_phase(Phase::BeforeEach("hooks"));
+    __enter_phase(Phase::Test);
+    __enter_phase(Phase::AfterEach("hooks"));
+}
+
+fn suite() -> Suite {
+    let mut suite = Suite::new("hooks");
+    suite.test(Test::new("setup fails", setup_fails));
+    suite.test(Test::new("teardown fails", teardown_fails));
+    suite.test(Test::new("panics in a hook", expected_panic_in_hook).should_panic(Some("expected")));
+    suite.test(Test::new("passes", passes));
+    suite
+}
+
+#[derive(Clone, Default)]
+struct Recorder {
+    events: Arc<Mutex<Vec<String>>>
+}
+
+impl Reporter for Recorder {
+    fn describe_started(&mut self, describe: &DescribeInfo) -> io::Result<()> {
+        self.events.lock().unwrap().push(format!("enter {}", describe.name));
+        Ok(())
+    }
+
+    fn describe_finished(&mut self, describe: &DescribeInfo) -> io::Result<()> {
+        self.events.lock().unwrap().push(format!("exit {}", describe.name));
+        Ok(())
+    }
+
+    fn hook_failed(&mut self, test: &TestInfo, hook: Phase, message: &str) -> io::Result<()> {
+        let message = message.split('\'').nth(1).unwrap_or("");
+        self.events.lock().unwrap().push(format!("{} failed in {}: {}", test.description, hook, message));
+        Ok(())
+    }
+
+    fn test_finished(&mut self, test: &TestInfo, result: &TestResult) -> io::Result<()> {
+        let outcome = match *result.outcome {
+            Outcome::Passed => "passed",
+            Outcome::Failed(_) => "failed",
+            Outcome::Ignored => "ignored",
+            Outcome::NotRun => "not run"
+        };
+        self.events.lock().unwrap().push(format!("{} {}", test.description, outcome));
+        Ok(())
+    }
+
+    fn run_finished(&mut self, summary: &Summary) -> io::Result<()> {
+        self.events.lock().unwrap().push(format!("{} passed, {} failed", summary.passed, summary.failed));
+        Ok(())
+    }
+}
+
+fn run(recorders: &[Recorder], args: &[&str]) -> String {
+    let options = Options::parse(args.iter().map(|arg| arg.to_string())).unwrap();
+    let mut out = vec![];
+    let mut runner = Runner::new(vec![suite()]);
+    for recorder in recorders {
+        runner = runner.reporter(recorder.clone());
+    }
+    runner.run_with(&options, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn reports_hook_failures() {
+    let recorder = Recorder::default();
+    run(&[recorder.clone()], &["--test-threads", "1"]);
+
+    assert_eq!(*recorder.events.lock().unwrap(), vec![
+        "enter hooks",
+        "setup fails failed in `before_each` of `hooks`: no database",
+        "setup fails failed",
+        "teardown fails failed in `after_each` of `hooks`: cannot clean up",
+        "teardown fails failed",
+        "panics in a hook failed in `before_each` of `hooks`: expected",
+        "panics in a hook failed",
+        "passes passed",
+        "exit hooks",
+        "1 passed, 3 failed",
+    ]);
+}
+
+#[test]
+fn labels_hook_failures_in_reports() {
+    let out = run(&[], &["--color", "never"]);
+    assert!(out.contains("`before_each` of `hooks` failed: panicked at 'no database'"));
+
+    let out = run(&[], &["--format", "json", "setup"]);
+    assert!(out.contains(r#"{"type":"hook_failed","description":"setup fails","path":["hooks"],"tags":[],"hook":"before_each","scope":"hooks","message":"panicked at 'no database'"#));
+}
+
+#[test]
+fn supports_several_reporters() {
+    let (first, second) = (Recorder::default(), Recorder::default());
+    let out = run(&[first.clone(), second.clone()], &["--format", "tap", "passes"]);
+
+    assert!(out.starts_with("TAP version 14"));
+    assert_eq!(*first.events.lock().unwrap(), *second.events.lock().unwrap());
+    assert_eq!(first.events.lock().unwrap().len(), 4);
+}
@@ -0,0 +1,247 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+extern crate stainless_runtime;
+
+use std::io::Read;
+
+use stainless_runtime::{run_suites, Bench, Bencher, Format, Options, Suite, Summary, Test};
+
+// What `describe!` generates in harness mode, written out by hand.
+fn adds() { let two = 2; assert_eq!(two + two, 4); }
+fn fails() { let two = 2; assert_eq!(two + two, 5); }
+fn panics() { panic!("expected message"); }
+fn ignored() { panic!("should not run"); }
+fn multiplies(bencher: &mut Bencher) { bencher.iter(|| 2 * 2); }
+
+fn suite() -> Suite {
+    let mut nested = Suite::new("nested").tag("slow");
+    nested.test(Test::new("fails", fails).tag("slow"));
+    nested.test(Test::new("panics", panics).tag("slow").should_panic(Some("expected")));
+    nested.test(Test::new("is ignored", ignored).tag("slow").ignored());
+
+    let mut suite = Suite::new("arithmetic");
+    suite.test(Test::new("adds", adds));
+    suite.describe(nested);
+    suite.bench(Bench::new("multiplies", multiplies));
+    suite
+}
+
+fn run(args: &[&str]) -> (Summary, String) {
+    let mut args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    args.push("--color".to_string());
+    args.push("never".to_string());
+
+    let options = Options::parse(args).unwrap();
+    let mut out = vec![];
+    let summary = run_suites(&[suite()], &options, &mut out).unwrap();
+    (summary, String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn runs_the_tree_in_order() {
+    let (summary, out) = run(&["--test-threads", "4"]);
+    assert_eq!((summary.passed, summary.failed, summary.ignored), (3, 1, 1));
+
+    let tree: Vec<&str> = out.lines().take(7).collect();
+    assert_eq!(tree[0], "arithmetic");
+    assert!(tree[1].starts_with("  ✓ adds ("));
+    assert_eq!(tree[2], "  nested");
+    assert!(tree[3].starts_with("    ✗ fails ("));
+    assert!(tree[4].starts_with("    ✓ panics ("));
+    assert_eq!(tree[5], "    - is ignored");
+    assert!(tree[6].starts_with("  ✓ multiplies ("));
+    assert!(out.contains("1) arithmetic > nested > fails"));
+    assert!(out.contains("panicked at 'assertion"), "{}", out);
+    assert!(out.contains("runtime/tests/runner.rs:15"), "{}", out);
+}
+
+#[test]
+fn filters_by_name_and_tags() {
+    let (summary, out) = run(&["nested::panics"]);
+    assert_eq!((summary.passed, summary.failed, summary.ignored), (1, 0, 0));
+    assert!(out.starts_with("arithmetic\n  nested\n    ✓ panics"));
+
+    let (summary, _) = run(&["--tags", "slow and not nothing", "--skip", "fails"]);
+    assert_eq!((summary.passed, summary.failed, summary.ignored), (1, 0, 1));
+
+    let (summary, _) = run(&["--exclude-tags", "slow"]);
+    assert_eq!((summary.passed, summary.failed, summary.ignored), (2, 0, 0));
+
+    let (summary, _) = run(&["--ignored"]);
+    assert_eq!((summary.passed, summary.failed, summary.ignored), (0, 1, 0));
+}
+
+#[test]
+fn lists_tests() {
+    let (_, out) = run(&["--list"]);
+    assert_eq!(out, "\
+arithmetic
+  adds
+  nested [slow]
+    fails [slow]
+    panics [slow]
+    is ignored (ignored) [slow]
+  multiplies (bench)
+
+4 tests, 1 benchmarks
+");
+
+    let (_, out) = run(&["--list", "--format", "terse"]);
+    assert_eq!(out, "\
+arithmetic::adds: test
+arithmetic::nested::fails: test
+arithmetic::nested::panics: test
+arithmetic::nested::is_ignored: test
+arithmetic::multiplies: bench
+
+4 tests, 1 benchmarks
+");
+}
+
+#[test]
+fn filters_by_describe_path() {
+    let (summary, out) = run(&["--list", "arithmetic > nested > is *"]);
+    assert_eq!(summary.passed + summary.failed, 0);
+    assert_eq!(out, "arithmetic\n  nested [slow]\n    is ignored (ignored) [slow]\n\n1 tests, 0 benchmarks\n");
+
+    let (summary, _) = run(&["arithmetic > nested"]);
+    assert_eq!((summary.passed, summary.failed, summary.ignored), (1, 1, 1));
+
+    let (summary, _) = run(&["** > /^(adds|panics)$/"]);
+    assert_eq!((summary.passed, summary.failed, summary.ignored), (2, 0, 0));
+
+    let (summary, _) = run(&["arithmetic > fails", "** > multipl?es"]);
+    assert_eq!((summary.passed, summary.failed, summary.ignored), (1, 0, 0));
+
+    assert!(Options::parse(vec!["a > > b".to_string()]).is_err());
+    assert!(Options::parse(vec!["a > /(/".to_string()]).is_err());
+}
+
+#[test]
+fn prints_a_character_per_test_in_terse_format() {
+    let (_, out) = run(&["--format", "terse", "--test-threads", "1"]);
+    assert!(out.starts_with(".F.i.\n\nfailures:"));
+}
+
+#[test]
+fn shuffles_reproducibly() {
+    // The output without timings, which differ between runs.
+    let order = |args: &[&str]| -> Vec<String> {
+        let (_, out) = run(args);
+        out.lines().map(|line| line.split(" (").next().unwrap().to_string())
+            .collect()
+    };
+
+    let (_, out) = run(&["--seed", "42"]);
+    assert!(out.starts_with("Shuffled with seed 42\n"));
+    assert!(out.contains("Shuffled with seed 42, rerun with `--seed 42` to reproduce the order."));
+    assert_eq!(order(&["--seed", "42", "--test-threads", "4"]), order(&["--seed", "42", "--test-threads", "1"]));
+
+    let declared = order(&["--list"]);
+    assert!((0..20).any(|seed| order(&["--list", "--seed", &seed.to_string()]) != declared));
+
+    // Every test is still listed once, under its own describe! blocks.
+    let (_, out) = run(&["--list", "--seed", "3"]);
+    assert_eq!(out.lines().filter(|line| line.trim().starts_with("fails")).count(), 1);
+    assert!(out.ends_with("4 tests, 1 benchmarks\n"));
+
+    // Shuffling within describe! blocks keeps each block together.
+    for seed in 0..20 {
+        let (_, out) = run(&["--list", "--shuffle-scope", "describe", "--seed", &seed.to_string()]);
+        assert_eq!(out.lines().filter(|line| line.trim() == "nested [slow]").count(), 1);
+        assert_eq!(out.lines().filter(|line| line.starts_with("      ")).count(), 0);
+    }
+}
+
+#[test]
+fn stops_after_the_maximum_number_of_failures() {
+    let (summary, out) = run(&["--fail-fast", "--test-threads", "1", "--include-ignored"]);
+    assert_eq!((summary.passed, summary.failed, summary.ignored, summary.not_run), (1, 1, 0, 3));
+    assert!(out.contains("    - panics (not run)\n    - is ignored (not run)\n  - multiplies (not run)\n"));
+    assert!(out.contains("1 passing, 1 failing, 0 pending, 3 not run ("));
+    assert!(!summary.is_success());
+
+    let (summary, _) = run(&["--max-failures", "2", "--test-threads", "1", "--include-ignored"]);
+    assert_eq!((summary.passed, summary.failed, summary.ignored, summary.not_run), (2, 2, 0, 1));
+}
+
+#[test]
+fn reports_slow_tests() {
+    let (_, out) = run(&["--slowest", "2", "--slow-threshold", "0ms", "--test-threads", "1"]);
+    assert!(out.contains("  ✓ adds ("));
+    assert!(out.contains(", slow)\n"));
+    assert!(out.contains("\nslowest tests:\n"));
+    assert_eq!(out.split("slowest tests:\n").nth(1).unwrap().lines().take_while(|line| !line.is_empty()).count(), 2);
+    assert!(out.contains("\nslowest describe! blocks:\n"));
+    assert!(out.contains("  arithmetic > nested\n"));
+
+    let (_, out) = run(&["--format", "json", "adds"]);
+    assert!(out.contains(r#""phases":[{"phase":"test","duration_ms":"#));
+    assert!(out.contains(r#""type":"suite_finished","name":"arithmetic","path":["arithmetic"],"tags":[],"duration_ms":"#));
+
+    assert!(Options::parse(vec!["--slow-threshold".to_string(), "2s".to_string()]).is_ok());
+    assert!(Options::parse(vec!["--slow-threshold".to_string(), "soon".to_string()]).is_err());
+}
+
+#[test]
+fn measures_benchmarks() {
+    let (summary, out) = run(&["--bench"]);
+    assert_eq!((summary.passed, summary.failed, summary.ignored), (1, 0, 0));
+    assert!(out.contains("✓ multiplies: "));
+    assert!(out.contains(" ns/iter (+/- "));
+}
+
+#[test]
+fn rejects_unknown_options() {
+    assert!(Options::parse(vec!["--frobnicate".to_string()]).is_err());
+    assert!(Options::parse(vec!["--test-threads".to_string(), "0".to_string()]).is_err());
+    assert!(Options::parse(vec!["--tags".to_string(), "a and".to_string()]).is_err());
+}
+
+#[test]
+fn accepts_values_after_an_equals_sign() {
+    let parse = |args: &[&str]| Options::parse(args.iter().map(|arg| arg.to_string())).unwrap();
+    let separate = parse(&["--test-threads", "4", "--format", "json", "--skip", "foo", "--color", "never", "--seed", "7"]);
+    let joined = parse(&["--test-threads=4", "--format=json", "--skip=foo", "--color=never", "--seed=7"]);
+    for options in &[separate, joined] {
+        assert_eq!(options.test_threads, 4);
+        assert_eq!(options.format, Format::Json);
+        assert_eq!(options.skip, vec!["foo"]);
+        assert!(!options.color);
+        assert_eq!(options.seed, Some(7));
+        assert!(options.filters.is_empty());
+    }
+
+    // Only flags are split, and the value may contain another `=`.
+    assert_eq!(parse(&["a=b"]).filters, vec!["a=b"]);
+    assert_eq!(parse(&["--skip=a=b"]).skip, vec!["a=b"]);
+    assert!(Options::parse(vec!["--list=yes".to_string()]).is_err());
+    assert!(Options::parse(vec!["--test-threads=0".to_string()]).is_err());
+}
+
+#[test]
+fn writes_junit_reports() {
+    let path = std::env::temp_dir().join("stainless-junit-writes-junit-reports.xml");
+    run(&["--junit", path.to_str().unwrap()]);
+
+    let mut report = String::new();
+    std::fs::File::open(&path).unwrap().read_to_string(&mut report).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(report.contains(r#"<testsuite name="arithmetic.nested" tests="3" failures="1" skipped="1""#));
+    assert!(report.contains(r#"<testcase classname="arithmetic" name="adds""#));
+}
+
+#[test]
+fn prints_the_chosen_format() {
+    let (_, out) = run(&["--format", "tap", "--test-threads", "1"]);
+    assert!(out.starts_with("TAP version 14\n# Subtest: arithmetic\n    ok 1 - adds\n"));
+    assert!(out.ends_with("not ok 1 - arithmetic\n1..1\n"));
+
+    let (_, out) = run(&["--format", "json", "adds"]);
+    assert_eq!(out.lines().count(), 6);
+    assert!(out.contains(r#""type":"test_passed","description":"adds","path":["arithmetic"],"tags":[]"#));
+}
@@ -0,0 +1,78 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+extern crate stainless_runtime;
+
+#[allow(deprecated)]
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+use std::thread;
+use std::time::Duration;
+
+use stainless_runtime::{run_suites, Options, Suite, Test};
+
+// `AtomicUsize::new` is not a `const fn` on the compiler of the plugin yet.
+#[allow(deprecated)] static RUNNING: AtomicUsize = ATOMIC_USIZE_INIT;
+#[allow(deprecated)] static MOST_RUNNING: AtomicUsize = ATOMIC_USIZE_INIT;
+#[allow(deprecated)] static DB: AtomicUsize = ATOMIC_USIZE_INIT;
+#[allow(deprecated)] static FILES: AtomicUsize = ATOMIC_USIZE_INIT;
+
+// Runs for a while, failing if more than `limit` tests hold `counter` at the same time.
+fn hold(counter: &AtomicUsize, limit: usize) {
+    let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+    let mut most = MOST_RUNNING.load(Ordering::SeqCst);
+    while running > most {
+        match MOST_RUNNING.compare_exchange(most, running, Ordering::SeqCst, Ordering::SeqCst) {
+            Ok(_) => break,
+            Err(current) => most = current
+        }
+    }
+    let holding = counter.fetch_add(1, Ordering::SeqCst) + 1;
+    thread::sleep(Duration::from_millis(20));
+    counter.fetch_sub(1, Ordering::SeqCst);
+    RUNNING.fetch_sub(1, Ordering::SeqCst);
+    assert!(holding <= limit, "{} tests ran at the same time", holding);
+}
+
+fn uses_db() { hold(&DB, 1) }
+fn uses_files() { hold(&FILES, 1) }
+fn parallel() { hold(&AtomicUsize::new(0), 1) }
+fn alone() {
+    assert_eq!(RUNNING.load(Ordering::SeqCst), 0);
+    hold(&AtomicUsize::new(0), 1);
+}
+
+fn suites() -> Vec<Suite> {
+    let mut db = Suite::new("db");
+    for _ in 0..4 {
+        db.test(Test::new("uses the database", uses_db).serial(Some("db")));
+    }
+
+    let mut nested = Suite::new("nested");
+    nested.test(Test::new("uses files", uses_files));
+    let mut files = Suite::new("files").serial(None);
+    files.test(Test::new("uses files", uses_files));
+    files.test(Test::new("uses files", uses_files));
+    files.describe(nested);
+
+    let mut other = Suite::new("other");
+    for _ in 0..4 {
+        other.test(Test::new("runs in parallel", parallel));
+    }
+    other.test(Test::new("runs alone", alone).serial(None));
+    other.test(Test::new("uses the database", uses_db).serial(Some("db")));
+
+    vec![db, files, other]
+}
+
+#[test]
+fn keeps_serial_tests_apart() {
+    let options = Options::parse(vec!["--jobs".to_string(), "4".to_string()]).unwrap();
+    let summary = run_suites(&suites(), &options, &mut vec![]).unwrap();
+
+    assert_eq!((summary.passed, summary.failed), (13, 0));
+    let most_running = MOST_RUNNING.load(Ordering::SeqCst);
+    assert!(most_running > 1 && most_running <= 4, "{} tests ran at the same time", most_running);
+}
@@ -0,0 +1,56 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+extern crate stainless_runtime;
+
+use std::time::Duration;
+
+use stainless_runtime::{DescribeInfo, Outcome, Reporter, SpecReporter, Summary, TestInfo, TestResult};
+
+#[test]
+fn prints_the_describe_tree_and_failures() {
+    let mut out = vec![];
+    {
+        let mut reporter = SpecReporter::new(&mut out, false);
+        let test = |reporter: &mut SpecReporter<_>, path: &[&str], description, outcome: Outcome| {
+            let info = TestInfo { description, path, tags: &[] };
+            let result = TestResult { outcome: &outcome, duration: Duration::from_millis(3), output: "",
+                                      phases: &[] };
+            reporter.test_finished(&info, &result).unwrap();
+        };
+
+        let outer = DescribeInfo { name: "stainless", path: &["stainless"], tags: &[] };
+        let inner = DescribeInfo { name: "nesting", path: &["stainless", "nesting"], tags: &[] };
+
+        reporter.describe_started(&outer).unwrap();
+        test(&mut reporter, outer.path, "makes organizing tests easy", Outcome::Passed);
+        reporter.describe_started(&inner).unwrap();
+        test(&mut reporter, inner.path, "fails", Outcome::Failed("assertion failed\nat line 3".to_string()));
+        test(&mut reporter, inner.path, "is not written yet", Outcome::Ignored);
+        reporter.describe_finished(&inner).unwrap();
+        reporter.describe_finished(&outer).unwrap();
+
+        let summary = Summary { passed: 1, failed: 1, ignored: 1, not_run: 0,
+                                duration: Duration::from_millis(1520) };
+        reporter.run_finished(&summary).unwrap();
+    }
+
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+stainless
+  ✓ makes organizing tests easy (3ms)
+  nesting
+    ✗ fails (3ms)
+    - is not written yet
+
+failures:
+
+  1) stainless > nesting > fails
+     assertion failed
+     at line 3
+
+1 passing, 1 failing, 1 pending (1.52s)
+");
+}
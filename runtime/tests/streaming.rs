@@ -0,0 +1,90 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+extern crate stainless_runtime;
+
+use std::time::Duration;
+
+use stainless_runtime::{BenchResult, DescribeInfo, JsonReporter, Outcome, Reporter, RunInfo, Summary,
+                        TapReporter, TestInfo, TestResult};
+
+// Reports a small run: a passing test, a nested block with a failing and an ignored test, and
+// a benchmark.
+fn report<R: Reporter>(reporter: &mut R) {
+    let outer = DescribeInfo { name: "stainless", path: &["stainless"], tags: &[] };
+    let inner = DescribeInfo { name: "nesting", path: &["stainless", "nesting"], tags: &["db"] };
+    let test = |reporter: &mut R, path: &[&str], description, outcome: Outcome| {
+        let info = TestInfo { description, path, tags: &[] };
+        let result = TestResult { outcome: &outcome, duration: Duration::from_millis(2), output: "",
+                                  phases: &[] };
+        reporter.test_started(&info).unwrap();
+        reporter.test_finished(&info, &result).unwrap();
+    };
+
+    reporter.run_started(&RunInfo { seed: None }).unwrap();
+    reporter.describe_started(&outer).unwrap();
+    test(reporter, outer.path, "passes #1", Outcome::Passed);
+    reporter.describe_started(&inner).unwrap();
+    test(reporter, inner.path, "fails", Outcome::Failed("panicked at 'no'".to_string()));
+    test(reporter, inner.path, "is pending", Outcome::Ignored);
+    reporter.describe_finished(&inner).unwrap();
+    let bench = TestInfo { description: "measures", path: outer.path, tags: &[] };
+    reporter.bench_finished(&bench, &BenchResult { ns_per_iter: 12, variance: 3 }).unwrap();
+    reporter.describe_finished(&outer).unwrap();
+
+    let summary = Summary { passed: 1, failed: 1, ignored: 1, not_run: 0,
+                            duration: Duration::from_millis(5) };
+    reporter.run_finished(&summary).unwrap();
+}
+
+#[test]
+fn tap_nests_describe_blocks_as_subtests() {
+    let mut out = vec![];
+    report(&mut TapReporter::new(&mut out));
+
+    assert_eq!(String::from_utf8(out).unwrap(), r#"TAP version 14
+# Subtest: stainless
+    ok 1 - passes \#1
+    # Subtest: nesting
+        not ok 1 - fails
+          ---
+          message: "panicked at 'no'"
+          duration_ms: 2.000
+          ...
+        ok 2 - is pending # SKIP ignored
+        1..2
+    not ok 2 - nesting
+    ok 3 - measures
+      ---
+      ns_per_iter: 12
+      variance: 3
+      ...
+    1..3
+not ok 1 - stainless
+1..1
+"#);
+}
+
+#[test]
+fn json_streams_one_event_per_line() {
+    let mut out = vec![];
+    report(&mut JsonReporter::new(&mut out));
+
+    assert_eq!(String::from_utf8(out).unwrap(), r#"{"type":"run_started","schema":1,"seed":null}
+{"type":"suite_started","name":"stainless","path":["stainless"],"tags":[]}
+{"type":"test_started","description":"passes #1","path":["stainless"],"tags":[]}
+{"type":"test_passed","description":"passes #1","path":["stainless"],"tags":[],"duration_ms":2.000,"output":"","phases":[]}
+{"type":"suite_started","name":"nesting","path":["stainless","nesting"],"tags":["db"]}
+{"type":"test_started","description":"fails","path":["stainless","nesting"],"tags":[]}
+{"type":"test_failed","description":"fails","path":["stainless","nesting"],"tags":[],"duration_ms":2.000,"message":"panicked at 'no'","output":"","phases":[]}
+{"type":"test_started","description":"is pending","path":["stainless","nesting"],"tags":[]}
+{"type":"test_skipped","description":"is pending","path":["stainless","nesting"],"tags":[],"duration_ms":2.000,"output":"","phases":[]}
+{"type":"suite_finished","name":"nesting","path":["stainless","nesting"],"tags":["db"],"duration_ms":4.000}
+{"type":"bench_result","description":"measures","path":["stainless"],"tags":[],"ns_per_iter":12,"variance":3}
+{"type":"suite_finished","name":"stainless","path":["stainless"],"tags":[],"duration_ms":6.000}
+{"type":"run_finished","passed":1,"failed":1,"ignored":1,"not_run":0,"duration_ms":5.000}
+"#);
+}
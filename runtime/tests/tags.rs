@@ -0,0 +1,46 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+extern crate stainless_runtime;
+
+use stainless_runtime::TagExpr;
+
+fn matches(expr: &str, tags: &[&str]) -> bool {
+    TagExpr::parse(expr).unwrap().matches(tags)
+}
+
+#[test]
+fn single_tag() {
+    assert!(matches("slow", &["slow", "db"]));
+    assert!(!matches("slow", &["db"]));
+}
+
+#[test]
+fn operators() {
+    assert!(matches("slow and db", &["slow", "db"]));
+    assert!(!matches("slow & db", &["slow"]));
+    assert!(matches("slow or db", &["db"]));
+    assert!(matches("slow, db", &["slow"]));
+    assert!(matches("not slow", &["db"]));
+    assert!(!matches("!slow", &["slow"]));
+}
+
+#[test]
+fn precedence_and_grouping() {
+    assert!(matches("a or b and c", &["a"]));
+    assert!(!matches("(a or b) and c", &["a"]));
+    assert!(matches("not a and b", &["b"]));
+    assert!(!matches("not (a or b)", &["b"]));
+}
+
+#[test]
+fn errors() {
+    assert!(TagExpr::parse("").is_err());
+    assert!(TagExpr::parse("slow and").is_err());
+    assert!(TagExpr::parse("(slow").is_err());
+    assert!(TagExpr::parse("slow db").is_err());
+    assert!(TagExpr::parse("slow?").is_err());
+}
@@ -4,7 +4,7 @@
 // Licensed under the MIT license. This file may not be copied, modified, or distributed except
 // according to those terms.
 
-use syntax::ast;
+use syntax::{ast, codemap};
 use syntax::ptr::P;
 
 /// A benchmark, represented as a description, an associated block,
@@ -13,5 +13,9 @@ use syntax::ptr::P;
 pub struct Bench {
     pub bench: P<ast::Ident>,
     pub description: String,
-    pub block: P<ast::Block>
+    pub block: P<ast::Block>,
+    /// Attributes written before the benchmark, copied onto the generated function.
+    pub attrs: Vec<ast::Attribute>,
+    /// The span of the description, where the benchmark is located by `cargo stainless`.
+    pub span: codemap::Span
 }
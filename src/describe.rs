@@ -17,10 +17,11 @@
 /// for `DescribeState`, which holds all the information necessary to
 /// generate the AST for an expanded `describe!`.
 ///
-/// The Parse implementation of DescribeState delegates to the Parse
-/// implementation of Test, defined in the test module, and Bench,
-/// defined in the bench module. The Generate implementation does the
-/// same.
+/// The Parse implementation of DescribeState reads the blocks with the
+/// grammar in the `stainless_grammar` crate, which `cargo stainless`
+/// shares, and converts them into the Test and Bench structs, defined
+/// in the test and bench modules. The Generate implementation delegates
+/// to the Generate implementations of those.
 ///
 /// Most of the code can be understood by just walking through the
 /// implementations of Parse and Generate for all the types inside
@@ -35,6 +36,8 @@ use syntax::util::small_vector::SmallVector;
 
 use parse::Parse;
 use generate::Generate;
+use lint;
+use expand;
 use test::Test;
 use bench::Bench;
 
@@ -42,9 +45,38 @@ use bench::Bench;
 #[derive(Clone)]
 pub struct DescribeState {
     pub name: Option<ast::Ident>,
-    pub before_each: Option<P<ast::Block>>,
-    pub after_each: Option<P<ast::Block>>,
-    pub subblocks: Vec<SubBlock>
+    /// Attributes of the `describe!` block, copied onto the generated module.
+    pub attrs: Vec<ast::Attribute>,
+    /// Whether to emit `use super::*` into the generated module.
+    pub super_glob: bool,
+    /// Tags given with `#![tags(...)]`, inherited by all tests in this block.
+    pub tags: Vec<String>,
+    /// `#![serial]` (`Some(None)`) or `#![serial(group)]` (`Some(Some(group))`).
+    pub serial: Option<Option<String>>,
+    /// Whether all tests of this block run in their own process, from `#![isolated]`.
+    pub isolated: bool,
+    /// Lints of stainless allowed with `#![allow(...)]`, in this block and the nested blocks.
+    pub allowed_lints: Vec<String>,
+    /// Whether to register with the stainless runtime instead of generating `#[test]`s.
+    pub harness: bool,
+    /// The `before_each` blocks, in the order they are written, which is the order they run in.
+    pub before_each: Vec<P<ast::Block>>,
+    /// The `after_each` blocks, in the order they are written. They run in reverse order.
+    pub after_each: Vec<P<ast::Block>>,
+    pub subblocks: Vec<SubBlock>,
+    /// Where the hooks, tests, steps and benchmarks of this block were written.
+    pub origins: Vec<Origin>,
+    /// The span of the invocation, or of the name of a nested block.
+    pub span: codemap::Span
+}
+
+/// A block of statements as written, named for `cargo stainless expand`.
+#[derive(Clone)]
+pub struct Origin {
+    /// The span of the block.
+    pub span: codemap::Span,
+    /// Like `before_each of describe! stack, tests/spec.rs:9`.
+    pub label: String
 }
 
 /// Any supported subblock.
@@ -52,7 +84,9 @@ pub struct DescribeState {
 pub enum SubBlock {
     Test(Test),
     Bench(Bench),
-    Describe(DescribeState)
+    Describe(DescribeState),
+    /// An ordinary item (`use`, `fn`, `struct`, ...), emitted unchanged.
+    Item(P<ast::Item>)
 }
 
 /// Defines the overarching `describe!` syntax extension.
@@ -60,11 +94,29 @@ pub enum SubBlock {
 /// All other macros in stainless are actually "fake" in the sense
 /// that they are detected and expanded inside of the implementation
 /// of `describe!`.
-pub fn describe<'a>(cx: &'a mut base::ExtCtxt, sp: codemap::Span,
-                name: ast::Ident, tokens: Vec<tokenstream::TokenTree>) -> Box<base::MacResult + 'a> {
-    // Parse a full DescribeState from the input, emitting errors if used incorrectly.
-    let state: DescribeState = Parse::parse(&mut parse::stream_to_parser(cx.parse_sess(), tokens.into_iter().collect()), (sp, &mut*cx, Some(name)));
+pub struct Describe {
+    /// Set by `#![plugin(stainless(harness))]`.
+    pub harness: bool
+}
+
+impl base::IdentMacroExpander for Describe {
+    fn expand<'cx>(&self, cx: &'cx mut base::ExtCtxt, sp: codemap::Span,
+                   name: ast::Ident, tokens: Vec<tokenstream::TokenTree>) -> Box<base::MacResult + 'cx> {
+        // Parse a full DescribeState from the input, emitting errors if used incorrectly.
+        let mut state: DescribeState = Parse::parse(&mut parse::stream_to_parser(cx.parse_sess(), tokens.into_iter().collect()), (sp, &mut*cx, name));
+        state.harness = self.harness;
+
+        // Warn about common mistakes in the specs.
+        lint::check(cx, &state);
+
+        // `cargo stainless expand` prints the module generated for a block.
+        let expanded = expand::requested().map(|path| (path, state.clone()));
 
-    // Export the new module.
-    base::MacEager::items(SmallVector::one(state.generate(sp, cx, None)))
+        // Export the new module.
+        let item = state.generate(sp, cx, None);
+        if let Some((path, state)) = expanded {
+            expand::write(cx, &path, &state, &item);
+        }
+        base::MacEager::items(SmallVector::one(item))
+    }
 }
@@ -0,0 +1,176 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use syntax::ast;
+use syntax::ext::base;
+use syntax::print::pprust;
+use syntax::ptr::P;
+
+use describe::{DescribeState, Origin, SubBlock};
+
+/// The path of the `describe!` block `cargo stainless expand` asks for, like `stack > empty`.
+const EXPAND: &'static str = "STAINLESS_EXPAND";
+
+/// The file the module generated for the block is appended to.
+const EXPAND_OUTPUT: &'static str = "STAINLESS_EXPAND_OUTPUT";
+
+const GENERATED: &'static str = "generated by stainless";
+
+/// The names of the blocks leading to the `describe!` block to expand, if any.
+pub fn requested() -> Option<Vec<String>> {
+    env::var(EXPAND).ok().map(|path| path.split('>').map(|name| name.trim().to_string()).collect())
+}
+
+/// Append the module generated for the block at `path` of `state` to the output file, if `item`
+/// is the module generated for `state` and contains the block.
+///
+/// Every run of statements in a test or benchmark is preceded by a comment with the hook, test,
+/// step or benchmark it was written in, or "generated by stainless" for the statements added by
+/// the plugin.
+pub fn write(cx: &base::ExtCtxt, path: &[String], state: &DescribeState, item: &ast::Item) {
+    if path.first() != state.name.map(|name| name.name.to_string()).as_ref() {
+        return;
+    }
+
+    // The origins of the enclosing blocks matter too, their hooks are spliced into the tests.
+    let mut origins = state.origins.clone();
+    let (mut state, mut item) = (state, item);
+    for name in &path[1..] {
+        let nested = state.subblocks.iter().filter_map(|block| match *block {
+            SubBlock::Describe(ref nested) if nested.name.map_or(false, |ident| &*ident.name.as_str() == name) => {
+                Some(nested)
+            },
+            _ => None
+        }).next();
+        let module = match item.node {
+            ast::ItemKind::Mod(ref module) => module.items.iter().find(|item| &*item.ident.name.as_str() == name),
+            _ => None
+        };
+        match (nested, module) {
+            (Some(nested), Some(module)) => {
+                origins.extend(nested.origins.iter().cloned());
+                state = nested;
+                item = module;
+            },
+            _ => return
+        }
+    }
+    collect_origins(state, &mut origins);
+
+    let loc = cx.codemap().lookup_char_pos(state.span.lo());
+    let mut out = format!("// describe! {} at {}:{}\n", path.join(" > "), loc.file.name, loc.line);
+    print_item(cx, item, &origins, 0, &mut out);
+
+    let output = env::var(EXPAND_OUTPUT).unwrap_or_default();
+    let written = OpenOptions::new().create(true).append(true).open(&output)
+        .and_then(|mut file| file.write_all(out.as_bytes()));
+    if let Err(e) = written {
+        cx.span_err(state.span, &format!("failed to write the expansion to `{}`: {}", output, e));
+    }
+}
+
+// Adds the origins of the nested blocks of `state`.
+fn collect_origins(state: &DescribeState, origins: &mut Vec<Origin>) {
+    for block in &state.subblocks {
+        if let SubBlock::Describe(ref nested) = *block {
+            origins.extend(nested.origins.iter().cloned());
+            collect_origins(nested, origins);
+        }
+    }
+}
+
+// The origin of a statement, if it was written in one of the blocks of `origins`.
+fn origin<'a>(stmt: &ast::Stmt, origins: &'a [Origin]) -> Option<&'a str> {
+    origins.iter()
+        .find(|origin| origin.span.lo() <= stmt.span.lo() && stmt.span.hi() <= origin.span.hi())
+        .map(|origin| &*origin.label)
+}
+
+// Whether statements of `block` were written in one of the blocks of `origins`.
+fn is_written(block: &ast::Block, origins: &[Origin]) -> bool {
+    block.stmts.iter().any(|stmt| origin(stmt, origins).is_some())
+}
+
+// Prints an item `depth` levels deep. Modules are printed item by item, and functions with
+// statements written in hooks, tests, steps or benchmarks statement by statement.
+fn print_item(cx: &base::ExtCtxt, item: &ast::Item, origins: &[Origin], depth: usize, out: &mut String) {
+    match item.node {
+        ast::ItemKind::Mod(ref module) => {
+            let (inner, outer): (Vec<&ast::Attribute>, Vec<&ast::Attribute>) = item.attrs.iter()
+                .partition(|attr| attr.style == ast::AttrStyle::Inner);
+            for attr in outer {
+                print_code(&pprust::attribute_to_string(attr), depth, out);
+            }
+            let public = if item.vis == ast::Visibility::Public { "pub " } else { "" };
+            print_code(&format!("{}mod {} {{", public, item.ident), depth, out);
+            for attr in inner {
+                print_code(&pprust::attribute_to_string(attr), depth + 1, out);
+            }
+            for item in &module.items {
+                print_item(cx, item, origins, depth + 1, out);
+            }
+            print_code("}", depth, out);
+        },
+
+        ast::ItemKind::Fn(ref decl, unsafety, constness, abi, ref generics, ref body)
+                if is_written(body, origins) => {
+            // The function without its statements, up to the opening brace.
+            let empty = P(ast::Block { stmts: vec![], ..(**body).clone() });
+            let node = ast::ItemKind::Fn(decl.clone(), unsafety, constness, abi, generics.clone(), empty);
+            let header = pprust::item_to_string(&ast::Item { node: node, ..item.clone() });
+            let header = header.rfind('{').map_or(&*header, |brace| &header[..brace + 1]);
+            print_code(header, depth, out);
+
+            let mut last = None;
+            for stmt in &body.stmts {
+                let origin = origin(stmt, origins);
+                if last != Some(origin) {
+                    print_code(&format!("// {}", origin.unwrap_or(GENERATED)), depth + 1, out);
+                }
+                last = Some(origin);
+
+                // The statements are printed as written, the generated ones as the compiler
+                // prints them.
+                let code = match origin.and_then(|_| cx.codemap().span_to_snippet(stmt.span).ok()) {
+                    Some(code) => dedent(&code, cx.codemap().lookup_char_pos(stmt.span.lo()).col.0),
+                    None => pprust::stmt_to_string(stmt)
+                };
+                print_code(&code, depth + 1, out);
+            }
+            print_code("}", depth, out);
+        },
+
+        _ => print_code(&pprust::item_to_string(item), depth, out)
+    }
+}
+
+// Code written at column `column`, with the lines after the first moved to column 0.
+fn dedent(code: &str, column: usize) -> String {
+    let mut lines = code.lines();
+    let mut dedented: Vec<&str> = lines.next().into_iter().collect();
+    for line in lines {
+        let indent = line.len() - line.trim_left().len();
+        dedented.push(&line[indent.min(column)..]);
+    }
+    dedented.join("\n")
+}
+
+// Prints code `depth` levels deep.
+fn print_code(code: &str, depth: usize, out: &mut String) {
+    for line in code.lines() {
+        if line.trim().is_empty() {
+            out.push('\n');
+        } else {
+            out.push_str(&"    ".repeat(depth));
+            out.push_str(line.trim_right());
+            out.push('\n');
+        }
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use syntax::{ast, codemap, parse};
+use syntax::ptr::P;
+use syntax::ext::base;
+use syntax::ext::build::AstBuilder;
+use syntax::parse::token;
+use syntax::symbol::Symbol;
+use syntax::tokenstream::TokenStream;
+use syntax::util::small_vector::SmallVector;
+
+use gherkin::{parse_feature, Scenario};
+
+/// Defines the `feature!` syntax extension, which reads a Gherkin `.feature` file while
+/// compiling and generates a module for the feature with a test for every scenario.
+///
+/// The tests run the scenarios with the `Steps` of the runtime, given as the second argument,
+/// or returned by a function `steps()` in scope otherwise. Each test builds its scenario with
+/// the steps read while compiling, and the file is included with `include_str!` so that Cargo
+/// rebuilds the tests when it changes.
+pub struct Feature {
+    /// Set by `#![plugin(stainless(harness))]`.
+    pub harness: bool
+}
+
+impl base::TTMacroExpander for Feature {
+    fn expand<'cx>(&self, cx: &'cx mut base::ExtCtxt, sp: codemap::Span,
+                   input: TokenStream) -> Box<base::MacResult + 'cx> {
+        // `feature!("path/to/file.feature")` or `feature!("path/to/file.feature", steps)`.
+        let mut parser = parse::stream_to_parser(cx.parse_sess(), input);
+        let path = match parser.parse_str() {
+            Ok((path, _)) => path.as_str().to_string(),
+            Err(e) => panic!("{:?}", parser.fatal(&format!("Expected the path of a `.feature` file, got err: {:?}", e)))
+        };
+        let steps = if parser.eat(&token::Comma) && parser.token != token::Eof {
+            match parser.parse_expr() {
+                Ok(steps) => steps,
+                Err(e) => panic!("{:?}", parser.fatal(&format!("Expected the step definitions, got err: {:?}", e)))
+            }
+        } else {
+            quote_expr!(cx, steps())
+        };
+        parser.eat(&token::Comma);
+        if parser.token != token::Eof {
+            panic!("{:?}", parser.fatal("Expected `feature!(\"path\")` or `feature!(\"path\", steps)`"));
+        }
+
+        // Like for `include_str!`, the path is relative to the file invoking the macro.
+        let invoker = PathBuf::from(cx.codemap().span_to_filename(sp));
+        let file = env::current_dir().unwrap_or_default()
+            .join(invoker.parent().unwrap_or(Path::new("")))
+            .join(&path);
+
+        let mut source = String::new();
+        if let Err(e) = File::open(&file).and_then(|mut file| file.read_to_string(&mut source)) {
+            cx.span_err(sp, &format!("failed to read `{}`: {}", file.display(), e));
+            return base::DummyResult::any(sp);
+        }
+        let feature = match parse_feature(&source) {
+            Ok(feature) => feature,
+            Err(e) => {
+                cx.span_err(sp, &format!("invalid feature file `{}`, {}", path, e));
+                return base::DummyResult::any(sp);
+            }
+        };
+
+        let test = cx.attribute(sp, cx.meta_word(sp, Symbol::intern("test")));
+        let ignore = cx.attribute(sp, cx.meta_word(sp, Symbol::intern("ignore")));
+        let file = &*file.to_string_lossy();
+        let label = &*path;
+
+        let mut items = vec![
+            quote_item!(cx, #[allow(unused_imports)] use super::*;).unwrap(),
+            quote_item!(cx, #[allow(dead_code)] const __STAINLESS_FEATURE: &'static str = include_str!($file);).unwrap()
+        ];
+        for scenario in &feature.scenarios {
+            let ident = cx.ident_of(&scenario.function);
+            let scenario_expr = scenario_expr(cx, sp, scenario);
+            let item = quote_item!(cx,
+                fn $ident() {
+                    $steps.run(&$scenario_expr, $label);
+                }
+            ).unwrap();
+
+            // In harness mode the test is registered with the runtime instead.
+            let ignored = is_ignored(&feature.tags, &scenario.tags);
+            let mut attrs = vec![];
+            if !self.harness {
+                attrs.push(test.clone());
+                if ignored {
+                    attrs.push(ignore.clone());
+                }
+            }
+            items.push(item.map(|item| ast::Item { attrs: attrs, ..item }));
+        }
+
+        if self.harness {
+            let name = &*feature.module;
+            let mut suite = quote_expr!(cx, ::stainless_runtime::Suite::new($name));
+            for tag in &feature.tags {
+                let tag = &**tag;
+                suite = quote_expr!(cx, $suite.tag($tag));
+            }
+
+            let registrations: Vec<ast::Stmt> = feature.scenarios.iter().filter_map(|scenario| {
+                let description = &*scenario.name;
+                let ident = cx.ident_of(&scenario.function);
+                let mut expr = quote_expr!(cx, ::stainless_runtime::Test::new($description, $ident));
+                for tag in &scenario.tags {
+                    let tag = &**tag;
+                    expr = quote_expr!(cx, $expr.tag($tag));
+                }
+                if is_ignored(&feature.tags, &scenario.tags) {
+                    expr = quote_expr!(cx, $expr.ignored());
+                }
+                quote_stmt!(cx, let _ = suite.test($expr);)
+            }).collect();
+
+            items.push(quote_item!(cx,
+                #[allow(unused_mut)]
+                pub fn __stainless_suite() -> ::stainless_runtime::Suite {
+                    let mut suite = $suite;
+                    $registrations
+                    suite
+                }
+            ).unwrap());
+        }
+
+        // The module is public in harness mode, so that `stainless_main!` can reach
+        // `__stainless_suite`.
+        let module = cx.item_mod(sp, sp, cx.ident_of(&feature.module), vec![], items);
+        let module = if self.harness {
+            module.map(|module| ast::Item { vis: ast::Visibility::Public, ..module })
+        } else {
+            module
+        };
+        base::MacEager::items(SmallVector::one(module))
+    }
+}
+
+/// `::stainless_runtime::Scenario::new(name, line).step(..)` with the steps of `scenario`.
+fn scenario_expr(cx: &base::ExtCtxt, sp: codemap::Span, scenario: &Scenario) -> P<ast::Expr> {
+    let name = &*scenario.name;
+    let line = scenario.line;
+    let mut expr = quote_expr!(cx, ::stainless_runtime::Scenario::new($name, $line));
+    for step in &scenario.steps {
+        let kind = cx.ident_of(step.kind.name());
+        let (keyword, text, line) = (&*step.keyword, &*step.text, step.line);
+        let mut step_expr = quote_expr!(cx,
+            ::stainless_runtime::Step::new(::stainless_runtime::StepKind::$kind, $keyword, $text, $line));
+        for row in &step.table {
+            let cells = cx.expr_vec_slice(sp, row.iter().map(|cell| cx.expr_str(sp, Symbol::intern(cell))).collect());
+            step_expr = quote_expr!(cx, $step_expr.row($cells));
+        }
+        if let Some(ref docstring) = step.docstring {
+            let docstring = &**docstring;
+            step_expr = quote_expr!(cx, $step_expr.docstring($docstring));
+        }
+        expr = quote_expr!(cx, $expr.step($step_expr));
+    }
+    expr
+}
+
+/// Scenarios tagged `@ignore`, or in a feature tagged `@ignore`, are ignored.
+fn is_ignored(feature_tags: &[String], scenario_tags: &[String]) -> bool {
+    feature_tags.iter().chain(scenario_tags).any(|tag| tag == "ignore")
+}
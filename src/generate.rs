@@ -4,6 +4,7 @@
 // Licensed under the MIT license. This file may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::mem;
 use std::ops::Deref;
 
 use syntax::{ast, abi, codemap};
@@ -26,7 +27,16 @@ pub trait Generate<Cfg> {
 
 impl<'a> Generate<&'a DescribeState> for Test {
     fn generate(self, sp: codemap::Span, cx: &mut base::ExtCtxt, state: &'a DescribeState) -> P<ast::Item> {
-        let Test { description, block, test_config } = self;
+        let Test { description, block, test_config, serial, isolated, attrs: user_attrs, .. } = self;
+
+        // libtest can neither keep tests from running in parallel nor run them in a process of
+        // their own.
+        if serial.is_some() && !state.harness {
+            cx.span_err(sp, "`serial` is only supported in harness mode, `#![plugin(stainless(harness))]`");
+        }
+        if isolated && !state.harness {
+            cx.span_err(sp, "`isolated` is only supported in harness mode, `#![plugin(stainless(harness))]`");
+        }
 
         // Create the #[test] attribute.
         let test_attribute = cx.attribute(sp, cx.meta_word(sp, Symbol::intern("test")));
@@ -42,38 +52,27 @@ impl<'a> Generate<&'a DescribeState> for Test {
                                                 vec![non_snake_word]);
         let allow_non_snake_case = cx.attribute(sp, allow_non_snake_case);
 
-        // Create the full test body by splicing in the statements and view items of the before and
-        // after blocks if they are present.
-        let test_body = match (&state.before_each, &state.after_each) {
-            (&None, &None) => block,
-
-            (&Some(ref before), &None) => {
-                P(ast::Block {
-                    stmts: before.stmts.iter()
-                            .chain(block.stmts.iter())
-                            .cloned().collect(),
-                    ..block.deref().clone()
-                })
-            },
-
-            (&None, &Some(ref after)) => {
-                P(ast::Block {
-                    stmts: block.stmts.iter()
-                            .chain(after.stmts.iter())
-                            .cloned().collect(),
-                    ..block.deref().clone()
-                })
-            },
+        // In harness mode, tell the runtime where the `before_each` statements end, so that it can
+        // tell hook failures from test failures.
+        let block = if state.harness {
+            enter_phase(cx, quote_expr!(cx, ::stainless_runtime::Phase::Test), block)
+        } else {
+            block
+        };
 
-            (&Some(ref before), &Some(ref after)) => {
-                P(ast::Block {
-                    stmts: before.stmts.iter()
-                            .chain(block.stmts.iter())
-                            .chain(after.stmts.iter())
-                            .cloned().collect(),
-                    ..block.deref().clone()
-                })
-            }
+        // Create the full test body by splicing in the statements of the before blocks in order
+        // and of the after blocks in reverse order, so that the hooks of enclosing blocks wrap
+        // the hooks of nested ones.
+        let test_body = if state.before_each.is_empty() && state.after_each.is_empty() {
+            block
+        } else {
+            P(ast::Block {
+                stmts: state.before_each.iter().flat_map(|before| before.stmts.iter())
+                        .chain(block.stmts.iter())
+                        .chain(state.after_each.iter().rev().flat_map(|after| after.stmts.iter()))
+                        .cloned().collect(),
+                ..block.deref().clone()
+            })
         };
 
         // Constructing attributes:
@@ -81,8 +80,15 @@ impl<'a> Generate<&'a DescribeState> for Test {
         // #[allow(non_snake_case_attr)] as description may contain upper case
         // #[should_panic] or #[should_panic(expected = "...")] if specified
         // #[ignore] if specified
-        let mut attrs = vec![test_attribute, allow_non_snake_case];
-        if test_config.failing {
+        // followed by any attributes written before the test
+        //
+        // In harness mode the test is registered with the runtime instead, which handles
+        // `failing` and `ignore` itself.
+        let mut attrs = vec![allow_non_snake_case];
+        if !state.harness {
+            attrs.push(test_attribute);
+        }
+        if test_config.failing && !state.harness {
             match test_config.failing_msg {
                 Some(msg) => {
                     // Create #[should_panic(expected = "...")] attribute
@@ -105,14 +111,15 @@ impl<'a> Generate<&'a DescribeState> for Test {
                 None => attrs.push(should_panic)
             };
         }
-        if test_config.ignored {
+        if test_config.ignored && !state.harness {
             attrs.push(ignore);
         }
+        attrs.extend(user_attrs);
 
         // Create the final Item that represents the test.
         P(ast::Item {
             // Name it with a snake_case version of the description.
-            ident: cx.ident_of(&description.replace(" ", "_")),
+            ident: test_ident(cx, &description),
             attrs: attrs,
             id: ast::DUMMY_NODE_ID,
             node: ast::ItemKind::Fn(
@@ -139,26 +146,37 @@ impl<'a> Generate<&'a DescribeState> for Test {
     }
 }
 
-impl Generate<()> for Bench {
-    fn generate(self, sp: codemap::Span, cx: &mut base::ExtCtxt, _: ()) -> P<ast::Item> {
-        let Bench { bench, description, block } = self;
+impl<'a> Generate<&'a DescribeState> for Bench {
+    fn generate(self, sp: codemap::Span, cx: &mut base::ExtCtxt, state: &'a DescribeState) -> P<ast::Item> {
+        let Bench { bench, description, block, attrs, .. } = self;
 
-        // Create the #[bench] attribute.
-        let bench_attribute = cx.attribute(sp, cx.meta_word(sp, Symbol::intern("bench")));
+        // Create the #[bench] attribute, unless the benchmark is registered with the runtime.
+        let bench_attribute = if state.harness {
+            None
+        } else {
+            Some(cx.attribute(sp, cx.meta_word(sp, Symbol::intern("bench"))))
+        };
+
+        // The Bencher of libtest, or of the runtime in harness mode.
+        let bencher = if state.harness {
+            quote_ty!(cx, &mut ::stainless_runtime::Bencher)
+        } else {
+            quote_ty!(cx, &mut ::test::Bencher)
+        };
 
         // Create the final Item that represents the benchmark.
         P(ast::Item {
             // Name it with a snake_case version of the description.
-            ident: cx.ident_of(&description.replace(" ", "_")),
+            ident: test_ident(cx, &description),
 
-            // Add #[test] and possibly #[should_panic]
-            attrs: vec![bench_attribute],
+            // Add #[bench] and any attributes written before the benchmark.
+            attrs: bench_attribute.into_iter().chain(attrs).collect(),
             id: ast::DUMMY_NODE_ID,
             node: ast::ItemKind::Fn(
                 // Takes one argument of &mut Bencher
                 P(ast::FnDecl {
                     inputs: vec![ast::Arg {
-                        ty: quote_ty!(cx, &mut ::test::Bencher),
+                        ty: bencher,
                         pat: quote_pat!(cx, $bench),
                         id: ast::DUMMY_NODE_ID
                     }],
@@ -187,8 +205,9 @@ impl<'a> Generate<&'a DescribeState> for SubBlock {
     fn generate(self, sp: codemap::Span, cx: &mut base::ExtCtxt, state: &'a DescribeState) -> P<ast::Item> {
         match self {
             SubBlock::Test(test) => test.generate(sp, cx, state),
-            SubBlock::Bench(bench) => bench.generate(sp, cx, ()),
-            SubBlock::Describe(item) => item.generate(sp, cx, Some(state))
+            SubBlock::Bench(bench) => bench.generate(sp, cx, state),
+            SubBlock::Describe(item) => item.generate(sp, cx, Some(state)),
+            SubBlock::Item(item) => item
         }
     }
 }
@@ -200,33 +219,64 @@ impl<'a> Generate<Option<&'a DescribeState>> for DescribeState {
         let name = self.name.clone().unwrap();
 
         if let Some(state) = state {
-            if let Some(ref parent) = state.before_each {
-                self.before_each = match self.before_each {
-                    Some(ref now) => Some(P(ast::Block {
-                        stmts: parent.stmts.iter().chain(&*now.stmts).cloned().collect(),
-                        ..now.deref().clone()
-                    })),
-                    None => Some(P(parent.deref().clone()))
-                };
-            }
+            // Tags and harness mode of the enclosing blocks are inherited.
+            self.tags = state.tags.iter().chain(&self.tags).cloned().collect();
+            self.harness = state.harness;
+        }
 
-            if let Some(ref parent) = state.after_each {
-                self.after_each = match self.after_each {
-                    Some(ref now) => Some(P(ast::Block {
-                        stmts: now.stmts.iter().chain(&*parent.stmts).cloned().collect(),
-                        ..now.deref().clone()
-                    })),
-                    None => Some(P(parent.deref().clone()))
-                };
-            }
+        // In harness mode, each hook reports itself to the runtime before it runs, so that
+        // failures can be attributed to the `describe!` block the hook belongs to.
+        if self.harness {
+            let scope = name.name.as_str();
+            let scope = &*scope;
+            let blocks = mem::replace(&mut self.before_each, vec![]);
+            self.before_each = blocks.into_iter().map(|block| {
+                enter_phase(cx, quote_expr!(cx, ::stainless_runtime::Phase::BeforeEach($scope)), block)
+            }).collect();
+            let blocks = mem::replace(&mut self.after_each, vec![]);
+            self.after_each = blocks.into_iter().map(|block| {
+                enter_phase(cx, quote_expr!(cx, ::stainless_runtime::Phase::AfterEach($scope)), block)
+            }).collect();
+        }
+
+        // The hooks of the enclosing blocks come first. The `after_each` blocks run in reverse,
+        // so the ones of this block run before the ones of the enclosing blocks.
+        if let Some(state) = state {
+            self.before_each = state.before_each.iter().chain(&self.before_each).cloned().collect();
+            self.after_each = state.after_each.iter().chain(&self.after_each).cloned().collect();
+        }
+
+        if self.serial.is_some() && !self.harness {
+            cx.span_err(sp, "`#![serial]` is only supported in harness mode, `#![plugin(stainless(harness))]`");
+        }
+        if self.isolated && !self.harness {
+            cx.span_err(sp, "`#![isolated]` is only supported in harness mode, `#![plugin(stainless(harness))]`");
         }
 
-        // Get a glob import of all items in scope to the module that `describe!` is called from.
+        // Get a glob import of all items in scope to the module that `describe!` is called from,
+        // unless it was disabled with `#![no_super_glob]`.
         //
-        // This glob is `pub use super::*` so that nested `describe!` blocks (which will also contain
-        // this glob) will be able to see all the symbols.
-        let super_glob = cx.item_use_glob(sp, ast::Visibility::Public, vec![cx.ident_of("super")]);
-        let mut items = vec![super_glob];
+        // The glob is private: it still sees the private items of the enclosing module, and nested
+        // `describe!` blocks (which will also contain this glob) can see everything it imported.
+        // Blocks which use nothing of the enclosing module would warn about it without the allow.
+        let mut items = vec![];
+        if self.super_glob {
+            let unused_imports_word = cx.meta_list_item_word(sp, Symbol::intern("unused_imports"));
+            let allow_unused_imports = cx.meta_list(sp, Symbol::intern("allow"),
+                                                    vec![unused_imports_word]);
+            let allow_unused_imports = cx.attribute(sp, allow_unused_imports);
+            let glob = cx.item_use_glob(sp, ast::Visibility::Inherited, vec![cx.ident_of("super")]);
+            items.push(glob.map(|mut item| {
+                item.attrs.push(allow_unused_imports);
+                item
+            }));
+        }
+
+        // In harness mode, register the tree with the runtime. The module is public so that
+        // `stainless_main!` can reach `__stainless_suite` from anywhere in the crate.
+        if self.harness {
+            items.push(self.suite_fn(sp, cx));
+        }
 
         // Create subblocks from a full DescribeState
         items.extend(self.subblocks.clone().into_iter().map(|block| {
@@ -234,6 +284,135 @@ impl<'a> Generate<Option<&'a DescribeState>> for DescribeState {
         }));
 
         // Generate the new module.
-        cx.item_mod(sp, sp, name, vec![], items)
+        let module = cx.item_mod(sp, sp, name, self.attrs.clone(), items);
+        if self.harness {
+            module.map(|module| ast::Item { vis: ast::Visibility::Public, ..module })
+        } else {
+            module
+        }
     }
 }
+
+impl DescribeState {
+    /// Generate `pub fn __stainless_suite() -> ::stainless_runtime::Suite`, which builds the
+    /// runtime representation of this `describe!` block in harness mode.
+    fn suite_fn(&self, sp: codemap::Span, cx: &mut base::ExtCtxt) -> P<ast::Item> {
+        let name = self.name.unwrap().name.as_str();
+        let name = &*name;
+
+        let mut suite = quote_expr!(cx, ::stainless_runtime::Suite::new($name));
+        for tag in &self.tags {
+            let tag = &**tag;
+            suite = quote_expr!(cx, $suite.tag($tag));
+        }
+        if let Some(ref group) = self.serial {
+            let group = serial_group(cx, group);
+            suite = quote_expr!(cx, $suite.serial($group));
+        }
+        if self.isolated {
+            suite = quote_expr!(cx, $suite.isolated());
+        }
+
+        let registrations: Vec<ast::Stmt> = self.subblocks.iter()
+            .filter_map(|block| self.registration(sp, cx, block))
+            .collect();
+
+        quote_item!(cx,
+            #[allow(unused_mut)]
+            pub fn __stainless_suite() -> ::stainless_runtime::Suite {
+                let mut suite = $suite;
+                $registrations
+                suite
+            }
+        ).unwrap()
+    }
+
+    /// The statement adding a subblock to `suite` in `__stainless_suite`, if it is a test,
+    /// benchmark or nested `describe!`.
+    ///
+    /// The `#[cfg]` attributes of the subblock are copied onto the statement, so that
+    /// configured out blocks are not registered either.
+    fn registration(&self, _sp: codemap::Span, cx: &mut base::ExtCtxt,
+                    block: &SubBlock) -> Option<ast::Stmt> {
+        fn cfgs(attrs: &[ast::Attribute]) -> Vec<ast::Attribute> {
+            attrs.iter().filter(|attr| attr.check_name("cfg")).cloned().collect()
+        }
+
+        match *block {
+            SubBlock::Test(ref test) => {
+                let description = &*test.description;
+                let ident = test_ident(cx, description);
+
+                let mut expr = quote_expr!(cx, ::stainless_runtime::Test::new($description, $ident));
+                for tag in self.tags.iter().chain(&test.tags) {
+                    let tag = &**tag;
+                    expr = quote_expr!(cx, $expr.tag($tag));
+                }
+                if test.test_config.ignored {
+                    expr = quote_expr!(cx, $expr.ignored());
+                }
+                if let Some(ref group) = test.serial {
+                    let group = serial_group(cx, group);
+                    expr = quote_expr!(cx, $expr.serial($group));
+                }
+                if test.isolated {
+                    expr = quote_expr!(cx, $expr.isolated());
+                }
+                if test.test_config.failing {
+                    expr = match test.test_config.failing_msg {
+                        Some((msg, _)) => {
+                            let msg = msg.as_str();
+                            let msg = &*msg;
+                            quote_expr!(cx, $expr.should_panic(Some($msg)))
+                        },
+                        None => quote_expr!(cx, $expr.should_panic(None))
+                    };
+                }
+
+                let cfgs = cfgs(&test.attrs);
+                quote_stmt!(cx, $cfgs let _ = suite.test($expr);)
+            },
+
+            SubBlock::Bench(ref bench) => {
+                let description = &*bench.description;
+                let ident = test_ident(cx, description);
+                let cfgs = cfgs(&bench.attrs);
+                quote_stmt!(cx, $cfgs let _ = suite.bench(::stainless_runtime::Bench::new($description, $ident));)
+            },
+
+            SubBlock::Describe(ref describe) => {
+                let name = describe.name.unwrap();
+                let cfgs = cfgs(&describe.attrs);
+                quote_stmt!(cx, $cfgs let _ = suite.describe($name::__stainless_suite());)
+            },
+
+            SubBlock::Item(_) => None
+        }
+    }
+}
+
+/// `Some("group")` or `None`, the argument of the `serial` builder methods of the runtime.
+fn serial_group(cx: &mut base::ExtCtxt, group: &Option<String>) -> P<ast::Expr> {
+    match *group {
+        Some(ref group) => {
+            let group = &**group;
+            quote_expr!(cx, Some($group))
+        },
+        None => quote_expr!(cx, None)
+    }
+}
+
+/// Prepend `::stainless_runtime::__enter_phase($phase);` to the statements of a block.
+fn enter_phase(cx: &base::ExtCtxt, phase: P<ast::Expr>, block: P<ast::Block>) -> P<ast::Block> {
+    let marker = quote_stmt!(cx, ::stainless_runtime::__enter_phase($phase);).unwrap();
+    block.map(|block| ast::Block {
+        stmts: Some(marker).into_iter().chain(block.stmts).collect(),
+        ..block
+    })
+}
+
+/// The name of the function generated for a test or benchmark, a snake_case version of the
+/// description.
+fn test_ident(cx: &base::ExtCtxt, description: &str) -> ast::Ident {
+    cx.ident_of(&description.replace(" ", "_"))
+}
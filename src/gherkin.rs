@@ -0,0 +1,523 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::mem;
+
+/// A `.feature` file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Feature {
+    /// The name following `Feature:`.
+    pub name: String,
+    /// The tags of the feature, without the `@`.
+    pub tags: Vec<String>,
+    /// The name of the module generated for the feature.
+    pub module: String,
+    /// The scenarios, with every row of the examples of a `Scenario Outline` as a scenario of
+    /// its own.
+    pub scenarios: Vec<Scenario>
+}
+
+/// A scenario of a feature, run as one test.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scenario {
+    /// The name following `Scenario:`, with the values of the example filled in for outlines.
+    pub name: String,
+    /// The tags of the scenario and of its `Rule:` and `Examples:`, without the `@`.
+    pub tags: Vec<String>,
+    /// The line of `Scenario:`, or of the example row for outlines.
+    pub line: usize,
+    /// The name of the test function generated for the scenario, unique within the feature.
+    pub function: String,
+    /// The steps, starting with the steps of the `Background:` blocks.
+    pub steps: Vec<Step>
+}
+
+/// The keyword of a step, with `And`, `But` and `*` resolved to the keyword they continue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepKind {
+    /// A `Given` step, which sets up the scenario.
+    Given,
+    /// A `When` step, which performs the action.
+    When,
+    /// A `Then` step, which checks the outcome.
+    Then
+}
+
+impl StepKind {
+    /// The name of the variant, as in `stainless_runtime::StepKind`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            StepKind::Given => "Given",
+            StepKind::When => "When",
+            StepKind::Then => "Then"
+        }
+    }
+}
+
+/// A step of a scenario.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    /// The keyword as written, like `Given` or `And`.
+    pub keyword: String,
+    /// What the keyword stands for.
+    pub kind: StepKind,
+    /// The text following the keyword.
+    pub text: String,
+    /// The line of the step.
+    pub line: usize,
+    /// The rows of the data table following the step, if any.
+    pub table: Vec<Vec<String>>,
+    /// The doc string following the step, if any.
+    pub docstring: Option<String>
+}
+
+// A scenario while its steps and examples are read.
+struct Pending {
+    name: String,
+    tags: Vec<String>,
+    line: usize,
+    outline: bool,
+    steps: Vec<Step>,
+    examples: Vec<Examples>
+}
+
+struct Examples {
+    tags: Vec<String>,
+    header: Option<Vec<String>>,
+    rows: Vec<(usize, Vec<String>)>
+}
+
+// Where the steps which are read go.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Feature,
+    Background,
+    RuleBackground,
+    Rule,
+    Scenario,
+    Examples
+}
+
+/// Parse the contents of a `.feature` file while compiling `feature!`. Errors are prefixed with
+/// the line they occur on.
+pub fn parse_feature(source: &str) -> Result<Feature, String> {
+    let mut feature: Option<Feature> = None;
+    let mut background: Vec<Step> = vec![];
+    let mut rule_background: Vec<Step> = vec![];
+    let mut rule_tags: Vec<String> = vec![];
+    let mut in_rule = false;
+    let mut tags: Vec<String> = vec![];
+    let mut pending: Option<Pending> = None;
+    let mut section = Section::Feature;
+    // Whether steps were read in the current section, after which text is no description.
+    let mut has_steps = false;
+    // The delimiter, indentation and lines of the doc string being read.
+    let mut docstring: Option<(&str, usize, Vec<String>)> = None;
+    let mut scenarios = vec![];
+
+    for (index, raw) in source.lines().enumerate() {
+        let number = index + 1;
+        let line = raw.trim();
+        let error = |message: String| Err(format!("line {}: {}", number, message));
+
+        if let Some((delimiter, indent, mut lines)) = docstring.take() {
+            if line == delimiter {
+                let text = lines.join("\n");
+                if let Some(step) = last_step(section, &mut background, &mut rule_background, &mut pending) {
+                    step.docstring = Some(text);
+                }
+            } else {
+                let skip = raw.chars().take(indent).take_while(|c| c.is_whitespace()).count();
+                lines.push(raw.chars().skip(skip).collect::<String>().replace("\\\"\\\"\\\"", "\"\"\""));
+                docstring = Some((delimiter, indent, lines));
+            }
+            continue;
+        }
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('@') {
+            let end = line.find(" #").unwrap_or(line.len());
+            tags.extend(line[..end].split_whitespace().map(|tag| tag.trim_left_matches('@').to_string()));
+            continue;
+        }
+
+        if line.starts_with('|') {
+            let cells = table_row(line);
+            if section == Section::Examples {
+                let examples = pending.as_mut().and_then(|pending| pending.examples.last_mut()).unwrap();
+                if examples.header.is_none() {
+                    examples.header = Some(cells);
+                } else {
+                    examples.rows.push((number, cells));
+                }
+                continue;
+            }
+            match last_step(section, &mut background, &mut rule_background, &mut pending) {
+                Some(ref mut step) if has_steps => step.table.push(cells),
+                _ => return error("a table has to follow a step or `Examples:`".to_string())
+            }
+            continue;
+        }
+
+        if line.starts_with("\"\"\"") || line.starts_with("```") {
+            if !has_steps || section == Section::Examples {
+                return error("a doc string has to follow a step".to_string());
+            }
+            let delimiter = if line.starts_with("```") { "```" } else { "\"\"\"" };
+            let indent = raw.len() - raw.trim_left().len();
+            docstring = Some((delimiter, indent, vec![]));
+            continue;
+        }
+
+        if let Some((keyword, name)) = header(line) {
+            if keyword != "Feature" && feature.is_none() {
+                return error(format!("`{}:` outside of a `Feature:`", keyword));
+            }
+            if keyword != "Examples" {
+                if let Some(pending) = pending.take() {
+                    finish(pending, &background, &rule_background, &mut scenarios)?;
+                }
+            }
+            let tags = mem::replace(&mut tags, vec![]);
+            has_steps = false;
+
+            match keyword {
+                "Feature" => {
+                    if feature.is_some() {
+                        return error("a file can only contain one `Feature:`".to_string());
+                    }
+                    feature = Some(Feature { name: name.to_string(), tags, module: String::new(), scenarios: vec![] });
+                    section = Section::Feature;
+                },
+                "Rule" => {
+                    rule_background.clear();
+                    rule_tags = tags;
+                    in_rule = true;
+                    section = Section::Rule;
+                },
+                "Background" => {
+                    section = match section {
+                        Section::Feature if background.is_empty() => Section::Background,
+                        Section::Rule if rule_background.is_empty() => Section::RuleBackground,
+                        _ => return error("`Background:` has to come before the scenarios".to_string())
+                    };
+                },
+                "Examples" => {
+                    match pending {
+                        Some(ref mut pending) => pending.examples.push(Examples { tags, header: None, rows: vec![] }),
+                        None => return error("`Examples:` has to follow a `Scenario Outline:`".to_string())
+                    }
+                    section = Section::Examples;
+                },
+                outline => {
+                    let mut all_tags = if in_rule { rule_tags.clone() } else { vec![] };
+                    all_tags.extend(tags);
+                    pending = Some(Pending {
+                        name: name.to_string(),
+                        tags: all_tags,
+                        line: number,
+                        outline: outline == "Scenario Outline",
+                        steps: vec![],
+                        examples: vec![]
+                    });
+                    section = Section::Scenario;
+                }
+            }
+            continue;
+        }
+
+        if let Some((keyword, text)) = step_keyword(line) {
+            if !tags.is_empty() {
+                return error("tags have to precede `Feature:`, `Rule:`, a scenario or `Examples:`".to_string());
+            }
+            let steps = match section {
+                Section::Background => &mut background,
+                Section::RuleBackground => &mut rule_background,
+                Section::Scenario => &mut pending.as_mut().unwrap().steps,
+                Section::Examples => return error("steps can not follow `Examples:`".to_string()),
+                Section::Feature | Section::Rule => {
+                    return error(format!("the step `{} {}` is not part of a scenario", keyword, text))
+                }
+            };
+            let kind = match keyword {
+                "Given" => StepKind::Given,
+                "When" => StepKind::When,
+                "Then" => StepKind::Then,
+                _ => steps.last().map_or(StepKind::Given, |step| step.kind)
+            };
+            steps.push(Step {
+                keyword: keyword.to_string(),
+                kind,
+                text: text.to_string(),
+                line: number,
+                table: vec![],
+                docstring: None
+            });
+            has_steps = true;
+            continue;
+        }
+
+        // Free text describes the feature, rule or scenario it follows.
+        if has_steps || section == Section::Examples {
+            return error(format!("expected a step, found `{}`", line));
+        }
+        if feature.is_none() {
+            return error(format!("expected `Feature:`, found `{}`", line));
+        }
+    }
+
+    if docstring.is_some() {
+        return Err("the last doc string is not closed".to_string());
+    }
+    if let Some(pending) = pending.take() {
+        finish(pending, &background, &rule_background, &mut scenarios)?;
+    }
+
+    let mut feature = feature.ok_or_else(|| "there is no `Feature:` in the file".to_string())?;
+    feature.module = identifier(&feature.name, "feature");
+
+    let mut functions: Vec<String> = vec![];
+    for mut scenario in scenarios {
+        let base = identifier(&scenario.name, "scenario");
+        let mut function = base.clone();
+        let mut count = 1;
+        while functions.contains(&function) {
+            count += 1;
+            function = format!("{}_{}", base, count);
+        }
+        functions.push(function.clone());
+        scenario.function = function;
+        feature.scenarios.push(scenario);
+    }
+    Ok(feature)
+}
+
+fn last_step<'p>(section: Section, background: &'p mut [Step], rule_background: &'p mut [Step],
+                 pending: &'p mut Option<Pending>) -> Option<&'p mut Step> {
+    match section {
+        Section::Background => background.last_mut(),
+        Section::RuleBackground => rule_background.last_mut(),
+        Section::Scenario => pending.as_mut().and_then(|pending| pending.steps.last_mut()),
+        _ => None
+    }
+}
+
+fn header(line: &str) -> Option<(&'static str, &str)> {
+    const HEADERS: &[(&str, &str)] = &[
+        ("Feature:", "Feature"),
+        ("Rule:", "Rule"),
+        ("Background:", "Background"),
+        ("Scenario Outline:", "Scenario Outline"),
+        ("Scenario Template:", "Scenario Outline"),
+        ("Scenario:", "Scenario"),
+        ("Example:", "Scenario"),
+        ("Examples:", "Examples"),
+        ("Scenarios:", "Examples")
+    ];
+    HEADERS.iter()
+        .find(|&&(prefix, _)| line.starts_with(prefix))
+        .map(|&(prefix, keyword)| (keyword, line[prefix.len()..].trim()))
+}
+
+fn step_keyword(line: &str) -> Option<(&str, &str)> {
+    ["Given ", "When ", "Then ", "And ", "But ", "* "].iter()
+        .find(|keyword| line.starts_with(*keyword))
+        .map(|keyword| (keyword.trim(), line[keyword.len()..].trim()))
+}
+
+fn table_row(line: &str) -> Vec<String> {
+    let mut cells = vec![];
+    let mut cell = String::new();
+    let mut chars = line.trim_left_matches('|').chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => cell.push('\n'),
+                Some(other) => cell.push(other),
+                None => cell.push('\\')
+            },
+            '|' => cells.push(mem::replace(&mut cell, String::new()).trim().to_string()),
+            c => cell.push(c)
+        }
+    }
+    cells
+}
+
+// Add a scenario, or one scenario per example of an outline.
+fn finish(pending: Pending, background: &[Step], rule_background: &[Step], scenarios: &mut Vec<Scenario>)
+          -> Result<(), String> {
+    let steps: Vec<Step> = background.iter().chain(rule_background).cloned().chain(pending.steps).collect();
+
+    if pending.examples.is_empty() {
+        if pending.outline {
+            return Err(format!("line {}: the outline `{}` has no `Examples:`", pending.line, pending.name));
+        }
+        scenarios.push(Scenario { name: pending.name, tags: pending.tags, line: pending.line, function: String::new(), steps });
+        return Ok(());
+    }
+
+    let mut count = 0;
+    for examples in &pending.examples {
+        let header = match examples.header {
+            Some(ref header) => header,
+            None => return Err(format!("line {}: the examples of `{}` have no table", pending.line, pending.name))
+        };
+        for &(line, ref row) in &examples.rows {
+            if row.len() != header.len() {
+                return Err(format!("line {}: the example has {} cells, but the header has {}", line, row.len(),
+                                   header.len()));
+            }
+            count += 1;
+            let fill = |text: &str| header.iter().zip(row).fold(text.to_string(), |text, (name, value)| {
+                text.replace(&format!("<{}>", name), value)
+            });
+
+            let mut name = fill(&pending.name);
+            if name == pending.name {
+                name = format!("{} (example {})", name, count);
+            }
+            let steps = steps.iter().map(|step| Step {
+                text: fill(&step.text),
+                table: step.table.iter().map(|row| row.iter().map(|cell| fill(cell)).collect()).collect(),
+                docstring: step.docstring.as_ref().map(|docstring| fill(docstring)),
+                ..step.clone()
+            }).collect();
+            let tags = pending.tags.iter().chain(&examples.tags).cloned().collect();
+            scenarios.push(Scenario { name, tags, line, function: String::new(), steps });
+        }
+    }
+    Ok(())
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "do", "dyn", "else", "enum", "extern",
+    "false", "final", "fn", "for", "if", "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut",
+    "override", "priv", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try",
+    "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield"
+];
+
+// Lowercase the name and replace everything but letters and digits with underscores, which
+// makes a valid identifier of any name.
+fn identifier(name: &str, fallback: &str) -> String {
+    let mut ident = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() && c.is_ascii() {
+            ident.push(c);
+        } else if !ident.is_empty() && !ident.ends_with('_') {
+            ident.push('_');
+        }
+    }
+    let ident = ident.trim_right_matches('_');
+
+    if ident.is_empty() {
+        fallback.to_string()
+    } else if ident.starts_with(|c: char| c.is_digit(10)) {
+        format!("{}_{}", fallback, ident)
+    } else if KEYWORDS.contains(&ident) {
+        format!("{}_", ident)
+    } else {
+        ident.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_feature, StepKind};
+
+    const FEATURE: &'static str = r#"# A comment
+@kitchen
+Feature: Eating cucumbers
+  Everybody likes cucumbers.
+
+  Background:
+    Given a basket
+
+  @smoke
+  Scenario: Eating one
+    Given there are 5 cucumbers
+    When I eat 1 cucumber
+    Then I should have 4 cucumbers
+    But the basket should not be empty
+
+  Scenario Outline: Eating <eat>
+    Given there are <start> cucumbers
+    When I eat <eat> cucumbers
+    Then I should have <left> cucumbers
+
+    Examples:
+      | start | eat | left |
+      |    12 |   5 |    7 |
+      |    20 |   5 |   15 |
+
+  Scenario: Eating one
+    Given the recipe:
+      """
+      Slice
+        thinly
+      """
+    And the prices
+      | kind     | price |
+      | pickled  | 2     |
+"#;
+
+    #[test]
+    fn parses_features() {
+        let feature = parse_feature(FEATURE).unwrap();
+        assert_eq!(feature.name, "Eating cucumbers");
+        assert_eq!(feature.module, "eating_cucumbers");
+        assert_eq!(feature.tags, vec!["kitchen"]);
+
+        let names: Vec<&str> = feature.scenarios.iter().map(|scenario| &*scenario.name).collect();
+        assert_eq!(names, vec!["Eating one", "Eating 5", "Eating 5", "Eating one"]);
+        let functions: Vec<&str> = feature.scenarios.iter().map(|scenario| &*scenario.function).collect();
+        assert_eq!(functions, vec!["eating_one", "eating_5", "eating_5_2", "eating_one_2"]);
+
+        let first = &feature.scenarios[0];
+        assert_eq!(first.tags, vec!["smoke"]);
+        assert_eq!(first.line, 10);
+        assert_eq!(first.steps.len(), 5);
+        assert_eq!(first.steps[0].text, "a basket");
+        assert_eq!((&*first.steps[4].keyword, first.steps[4].kind), ("But", StepKind::Then));
+
+        let outline = &feature.scenarios[2];
+        assert_eq!(outline.line, 24);
+        assert_eq!(outline.steps[1].text, "there are 20 cucumbers");
+        assert_eq!(outline.steps[3].text, "I should have 15 cucumbers");
+
+        let last = &feature.scenarios[3];
+        assert_eq!(last.steps[1].docstring.as_ref().unwrap(), "Slice\n  thinly");
+        assert_eq!(last.steps[2].table, vec![vec!["kind", "price"], vec!["pickled", "2"]]);
+    }
+
+    #[test]
+    fn numbers_examples_of_outlines_without_placeholders_in_the_name() {
+        let source = "Feature: F\nScenario Outline: Eating\n  Given <n>\n  Examples:\n    | n |\n    | 1 |\n    | 2 |\n";
+        let feature = parse_feature(source).unwrap();
+        let names: Vec<&str> = feature.scenarios.iter().map(|scenario| &*scenario.name).collect();
+        assert_eq!(names, vec!["Eating (example 1)", "Eating (example 2)"]);
+    }
+
+    #[test]
+    fn reports_errors_with_lines() {
+        assert_eq!(parse_feature("Scenario: no feature\n").unwrap_err(), "line 1: `Scenario:` outside of a `Feature:`");
+        assert_eq!(parse_feature("Feature: F\n  Scenario: S\n    Given x\n    Gvien y\n").unwrap_err(),
+                   "line 4: expected a step, found `Gvien y`");
+        assert_eq!(parse_feature("Feature: F\n  Scenario Outline: S\n    Given <x>\n").unwrap_err(),
+                   "line 2: the outline `S` has no `Examples:`");
+        assert_eq!(parse_feature("# nothing\n").unwrap_err(), "there is no `Feature:` in the file");
+    }
+
+    #[test]
+    fn makes_identifiers_of_names() {
+        let feature = parse_feature("Feature: 2 Käse\n  Scenario: fn\n  Scenario: --\n").unwrap();
+        assert_eq!(feature.module, "feature_2_k_se");
+        let functions: Vec<&str> = feature.scenarios.iter().map(|scenario| &*scenario.function).collect();
+        assert_eq!(functions, vec!["fn_", "scenario"]);
+    }
+}
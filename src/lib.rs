@@ -4,7 +4,7 @@
 // Licensed under the MIT license. This file may not be copied, modified, or distributed except
 // according to those terms.
 
-#![feature(plugin_registrar, quote, rustc_private)]
+#![feature(macro_vis_matcher, plugin_registrar, quote, rustc_private)]
 #![deny(missing_docs, warnings)]
 
 //! > Stainless is a lightweight, flexible, unopinionated testing framework.
@@ -46,6 +46,7 @@
 //! * `it`, `failing`, and `ignore`
 //! * `bench`
 //! * nested `describe!`
+//! * ordinary items such as `use`, `fn` and `struct`
 //!
 //! `before_each` and `after_each` allow you to group common
 //! initialization and teardown for a group of tests into a single block,
@@ -66,9 +67,167 @@
 //! and `after_each` apply. Of course the `before_each` and `after_each`
 //! blocks of the wrapping `describe!` blocks are executed as well.
 //!
+//! A `describe!` block can have any number of `before_each` and
+//! `after_each` blocks, to keep setup for different concerns apart.
+//! `before_each` blocks run in the order they are written, starting with
+//! the ones of the wrapping blocks, and `after_each` blocks in reverse
+//! order, ending with the ones of the wrapping blocks. Like the statements
+//! of a single hook, everything bound in a `before_each` block is
+//! available to the following ones.
+//!
 //! Together, these 4 types of subblocks give you more flexibility and
 //! control than the built in testing infrastructure.
 //!
+//! ## Attributes
+//!
+//! Outer attributes and doc comments can be written before any `it`,
+//! `failing`, `ignore`, `bench` or nested `describe!` block. They are
+//! copied onto the generated function or module, so `#[cfg(...)]`,
+//! `#[allow(...)]` and attributes from other crates work as usual. A
+//! `#[cfg(...)]` on a `describe!` block removes the whole block, including
+//! its `before_each` and `after_each`.
+//!
+//! ```rust
+//! describe! stainless {
+//!     /// Only runs on unix.
+//!     #[cfg(unix)]
+//!     it "uses unix sockets" {
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! ## Tags
+//!
+//! Tests can be tagged by writing `tags(...)` between the description and
+//! the body, and `describe!` blocks can be tagged with `#![tags(...)]` at
+//! the top of their body. Tests inherit the tags of all enclosing
+//! `describe!` blocks.
+//!
+//! ```rust
+//! describe! database {
+//!     #![tags(db)]
+//!
+//!     it "stores records" tags(slow) {
+//!         // Tagged with `db` and `slow`.
+//!     }
+//! }
+//! ```
+//!
+//! Tags are recorded for the stainless runtime, which selects tests with
+//! tag expressions such as `slow and not (db or network)`.
+//!
+//! ## Harness mode
+//!
+//! By default `describe!` generates plain `#[test]` functions, run by the
+//! built in test harness. In harness mode `describe!` instead registers the
+//! whole tree with the `stainless_runtime` crate, which runs the tests with
+//! its own scheduler and reporter and knows about `describe!` blocks, tags
+//! and the original descriptions. It accepts the same command line flags as
+//! the built in harness, plus `--tags` and `--exclude-tags`.
+//!
+//! Harness mode is enabled per test target:
+//!
+//! ``` toml
+//! [dev-dependencies]
+//! stainless = "*"
+//! stainless_runtime = "*"
+//!
+//! [[test]]
+//! name = "spec"
+//! harness = false
+//! ```
+//!
+//! ``` rust
+//! // tests/spec.rs
+//! #![feature(plugin)]
+//! #![plugin(stainless(harness))]
+//!
+//! #[macro_use]
+//! extern crate stainless_runtime;
+//!
+//! describe! addition {
+//!     it "adds" {
+//!         assert_eq!(2 + 2, 4);
+//!     }
+//! }
+//!
+//! // Generates `fn main`, listing the top-level `describe!` blocks.
+//! stainless_main!(addition);
+//! ```
+//!
+//! A top-level `describe!` block missing from `stainless_main!` would
+//! never run, so it fails to compile with the `unlisted_describe` lint.
+//!
+//! Run `cargo test --test spec -- --tags "slow and not db"` to select
+//! tests by tag.
+//!
+//! Filters containing `>` select tests by their `describe!` path and
+//! original description, such as `"stainless > nesting > makes it simple"`.
+//! Every level is a glob (`*`, `?`) or a regular expression between
+//! slashes, and `**` matches any number of levels, so `"** > /^makes/"`
+//! runs all tests whose description starts with `makes`. A glob on the
+//! last level only has to match the start of a name, which is how the
+//! filter above selects `makes it simple to categorize tests`. Add
+//! `--list` to print the matching tree without running anything.
+//!
+//! `--shuffle` runs the tests in a random order to expose tests depending
+//! on each other, and prints the seed it used at the start and after the
+//! failures. `--seed N` reproduces that order exactly, and
+//! `--shuffle-scope describe` only shuffles within every `describe!`
+//! block, so that each block still runs all of its tests together.
+//!
+//! `--fail-fast` stops starting new tests after the first failure, and
+//! `--max-failures N` after `N` failures. Tests which already started
+//! finish, including their `after_each` blocks, and the remaining tests
+//! are reported as not run.
+//!
+//! Tests run in parallel, on at most `--jobs N` threads. Tests which share
+//! a resource are kept apart with `serial`: a test marked `serial` runs
+//! while no other test runs, tests in the same `serial(group)` never run
+//! at the same time, and `#![serial]` runs the tests of a `describe!`
+//! block one at a time, while other blocks continue in parallel:
+//!
+//! ```rust
+//! describe! database {
+//!     #![serial(db)]
+//!
+//!     it "migrates the schema" { /* ... */ }
+//!     it "resets the counters" serial { /* ... */ }
+//! }
+//! ```
+//!
+//! Tests which call `std::process::exit`, abort or leave global state
+//! behind are marked `isolated`, or `#![isolated]` for a whole
+//! `describe!` block, and `--isolate` does the same for every test. Each
+//! of these tests runs in a new process of the test binary, and exit
+//! codes and signals are reported as failures along with everything the
+//! test printed.
+//!
+//! `--slowest 10` lists the ten slowest tests, with the time spent in
+//! their `before_each` and `after_each` blocks, and the ten slowest
+//! `describe!` blocks after the run. `--slow-threshold 500ms` marks every
+//! test taking longer than that as slow.
+//!
+//! `--format tap` prints TAP version 14, with nested `describe!` blocks
+//! as subtests, and `--format json` prints one JSON object per line for
+//! every event of the run. All output formats implement the `Reporter`
+//! trait of `stainless_runtime`.
+//!
+//! Custom reporters implement `Reporter` too, and are listed after the
+//! modules. They receive every event (entering and leaving `describe!`
+//! blocks, tests starting and finishing, failing `before_each` and
+//! `after_each` blocks and benchmark results) alongside the built-in ones:
+//!
+//! ```rust
+//! stainless_main!(addition; reporters: Dashboard::new(), Notifier::new());
+//! ```
+//!
+//! `--junit report.xml` additionally writes a JUnit XML report with one
+//! `<testsuite>` per `describe!` block. Enable the `nightly` feature of
+//! `stainless_runtime` to capture what tests print, which is shown for
+//! failed tests and included in the reports.
+//!
 //! ## Example
 //!
 //! ```rust
@@ -139,27 +298,243 @@
 //! }
 //! ```
 //!
-//! ## Importing modules
+//! `cargo stainless expand "stainless > nesting"` prints the exact module,
+//! with a comment naming the hook or test every statement comes from.
+//!
+//! ## Items and imports
 //!
-//! At this point it is not possible to put `use` statements inside the
-//! `describe!` blocks. To allow usage of data structures from other
-//! modules and crates each `describe!` block comes with a silent `pub use
-//! super::*;` in it. That way everything you `pub use` in the containing
-//! module is available in your tests.
+//! Ordinary Rust items such as `use` statements, helper functions,
+//! structs, constants and impls can be placed anywhere inside a
+//! `describe!` block. They are emitted unchanged into the generated module,
+//! so they are visible to all of its tests and nested `describe!` blocks.
 //!
 //! ```rust
-//! #[cfg(test)]
-//! mod tests {
-//!     pub use std::collections::HashMap;
+//! describe! stainless {
+//!     use std::collections::HashMap;
+//!
+//!     fn map_with_one() -> HashMap<u32, u32> {
+//!         let mut map = HashMap::new();
+//!         map.insert(1, 1);
+//!         map
+//!     }
+//!
+//!     it "can use HashMap" {
+//!         assert_eq!(map_with_one().len(), 1);
+//!     }
+//! }
+//! ```
 //!
-//!     describe! stainless {
-//!         it "can use HashMap" {
-//!             let map = HashMap::new();
+//! In addition, each `describe!` block comes with a silent `use super::*;`
+//! in it. That way everything in scope of the containing module, private
+//! items and imports included, is available in your tests as well. If the
+//! glob causes name clashes, disable it with `#![no_super_glob]` at the top
+//! of the `describe!` block and import what you need with `use` items
+//! instead. Nested `describe!` blocks still see the items of their parent.
+//!
+//! ```rust
+//! describe! stainless {
+//!     #![no_super_glob]
+//!
+//!     use std::collections::*;
+//!
+//!     it "only sees its own imports" {
+//!         let map: HashMap<u32, u32> = HashMap::new();
+//!     }
+//! }
+//! ```
+//!
+//! ## Lints
+//!
+//! `describe!` warns about common mistakes in specs:
+//!
+//! - `empty_test`: a test without statements.
+//! - `missing_assertion`: a test, including its hooks, without an
+//!   `assert*!` macro.
+//! - `hook_without_tests`: `before_each` or `after_each` in a block without
+//!   tests to run them.
+//! - `unused_before_each`: a binding of `before_each` which no test of the
+//!   block uses.
+//! - `failing_without_panic`: a `failing` test without a panicking macro, a
+//!   call, indexing or a division.
+//! - `non_sentence_description`: a description which does not read as a
+//!   sentence after "it", like `"test_add"` or `"addsNumbers"`.
+//!
+//! Tests and blocks which `#[cfg(...)]` removes are not checked.
+//!
+//! Allow them with `#![allow(...)]` at the top of a `describe!` block, which
+//! also allows them in nested blocks. Other lints in the same attribute are
+//! passed on to the compiler.
+//!
+//! ```rust
+//! describe! stack {
+//!     #![allow(missing_assertion, dead_code)]
+//!
+//!     it "pushes" {
+//!         check_push();
+//!     }
+//! }
+//! ```
+//!
+//! ## Scenarios
+//!
+//! `scenario` writes a test as a sequence of `given`, `when`, `then`, `and`
+//! and `but` steps, each with a description. The steps run in order as a
+//! single test, so everything bound in one step is available in the steps
+//! after it. When a step panics, the test prints which one, like
+//! `step failed: Then it has one element (tests/stack.rs:12)`.
+//!
+//! ```rust
+//! describe! stack {
+//!     scenario "pushing onto an empty stack" {
+//!         given "an empty stack" {
+//!             let mut stack: Vec<u32> = vec![];
+//!         }
+//!
+//!         when "an element is pushed" {
+//!             stack.push(1);
+//!         }
+//!
+//!         then "it has one element" {
+//!             assert_eq!(stack.len(), 1);
+//!         }
+//!
+//!         and "the element is on top" {
+//!             assert_eq!(stack.last(), Some(&1));
 //!         }
 //!     }
 //! }
 //! ```
 //!
+//! Scenarios take the same `tags(...)`, `serial` and `isolated` modifiers
+//! as `it`, and `before_each` and `after_each` run around them like around
+//! any other test.
+//!
+//! ## Gherkin features
+//!
+//! `feature!` turns a Gherkin `.feature` file into tests. The file is read
+//! while compiling, and every `Feature:` becomes a module with one test per
+//! `Scenario:`, or per example row of a `Scenario Outline:`. Like with
+//! `include_str!`, the path is relative to the file invoking the macro.
+//!
+//! ```gherkin
+//! Feature: Eating cucumbers
+//!   Background:
+//!     Given a basket
+//!
+//!   Scenario Outline: Eating <eat> of <start>
+//!     Given there are <start> cucumbers
+//!     When I eat <eat> cucumbers
+//!     Then I should have <left> cucumbers
+//!
+//!     Examples:
+//!       | start | eat | left |
+//!       |    12 |   5 |    7 |
+//!       |    20 |   5 |   15 |
+//! ```
+//!
+//! The steps run against a "world", which starts from `Default::default()`
+//! in every scenario. They are defined with `Steps` from the
+//! `stainless_runtime` crate, matching the text of a step either by a
+//! regular expression (a pattern starting with `^` or ending with `$`) or
+//! by an expression with `{int}`, `{float}`, `{word}`, `{string}` and `{}`
+//! arguments. `feature!` uses the `Steps` returned by `steps()`, or the
+//! expression given as its second argument.
+//!
+//! ```rust
+//! extern crate stainless_runtime;
+//!
+//! use stainless_runtime::Steps;
+//!
+//! #[derive(Default)]
+//! struct Basket { cucumbers: i32 }
+//!
+//! fn steps() -> Steps<Basket> {
+//!     Steps::<Basket>::new()
+//!         .given("a basket", |_, _| {})
+//!         .given("there are {int} cucumbers", |basket, args| basket.cucumbers = args.get(0))
+//!         .when(r"^I eat (\d+) cucumbers?$", |basket, args| basket.cucumbers -= args.get::<i32>(0))
+//!         .then("I should have {int} cucumbers", |basket, args| {
+//!             assert_eq!(basket.cucumbers, args.get::<i32>(0))
+//!         })
+//! }
+//!
+//! feature!("features/cucumbers.feature");
+//! ```
+//!
+//! A scenario with undefined steps fails before running any of them, and
+//! lists them with definitions to start from. A failing step fails the test
+//! with the text and line of the step. Data tables and doc strings are
+//! available from the step arguments, and scenarios tagged `@ignore` are
+//! ignored.
+//!
+//! ## cargo stainless
+//!
+//! The `cargo-stainless` crate adds a `cargo stainless` subcommand with
+//! tools for working with specs, installed with
+//! `cargo install cargo-stainless`.
+//!
+//! `cargo stainless watch` runs the tests and runs them again whenever a
+//! file in `src`, `tests`, `benches` or `examples` changes. Tests which
+//! failed in the previous run go first, followed by the rest. When only
+//! files containing `describe!` blocks of harness mode tests changed, only
+//! the tests in these blocks run, and `--all` runs everything every time.
+//! A failing test binary does not stop the others. Other options are
+//! passed on to `cargo test` and arguments after `--` to the tests:
+//!
+//! ```text
+//! cargo stainless watch --test spec -- --tags "not slow"
+//! ```
+//!
+//! Failed tests are found in the JSON events of harness mode, which
+//! `--json PATH` or the `STAINLESS_JSON` environment variable append to a
+//! file, and in the list of failures the test harness of Rust prints for
+//! other tests.
+//!
+//! `cargo stainless list` prints the `describe!` blocks, tests and
+//! benchmarks of the package with their tags, modifiers and `file:line`,
+//! without compiling anything, and `--format json` prints the same tree as
+//! JSON for editors and reports. It reads the blocks from the source with
+//! the grammar of the `describe!` macro, from the `stainless_grammar`
+//! crate, and follows `mod` declarations to the files of the modules.
+//!
+//! `cargo stainless fmt` formats the hooks, tests, benchmarks and items in
+//! `describe!` blocks with rustfmt, which leaves macro bodies alone, and
+//! normalizes headers like `it "description"`,
+//! `failing("message") "description"` and `bench "description" (b)`. It
+//! rewrites the files of the package in place, or with `--check` lists
+//! the files which are not formatted and exits with 1, for CI.
+//!
+//! `cargo stainless migrate` converts private modules of `#[test]`
+//! functions to `describe!` blocks: `#[should_panic(expected = "...")]`
+//! becomes `failing("...")`, `#[ignore]` becomes `ignore`, and function
+//! names become descriptions which generate the same names, so
+//! `adds_two_numbers` becomes `it "adds two numbers"`. With `--hoist`, the
+//! statements which all tests of a block start with move to `before_each`,
+//! as long as every test keeps at least one statement. Tests with
+//! arguments or a return type stay as they are, and the crate still has
+//! to enable the plugin.
+//!
+//! `cargo stainless expand` prints the module generated for a `describe!`
+//! block, given by its path like `"stack > empty"`. Every run of
+//! statements in a test starts with a comment naming where it was written,
+//! such as `// before_each of describe! stack, tests/spec.rs:9`, which
+//! shows what hook splicing did when it causes surprising borrow errors.
+//! It compiles the package with `cargo test --no-run` in
+//! `target/stainless-expand`, passing on other options like `--test spec`,
+//! and prints the module the plugin generated.
+//!
+//! `cargo stainless docs` writes all `describe!` trees as living
+//! documentation: Markdown by default, or a static HTML page with
+//! `--format html`. Blocks become headings and tests bullets, and ignored
+//! tests are listed under "Pending" as behavior which is not implemented
+//! yet. With `--report PATH`, the JSON events of the last test run add
+//! pass/fail badges:
+//!
+//! ```text
+//! cargo test -- --json target/report.json
+//! cargo stainless docs --report target/report.json -o SPEC.md
+//! ```
+//!
 //! ## License
 //!
 //! MIT. See the LICENSE file for details.
@@ -169,24 +544,53 @@
 //! See Cargo.toml for the full list of authors.
 
 extern crate syntax;
+#[macro_use]
+extern crate rustc;
 extern crate rustc_plugin;
+extern crate stainless_grammar as grammar;
 
-use self::describe::describe;
+use self::describe::Describe;
+use self::feature::Feature;
+use self::unlisted::UnlistedDescribe;
 use rustc_plugin as plugin;
 use syntax::symbol::Symbol;
 
-
 mod describe;
 mod parse;
 mod test;
 mod bench;
 mod generate;
+mod lint;
+mod expand;
+mod feature;
+mod gherkin;
+mod unlisted;
 
 #[plugin_registrar]
 #[doc(hidden)]
 pub fn plugin_registrar(reg: &mut plugin::Registry) {
+    // `#![plugin(stainless(harness))]` registers tests with `stainless_runtime`.
+    let mut harness = false;
+    for arg in reg.args() {
+        if arg.check_name("harness") {
+            harness = true;
+        } else {
+            reg.sess.span_err(arg.span, "unknown stainless plugin argument, expected `harness`");
+        }
+    }
+
     reg.register_syntax_extension(
         Symbol::intern("describe"),
-        syntax::ext::base::IdentTT(Box::new(describe), None, false)
+        syntax::ext::base::IdentTT(Box::new(Describe { harness: harness }), None, false)
+    );
+    reg.register_syntax_extension(
+        Symbol::intern("feature"),
+        syntax::ext::base::NormalTT {
+            expander: Box::new(Feature { harness: harness }),
+            def_info: None,
+            allow_internal_unstable: false,
+            allow_internal_unsafe: false
+        }
     );
+    reg.register_early_lint_pass(Box::new(UnlistedDescribe));
 }
@@ -0,0 +1,272 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::collections::HashSet;
+
+use syntax::{ast, attr, codemap};
+use syntax::ext::base;
+use syntax::parse::token;
+use syntax::tokenstream::{TokenStream, TokenTree};
+use syntax::visit::{self, Visitor};
+
+use grammar::{EMPTY_TEST, FAILING_WITHOUT_PANIC, HOOK_WITHOUT_TESTS, MISSING_ASSERTION, NON_SENTENCE_DESCRIPTION,
+              UNUSED_BEFORE_EACH};
+
+use describe::{DescribeState, SubBlock};
+use test::Test;
+
+// Macros which panic without being assertions.
+const PANICKING: &'static [&'static str] = &["panic", "unreachable", "unimplemented"];
+
+/// Warn about common mistakes in a top-level `describe!` block and its nested blocks.
+pub fn check(cx: &base::ExtCtxt, state: &DescribeState) {
+    check_describe(cx, state, &[], &[]);
+}
+
+// `hooks` are the `before_each` and `after_each` blocks of the enclosing blocks, which are
+// spliced into the tests of this one, and `allowed` the lints they allow.
+fn check_describe(cx: &base::ExtCtxt, state: &DescribeState, hooks: &[&ast::Block], allowed: &[String]) {
+    let name = state.name.map(|name| name.name.to_string()).unwrap_or_default();
+    let allowed: Vec<String> = allowed.iter().chain(&state.allowed_lints).cloned().collect();
+    let warn = |lint: &str, span: codemap::Span, message: &str| {
+        if !allowed.iter().any(|allowed| allowed == lint) {
+            cx.struct_span_warn(span, message)
+                .note(&format!("`#![allow({})]` on the `describe!` block disables this warning", lint))
+                .emit();
+        }
+    };
+
+    let hooks: Vec<&ast::Block> = hooks.iter().cloned()
+        .chain(state.before_each.iter().map(|block| &**block))
+        .chain(state.after_each.iter().map(|block| &**block))
+        .collect();
+
+    for subblock in &state.subblocks {
+        match *subblock {
+            SubBlock::Test(ref test) if is_configured(cx, &test.attrs) => check_test(test, &hooks, &warn),
+            SubBlock::Describe(ref nested) if is_configured(cx, &nested.attrs) => {
+                check_describe(cx, nested, &hooks, &allowed)
+            },
+            _ => {}
+        }
+    }
+
+    // Benchmarks don't run the hooks, so only tests count.
+    if !has_tests(cx, state) {
+        if !state.before_each.is_empty() || !state.after_each.is_empty() {
+            warn(HOOK_WITHOUT_TESTS, state.span, &format!("`describe! {}` has hooks, but no tests to run them", name));
+        }
+        return;
+    }
+
+    for (i, before) in state.before_each.iter().enumerate() {
+        // The bindings are visible to the rest of the block, to the following `before_each`
+        // blocks, to `after_each` and to all tests and hooks of nested blocks.
+        let mut used = Contents::default();
+        for stmt in &before.stmts {
+            match stmt.node {
+                ast::StmtKind::Local(ref local) => {
+                    if let Some(ref init) = local.init {
+                        used.visit_expr(init);
+                    }
+                },
+                _ => used.visit_stmt(stmt)
+            }
+        }
+        let mut blocks: Vec<&ast::Block> = state.before_each[i + 1..].iter().map(|block| &**block).collect();
+        scope_blocks(cx, state, false, &mut blocks);
+        for block in blocks {
+            used.visit_block(block);
+        }
+
+        let mut bindings = Bindings::default();
+        for stmt in &before.stmts {
+            if let ast::StmtKind::Local(ref local) = stmt.node {
+                bindings.visit_pat(&local.pat);
+            }
+        }
+        for binding in bindings.0 {
+            if !binding.node.name.as_str().starts_with('_') && !used.idents.contains(&binding.node.name) {
+                warn(UNUSED_BEFORE_EACH, binding.span,
+                     &format!("`{}` is bound in `before_each`, but no test of `describe! {}` uses it",
+                              binding.node, name));
+            }
+        }
+    }
+}
+
+fn check_test<F>(test: &Test, hooks: &[&ast::Block], warn: &F) where F: Fn(&str, codemap::Span, &str) {
+    if !is_sentence(&test.description) {
+        warn(NON_SENTENCE_DESCRIPTION, test.span,
+             &format!("the description {:?} does not read as a sentence, like \"adds two numbers\"", test.description));
+    }
+
+    // Ignored tests are often placeholders.
+    if test.test_config.ignored {
+        return;
+    }
+
+    if test.block.stmts.is_empty() {
+        warn(EMPTY_TEST, test.span, &format!("the test {:?} is empty", test.description));
+        return;
+    }
+
+    let mut contents = Contents::default();
+    for block in hooks.iter().cloned().chain(Some(&*test.block)) {
+        contents.visit_block(block);
+    }
+
+    if test.test_config.failing && !contents.panics {
+        warn(FAILING_WITHOUT_PANIC, test.span,
+             &format!("the failing test {:?} has no obvious way to panic", test.description));
+    } else if !test.test_config.failing && !contents.assertions {
+        warn(MISSING_ASSERTION, test.span, &format!("the test {:?} does not assert anything", test.description));
+    }
+}
+
+/// Whether a description reads like a sentence following "it", like "adds two numbers", rather
+/// than like the name of a function, like "test_add", "test 1" or "addsNumbers".
+pub fn is_sentence(description: &str) -> bool {
+    let first = description.split_whitespace().next().unwrap_or("").to_lowercase();
+    let test_prefix = first.starts_with("test") && first[4..].chars().all(|c| c.is_digit(10));
+
+    // Underscores and camel case only give away a function name in a single word, sentences
+    // like "parses snake_case keys" may well mention identifiers.
+    let identifier = description.chars().all(|c| c.is_alphanumeric() || c == '_');
+    let camel_case = description.chars().zip(description.chars().skip(1))
+        .any(|(a, b)| a.is_lowercase() && b.is_uppercase());
+    let function_name = identifier && (description.contains('_') || camel_case);
+
+    !first.is_empty() && !test_prefix && !function_name
+}
+
+// Whether the `#[cfg]` attributes of a test or block keep it, the lints skip tests and blocks the
+// compiler removes.
+fn is_configured(cx: &base::ExtCtxt, attrs: &[ast::Attribute]) -> bool {
+    attrs.iter().filter(|attr| attr.check_name("cfg")).all(|attr| match attr.meta_item_list() {
+        Some(ref list) if list.len() == 1 => {
+            list[0].meta_item().map_or(true, |cfg| attr::cfg_matches(cfg, cx.parse_sess, cx.ecfg.features))
+        },
+        _ => true
+    })
+}
+
+fn has_tests(cx: &base::ExtCtxt, state: &DescribeState) -> bool {
+    state.subblocks.iter().any(|subblock| match *subblock {
+        SubBlock::Test(ref test) => is_configured(cx, &test.attrs),
+        SubBlock::Describe(ref nested) => is_configured(cx, &nested.attrs) && has_tests(cx, nested),
+        SubBlock::Bench(_) | SubBlock::Item(_) => false
+    })
+}
+
+// The blocks which see the bindings of `before_each` of a block: the tests and `after_each`,
+// and the tests and hooks of nested blocks.
+fn scope_blocks<'s>(cx: &base::ExtCtxt, state: &'s DescribeState, nested: bool, blocks: &mut Vec<&'s ast::Block>) {
+    if nested {
+        blocks.extend(state.before_each.iter().map(|block| &**block));
+    }
+    blocks.extend(state.after_each.iter().map(|block| &**block));
+
+    for subblock in &state.subblocks {
+        match *subblock {
+            SubBlock::Test(ref test) if is_configured(cx, &test.attrs) => blocks.push(&test.block),
+            SubBlock::Describe(ref nested) if is_configured(cx, &nested.attrs) => {
+                scope_blocks(cx, nested, true, blocks)
+            },
+            _ => {}
+        }
+    }
+}
+
+// What the visited code contains, as far as the lints are concerned. Macros are not expanded
+// yet, so only their names and the identifiers in their arguments are known.
+#[derive(Default)]
+struct Contents {
+    idents: HashSet<ast::Name>,
+    // Whether there is an `assert*!` macro.
+    assertions: bool,
+    // Whether there is anything which obviously can panic: a panicking macro, a call, indexing
+    // or a division.
+    panics: bool
+}
+
+impl Contents {
+    fn tokens(&mut self, stream: TokenStream) {
+        for tree in stream.trees() {
+            match tree {
+                TokenTree::Token(_, token::Ident(ident)) => { self.idents.insert(ident.name); },
+                TokenTree::Delimited(_, ref delimited) => self.tokens(delimited.stream()),
+                TokenTree::Token(..) => {}
+            }
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for Contents {
+    fn visit_ident(&mut self, _: codemap::Span, ident: ast::Ident) {
+        self.idents.insert(ident.name);
+    }
+
+    fn visit_expr(&mut self, expr: &'a ast::Expr) {
+        match expr.node {
+            ast::ExprKind::Call(..) | ast::ExprKind::MethodCall(..) | ast::ExprKind::Index(..) => self.panics = true,
+            ast::ExprKind::Binary(op, _, _) if op.node == ast::BinOpKind::Div || op.node == ast::BinOpKind::Rem => {
+                self.panics = true
+            },
+            _ => {}
+        }
+        visit::walk_expr(self, expr);
+    }
+
+    fn visit_mac(&mut self, mac: &'a ast::Mac) {
+        if let Some(segment) = mac.node.path.segments.last() {
+            let name = segment.identifier.name.as_str();
+            if name.contains("assert") {
+                self.assertions = true;
+                self.panics = true;
+            } else if PANICKING.contains(&&*name) {
+                self.panics = true;
+            }
+        }
+        self.tokens(mac.node.stream());
+    }
+}
+
+// The identifiers bound by a pattern.
+#[derive(Default)]
+struct Bindings(Vec<ast::SpannedIdent>);
+
+impl<'a> Visitor<'a> for Bindings {
+    fn visit_pat(&mut self, pat: &'a ast::Pat) {
+        if let ast::PatKind::Ident(_, ident, _) = pat.node {
+            self.0.push(ident);
+        }
+        visit::walk_pat(self, pat);
+    }
+
+    fn visit_mac(&mut self, _: &'a ast::Mac) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_sentence;
+
+    #[test]
+    fn sentences() {
+        for description in &["adds two numbers", "works", "parses snake_case keys", "handles MAX_VALUE",
+                             "supports iOS", "calls addNumbers once", "tests the parser"] {
+            assert!(is_sentence(description), "{:?} was not taken for a sentence", description);
+        }
+    }
+
+    #[test]
+    fn function_names() {
+        for description in &["", "  ", "test_add", "adds_two_numbers", "addsNumbers", "test", "test 1", "test2",
+                             "_private"] {
+            assert!(!is_sentence(description), "{:?} was taken for a sentence", description);
+        }
+    }
+}
@@ -4,16 +4,21 @@
 // Licensed under the MIT license. This file may not be copied, modified, or distributed except
 // according to those terms.
 
-use syntax::{ast, codemap};
+use syntax::{ast, attr, codemap};
+use syntax::errors::{DiagnosticBuilder, FatalError};
 use syntax::ext::base;
+use syntax::ext::build::AstBuilder;
 use syntax::parse::token;
+use syntax::symbol::Symbol;
 
 use syntax::ptr::P;
 use syntax::parse::parser::Parser;
 
+use grammar::{self, Body, Child, Meta, Punct};
+
 use test::{Test, TestConfig};
 use bench::Bench;
-use describe::{DescribeState, SubBlock};
+use describe::{DescribeState, Origin, SubBlock};
 
 /// Trait that means something can be parsed with a configuration.
 pub trait Parse<Cfg> {
@@ -21,198 +26,259 @@ pub trait Parse<Cfg> {
     fn parse(&mut Parser, Cfg) -> Self;
 }
 
-impl Parse<TestConfig> for Test {
-    fn parse(parser: &mut Parser, test_config: TestConfig) -> Test {
-        // Description of this test.
-        let (description, _) = parser.parse_str().ok().expect("Test should have description");
-
-        Test {
-            // Get as a String
-            description: description.to_string(),
-
-            // The associated block
-            block: parser.parse_block().ok().unwrap(),
-
-            test_config: test_config
-        }
+impl<'a, 'b> Parse<(codemap::Span, &'a mut base::ExtCtxt<'b>, ast::Ident)> for DescribeState {
+    fn parse(parser: &mut Parser, (sp, cx, name): (codemap::Span, &'a mut base::ExtCtxt, ast::Ident)) -> DescribeState {
+        // The blocks are read with the grammar `cargo stainless` reads them with, the parser of
+        // the compiler reads the attributes, blocks and items.
+        let describe = match grammar::parse_describe(&mut Tokens { parser: parser }, name, sp) {
+            Ok(describe) => describe,
+            Err(mut e) => {
+                e.emit();
+                panic!(FatalError);
+            }
+        };
+        describe_state(cx, describe)
     }
 }
 
-impl Parse<()> for Bench {
-    fn parse(parser: &mut Parser, _: ()) -> Bench {
-        // Description of this benchmark
-        let (description, _) = parser.parse_str().ok().expect("Bench should have description");
-
-        let open_delim = parser.token.clone();
-        parser.bump();
+// The tokens of a `describe!` invocation, read with the parser of the compiler.
+struct Tokens<'p, 'a: 'p> {
+    parser: &'p mut Parser<'a>
+}
 
-        let bench_ident = match parser.parse_ident() {
-            Ok(id) => id,
-            Err(e) => {
-                panic!("{:?}", parser.fatal(&format!("Expected `($ident)`, got err: {:?}", e)));
-            }
-        };
+impl<'p, 'a> grammar::Tokens for Tokens<'p, 'a> {
+    type Span = codemap::Span;
+    type Ident = ast::Ident;
+    type Attribute = ast::Attribute;
+    type Block = P<ast::Block>;
+    type Item = P<ast::Item>;
+    type Error = DiagnosticBuilder<'a>;
 
-        let close_delim = parser.token.clone();
-        parser.bump();
+    fn span(&self) -> codemap::Span {
+        self.parser.span
+    }
 
-        let name = match (open_delim, bench_ident, close_delim) {
-            (token::OpenDelim(token::Paren), ident, token::CloseDelim(token::Paren)) => { ident },
+    fn ident(&self, ahead: usize) -> Option<String> {
+        self.parser.look_ahead(ahead, |token| match *token {
+            token::Ident(ident) => Some(ident.name.to_string()),
+            _ => None
+        })
+    }
 
-            (one, two, three) => {
-                panic!("{:?}", parser.fatal(&format!("Expected `($ident)`, found {:?}{:?}{:?}", one, two, three)));
-            }
+    fn is(&self, ahead: usize, punct: Punct) -> bool {
+        let expected = match punct {
+            Punct::Not => token::Not,
+            Punct::Comma => token::Comma,
+            Punct::OpenParen => token::OpenDelim(token::Paren),
+            Punct::CloseParen => token::CloseDelim(token::Paren),
+            Punct::OpenBrace => token::OpenDelim(token::Brace),
+            Punct::CloseBrace => token::CloseDelim(token::Brace)
         };
+        self.parser.look_ahead(ahead, |token| *token == expected)
+    }
 
-        Bench {
-            description: description.to_string(),
-            block: parser.parse_block().ok().unwrap(),
-            bench: P(name)
-        }
+    fn at_end(&self) -> bool {
+        self.parser.token == token::Eof
     }
-}
 
-const BEFORE_EACH: &'static str = "before_each";
-const GIVEN:       &'static str = "given";
-const AFTER_EACH:  &'static str = "after_each";
-const THEN:        &'static str = "then";
-const IT:          &'static str = "it";
-const IGNORE:      &'static str = "ignore";
-const WHEN:        &'static str = "when";
-const DESCRIBE:    &'static str = "describe";
-const FAILING:     &'static str = "failing";
-const BENCH:       &'static str = "bench";
-
-impl<'a, 'b> Parse<(codemap::Span, &'a mut base::ExtCtxt<'b>, Option<ast::Ident>)> for DescribeState {
-    fn parse(parser: &mut Parser,
-             (sp, cx, name): (codemap::Span, &'a mut base::ExtCtxt, Option<ast::Ident>)) -> DescribeState {
-        let mut state = DescribeState {
-            name: None,
-            before_each: None,
-            after_each: None,
-            subblocks: vec![],
-        };
+    fn bump(&mut self) {
+        self.parser.bump();
+    }
 
-        state.name = match name {
-            // Top-level describe block.
-            Some(name) => Some(name),
-            // Nested describe block.
-            None => {
-                // Get the name of this describe block
-                match parser.parse_ident() {
-                    Ok(name) => {
-                        // Moving past the opening {
-                        try(parser, token::OpenDelim(token::Brace), "{ after the name of a describe! block");
-                        Some(name)
-                    },
-                    Err(e) => {
-                        panic!("{:?}", parser.fatal(&format!("Failed to parse the name of the describe block, got err: {:?}", e)));
-                    }
+    fn parse_ident(&mut self) -> Result<ast::Ident, DiagnosticBuilder<'a>> {
+        self.parser.parse_ident()
+    }
 
-                }
-            }
-        };
+    fn name(&self, ident: &ast::Ident) -> String {
+        ident.name.to_string()
+    }
 
-        // Now parse all tests and subsections:
-        while parser.token != token::CloseDelim(token::Brace) && parser.token != token::Eof {
-            // Get the name of this block, must be either:
-            //     - before_each
-            //     - after_each
-            //     - before
-            //     - after
-            //     - it
-            //     - failing
-            //     - bench
-            //     - describe!
-            //
-            // Any other top-level idents are not allowed.
-            let block_name = match parser.parse_ident() {
-                Ok(ident) => ident.name,
-                Err(e) => {
-                    // technically this should never be reachable
-                    panic!("{:?}", parser.fatal(&format!("Failed to parse the name of a test block, got err: {:?}", e)));
-                },
-            };
-
-
-            match &*block_name.as_str() {
-                BEFORE_EACH | GIVEN => {
-                    if state.before_each.is_some() {
-                        panic!("{:?}", parser.fatal("Only one `before_each` block is allowed per `describe!` block."));
-                    }
-                    state.before_each = Some(parser.parse_block().ok().unwrap());
-                },
+    fn parse_str(&mut self) -> Result<String, DiagnosticBuilder<'a>> {
+        self.parser.parse_str().map(|(string, _)| string.to_string())
+    }
 
-                AFTER_EACH | THEN => {
-                    if state.after_each.is_some() {
-                        panic!("{:?}", parser.fatal("Only one `after_each` block is allowed per `describe!` block."));
-                    }
-                    state.after_each = Some(parser.parse_block().ok().unwrap());
-                },
+    fn parse_block(&mut self) -> Result<P<ast::Block>, DiagnosticBuilder<'a>> {
+        self.parser.parse_block()
+    }
 
-                // Regular `#[test]`.
-                IT | WHEN => { state.subblocks.push(SubBlock::Test(Parse::parse(parser, TestConfig::test()))) },
+    fn parse_inner_attributes(&mut self) -> Result<Vec<ast::Attribute>, DiagnosticBuilder<'a>> {
+        self.parser.parse_inner_attributes()
+    }
 
-                // `#[should_panic]` or `#[should_panic(expected = "...")] test.
-                FAILING => {
+    fn parse_outer_attributes(&mut self) -> Result<Vec<ast::Attribute>, DiagnosticBuilder<'a>> {
+        self.parser.parse_outer_attributes()
+    }
 
-                    let mut fail_msg = None;
-                    if parser.token == token::OpenDelim(token::Paren) {
-                        parser.bump();
-                        fail_msg = Some(parser.parse_str().ok().expect("Expected failing message"));
-                        try(parser, token::CloseDelim(token::Paren), "unclosed failing condition paren");
-                    }
-                    state.subblocks.push(SubBlock::Test(Parse::parse(parser, TestConfig::failing_test(fail_msg))))
-                },
+    fn parse_item(&mut self, attrs: Vec<ast::Attribute>) -> Result<P<ast::Item>, DiagnosticBuilder<'a>> {
+        match self.parser.parse_item()? {
+            Some(item) => Ok(item.map(|mut item| {
+                item.attrs = attrs.into_iter().chain(item.attrs).collect();
+                item
+            })),
+            None => {
+                let found = self.parser.this_token_to_string();
+                Err(self.parser.fatal(&format!(
+                    "expected an item or one of: `{}`, but found: `{}`", grammar::BLOCKS.join("`, `"), found)))
+            }
+        }
+    }
 
-                //`#[ignore]` test
-                IGNORE => { state.subblocks.push(SubBlock::Test(Parse::parse(parser, TestConfig::ignored_test()))) },
+    fn meta(&self, attr: &ast::Attribute) -> Meta {
+        Meta {
+            name: attr.path.to_string(),
+            list: attr.meta_item_list().map(|items| {
+                items.iter().map(|item| item.name().map(|name| name.to_string())).collect()
+            })
+        }
+    }
 
-                // #[bench] benchmark.
-                BENCH => { state.subblocks.push(SubBlock::Bench(Parse::parse(parser, ()))) }
+    fn without(&self, attr: ast::Attribute, names: &[&str]) -> Option<ast::Attribute> {
+        let name = match attr.name() {
+            Some(name) => name,
+            None => return Some(attr)
+        };
+        let others: Vec<ast::NestedMetaItem> = attr.meta_item_list().unwrap_or_default().into_iter()
+            .filter(|item| !item.name().map_or(false, |name| names.contains(&&*name.as_str())))
+            .collect();
+        if others.is_empty() {
+            return None;
+        }
+        let list = attr::mk_list_item(name, others);
+        Some(match attr.style {
+            ast::AttrStyle::Inner => attr::mk_spanned_attr_inner(attr.span, attr::mk_attr_id(), list),
+            ast::AttrStyle::Outer => attr::mk_spanned_attr_outer(attr.span, attr::mk_attr_id(), list)
+        })
+    }
 
-                // Nested `describe!` block.
-                DESCRIBE => {
-                    // Skip over the ! in describe!
-                    try(parser, token::Not, "!");
+    fn error(&self, message: &str) -> DiagnosticBuilder<'a> {
+        self.parser.fatal(message)
+    }
+}
 
-                    // Parse this subblock, generate new item.
-                    state.subblocks.push(SubBlock::Describe(Parse::parse(parser, (sp, &mut*cx, None))));
+fn describe_state(cx: &mut base::ExtCtxt, describe: grammar::Describe<Tokens>) -> DescribeState {
+    let mut origins = vec![];
+    for block in &describe.before_each {
+        origins.push(origin(cx, block.span, block.span, format!("before_each of describe! {}", describe.name)));
+    }
+    for block in &describe.after_each {
+        origins.push(origin(cx, block.span, block.span, format!("after_each of describe! {}", describe.name)));
+    }
 
-                    // Move past closing bracket and paren.
-                    //
-                    // This has to go in here because it is EOF on the highest-level invocation.
-                    try(parser, token::CloseDelim(token::Brace), "}} to close `describe!`")
+    let mut subblocks = vec![];
+    for child in describe.children {
+        subblocks.push(match child {
+            Child::Test(test) => {
+                match test.body {
+                    Body::Block(ref block) => {
+                        let label = format!("{} {:?}", test.keyword, test.description);
+                        origins.push(origin(cx, block.span, test.span, label));
+                    },
+                    Body::Scenario(ref steps) => {
+                        for step in steps {
+                            let label = format!("{} {:?} of scenario {:?}", step.keyword, step.text, test.description);
+                            origins.push(origin(cx, step.block.span, step.span, label));
+                        }
+                    }
                 }
+                SubBlock::Test(self::test(cx, test))
+            },
+            Child::Bench(bench) => {
+                origins.push(origin(cx, bench.block.span, bench.span, format!("bench {:?}", bench.description)));
+                SubBlock::Bench(Bench {
+                    description: bench.description,
+                    block: bench.block,
+                    bench: P(bench.bencher),
+                    attrs: bench.attrs,
+                    span: bench.span
+                })
+            },
+            Child::Describe(nested) => SubBlock::Describe(describe_state(cx, nested)),
+            Child::Item(item) => SubBlock::Item(item)
+        });
+    }
 
-                otherwise => { illegal(parser, otherwise) }
-            }
-        }
-
-        state
+    DescribeState {
+        name: Some(describe.name),
+        attrs: describe.attrs,
+        super_glob: describe.super_glob,
+        tags: describe.tags,
+        serial: describe.serial,
+        isolated: describe.isolated,
+        allowed_lints: describe.allowed_lints,
+        harness: false,
+        before_each: describe.before_each,
+        after_each: describe.after_each,
+        subblocks: subblocks,
+        origins: origins,
+        span: describe.span
     }
 }
 
-// checks if current token is the expected token
-fn try(parser: &mut Parser, expected_token: token::Token, err: &str) {
-    if parser.token != expected_token {
-        panic!("{:?}", parser.fatal(&format!("Expected {}, but found `{:?}`", err, parser.token)));
+// the block at `span`, named with `label` and the location of `at`
+fn origin(cx: &base::ExtCtxt, span: codemap::Span, at: codemap::Span, label: String) -> Origin {
+    let loc = cx.codemap().lookup_char_pos(at.lo());
+    Origin { span: span, label: format!("{}, {}:{}", label, loc.file.name, loc.line) }
+}
+
+fn test(cx: &mut base::ExtCtxt, test: grammar::Test<Tokens>) -> Test {
+    let test_config = match test.failing {
+        // `#[should_panic]` or `#[should_panic(expected = "...")]` test.
+        Some(message) => {
+            TestConfig::failing_test(message.map(|message| (Symbol::intern(&message), ast::StrStyle::Cooked)))
+        },
+        None if test.ignored() => TestConfig::ignored_test(),
+        None => TestConfig::test()
+    };
+
+    Test {
+        description: test.description,
+        block: match test.body {
+            Body::Block(block) => block,
+            Body::Scenario(steps) => scenario(cx, steps)
+        },
+        test_config: test_config,
+        tags: test.tags,
+        serial: test.serial,
+        isolated: test.isolated,
+        attrs: test.attrs,
+        span: test.span
     }
-    parser.bump();
 }
 
-fn illegal(parser: &mut Parser, banned: &str) {
-    // Illegal block name.
-    let span = parser.span;
-    panic!(
-        "{:?}",
-        parser.span_fatal(
-            span,
-            &format!(
-                "Expected one of: `{}`, but found: `{}`",
-                format!("{}, {}, {}, {}, {}, {}, {}", BEFORE_EACH, AFTER_EACH, IT, BENCH, FAILING, DESCRIBE, IGNORE),
-                banned
-            )
-        )
-    );
+// the block of a test running the statements of the steps of a scenario in order, so that the
+// bindings of a step are visible to the following steps
+//
+// The test keeps track of the current step in a guard, which names the step on stderr if it
+// panics. The guard is cleared after the last step, so that a failing `after_each` is not blamed
+// on the last step.
+fn scenario(cx: &mut base::ExtCtxt, steps: Vec<grammar::Step<Tokens>>) -> P<ast::Block> {
+    let span = steps[0].block.span.to(steps[steps.len() - 1].block.span);
+    let mut stmts = vec![
+        quote_stmt!(cx, struct __StainlessStep(Option<&'static str>);).unwrap(),
+        quote_stmt!(cx,
+            impl Drop for __StainlessStep {
+                fn drop(&mut self) {
+                    if let Some(step) = self.0 {
+                        if ::std::thread::panicking() {
+                            eprintln!("step failed: {}", step);
+                        }
+                    }
+                }
+            }
+        ).unwrap(),
+        quote_stmt!(cx, let mut __stainless_step = __StainlessStep(None);).unwrap()
+    ];
+
+    for step in steps {
+        // Like in Gherkin: "Given a stack (tests/stack.rs:12)".
+        let loc = cx.codemap().lookup_char_pos(step.span.lo());
+        let label = format!("{}{} {} ({}:{})", step.keyword[..1].to_uppercase(), &step.keyword[1..], step.text,
+                            loc.file.name, loc.line);
+        let label = &*label;
+        stmts.push(quote_stmt!(cx, __stainless_step.0 = Some($label);).unwrap());
+        stmts.extend(step.block.stmts.iter().cloned());
+    }
+    stmts.push(quote_stmt!(cx, __stainless_step.0 = None;).unwrap());
+
+    cx.block(span, stmts)
 }
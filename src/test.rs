@@ -5,7 +5,7 @@
 // according to those terms.
 
 use syntax::ptr::P;
-use syntax::ast;
+use syntax::{ast, codemap};
 use syntax::symbol::Symbol;
 
 /// A test as a description and associated block.
@@ -13,7 +13,17 @@ use syntax::symbol::Symbol;
 pub struct Test {
     pub description: String,
     pub block: P<ast::Block>,
-    pub test_config: TestConfig
+    pub test_config: TestConfig,
+    /// Tags given with `tags(...)`, not including the ones inherited from `describe!` blocks.
+    pub tags: Vec<String>,
+    /// `serial` (`Some(None)`) or `serial(group)` (`Some(Some(group))`).
+    pub serial: Option<Option<String>>,
+    /// Whether the test runs in its own process, from `isolated`.
+    pub isolated: bool,
+    /// Attributes written before the test, copied onto the generated function.
+    pub attrs: Vec<ast::Attribute>,
+    /// The span of the description, where the test is located by `cargo stainless`.
+    pub span: codemap::Span
 }
 
 #[derive(Clone)]
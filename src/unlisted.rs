@@ -0,0 +1,79 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::collections::HashSet;
+
+use rustc::lint::{EarlyContext, EarlyLintPass, LintArray, LintContext, LintPass};
+use syntax::ast;
+use syntax::codemap::Span;
+use syntax::visit::{self, Visitor};
+
+// Allowed with `#![allow(unlisted_describe)]` on the crate.
+declare_lint! {
+    pub UNLISTED_DESCRIBE,
+    Deny,
+    "a `describe!` block in harness mode which is missing from `stainless_main!`"
+}
+
+const SUITE_FN: &'static str = "__stainless_suite";
+
+/// Checks the expanded crate for `describe!` blocks in harness mode whose suite is never built,
+/// as `stainless_main!` only runs the blocks it lists.
+pub struct UnlistedDescribe;
+
+impl LintPass for UnlistedDescribe {
+    fn get_lints(&self) -> LintArray {
+        lint_array!(UNLISTED_DESCRIBE)
+    }
+}
+
+impl EarlyLintPass for UnlistedDescribe {
+    fn check_crate(&mut self, cx: &EarlyContext, krate: &ast::Crate) {
+        let mut suites = Suites::default();
+        visit::walk_crate(&mut suites, krate);
+
+        // Paths are not resolved yet, so a block counts as run when a suite of a module with its
+        // name is built anywhere. Nested blocks are built by the suite of their parent.
+        for &(name, span) in &suites.defined {
+            if !suites.built.contains(&name) {
+                cx.span_lint(UNLISTED_DESCRIBE, span,
+                             &format!("`describe! {}` is never run, add `{}` to `stainless_main!`", name, name));
+            }
+        }
+    }
+}
+
+// The modules generated in harness mode and the modules whose suites are built.
+#[derive(Default)]
+struct Suites {
+    defined: Vec<(ast::Name, Span)>,
+    built: HashSet<ast::Name>
+}
+
+impl<'a> Visitor<'a> for Suites {
+    fn visit_item(&mut self, item: &'a ast::Item) {
+        if let ast::ItemKind::Mod(ref module) = item.node {
+            let has_suite = module.items.iter().any(|item| match item.node {
+                ast::ItemKind::Fn(..) => item.ident.name == SUITE_FN,
+                _ => false
+            });
+            if has_suite {
+                self.defined.push((item.ident.name, item.span));
+            }
+        }
+        visit::walk_item(self, item);
+    }
+
+    fn visit_path(&mut self, path: &'a ast::Path, _id: ast::NodeId) {
+        let segments = &path.segments;
+        if segments.len() >= 2 && segments[segments.len() - 1].identifier.name == SUITE_FN {
+            self.built.insert(segments[segments.len() - 2].identifier.name);
+        }
+        visit::walk_path(self, path);
+    }
+
+    fn visit_mac(&mut self, _: &'a ast::Mac) {}
+}
@@ -0,0 +1,45 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+#![feature(plugin)]
+#![plugin(stainless)]
+
+describe! attributes {
+    #![allow(missing_assertion)]
+
+    before_each {
+        let value = 1;
+    }
+
+    /// Doc comments are allowed as well.
+    #[allow(unused_variables)]
+    it "should pass attributes through to the test" {
+        let unused = value;
+    }
+
+    #[cfg(all(unix, not(unix)))]
+    it "should be removed by cfg" {
+        panic!("should never be compiled");
+    }
+
+    #[cfg(all(unix, not(unix)))]
+    describe! removed {
+        before_each {
+            this_does_not_compile!();
+        }
+
+        it "should remove the whole subtree" {
+            panic!("should never be compiled");
+        }
+    }
+
+    #[allow(unused_variables)]
+    describe! nested {
+        it "should pass attributes through to the module" {
+            let unused = value;
+        }
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+// The modules the plugin writes for `cargo stainless expand`, for blocks of the other tests.
+
+use std::env;
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Compiles `file` with the plugin, asking it for the block at `path`.
+fn expand(file: &str, path: &str) -> String {
+    // The plugin and the runtime are next to the test binary.
+    let deps = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let output = deps.join(format!("expand-{}.rs", path.replace(" > ", "-")));
+    let _ = fs::remove_file(&output);
+
+    let status = Command::new("rustc")
+        .args(&["--test", file, "--crate-name", "expand", "-Z", "no-trans"])
+        .arg("-L").arg(format!("dependency={}", deps.display()))
+        .arg("--extern").arg(format!("stainless={}/{}stainless{}", deps.display(), DLL_PREFIX, DLL_SUFFIX))
+        .arg("--extern").arg(format!("stainless_runtime={}", newest(&deps, "libstainless_runtime-").display()))
+        .env("STAINLESS_EXPAND", path)
+        .env("STAINLESS_EXPAND_OUTPUT", &output)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // Nothing is written if there is no such block.
+    let mut expanded = String::new();
+    if let Ok(mut file) = File::open(&output) {
+        file.read_to_string(&mut expanded).unwrap();
+    }
+    expanded
+}
+
+// The most recently built file in `dir` starting with `prefix`.
+fn newest(dir: &Path, prefix: &str) -> PathBuf {
+    fs::read_dir(dir).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.file_name().unwrap().to_string_lossy().starts_with(prefix))
+        .max_by_key(|path| fs::metadata(path).unwrap().modified().unwrap())
+        .unwrap()
+}
+
+#[test]
+fn splices_in_the_hooks_of_enclosing_blocks() {
+    assert_eq!(expand("tests/nested_hooks.rs", "top_level > nested"), "\
+// describe! top_level > nested at tests/nested_hooks.rs:19
+mod nested {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(non_snake_case)]
+    #[test]
+    fn should_be_more_specific() {
+        // before_each of describe! top_level, tests/nested_hooks.rs:11
+        let mut foo = 1;
+        // before_each of describe! nested, tests/nested_hooks.rs:20
+        assert_eq!(foo, 1);
+        foo += 1;
+        // it \"should be more specific\", tests/nested_hooks.rs:25
+        assert_eq!(foo, 2);
+        foo += 1;
+        // after_each of describe! nested, tests/nested_hooks.rs:30
+        assert_eq!(foo, 3);
+        foo += 1;
+        // after_each of describe! top_level, tests/nested_hooks.rs:15
+        assert_eq!(foo, 4);
+    }
+}
+");
+}
+
+#[test]
+fn generates_should_panic_attributes() {
+    assert_eq!(expand("tests/failing.rs", "failing"), "\
+// describe! failing at tests/failing.rs:10
+mod failing {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(non_snake_case)]
+    #[test]
+    #[should_panic]
+    fn should_fail() {
+        // failing \"should fail\", tests/failing.rs:11
+        panic!(\"should still pass\");
+    }
+    #[allow(non_snake_case)]
+    #[test]
+    #[should_panic(expected = \"should still pass\")]
+    fn should_fail_with_message() {
+        // failing \"should fail with message\", tests/failing.rs:15
+        panic!(\"should still pass\");
+    }
+}
+");
+}
+
+#[test]
+fn registers_blocks_in_harness_mode() {
+    assert_eq!(expand("tests/harness.rs", "harness > nested"), "\
+// describe! harness > nested at tests/harness.rs:44
+pub mod nested {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(unused_mut)]
+    pub fn __stainless_suite() -> ::stainless_runtime::Suite {
+        let mut suite = ::stainless_runtime::Suite::new(\"nested\").tag(\"runtime\");
+        let _ =
+            suite.test(::stainless_runtime::Test::new(\"should run nested tests\",
+                                                      should_run_nested_tests).tag(\"runtime\").tag(\"nested\"));
+        suite
+    }
+    #[allow(non_snake_case)]
+    fn should_run_nested_tests() {
+        // generated by stainless
+        ::stainless_runtime::__enter_phase(::stainless_runtime::Phase::BeforeEach(\"harness\"));
+        // before_each of describe! harness, tests/harness.rs:18
+        let mut value = 1;
+        // generated by stainless
+        ::stainless_runtime::__enter_phase(::stainless_runtime::Phase::Test);
+        // it \"should run nested tests\", tests/harness.rs:45
+        assert_eq!(value, 1);
+    }
+}
+");
+}
+
+#[test]
+fn names_the_steps_of_scenarios() {
+    let expanded = expand("tests/scenario.rs", "stack");
+    assert!(expanded.contains("
+        let mut __stainless_step = __StainlessStep(None);
+        __stainless_step.0 = Some(\"Given an empty stack (tests/scenario.rs:22)\");
+        // given \"an empty stack\" of scenario \"pushing onto an empty stack\", tests/scenario.rs:22
+        let mut stack: Vec<u32> = vec![];
+"));
+    assert!(expanded.contains("
+        // generated by stainless
+        __stainless_step.0 = None;
+"));
+}
+
+#[test]
+fn writes_nothing_for_other_blocks() {
+    assert_eq!(expand("tests/failing.rs", "failing > nested"), "");
+}
@@ -0,0 +1,42 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+#![feature(plugin)]
+#![plugin(stainless)]
+
+extern crate stainless_runtime;
+
+use stainless_runtime::Steps;
+
+#[derive(Default)]
+pub struct Basket {
+    ready: bool,
+    cucumbers: i32
+}
+
+fn steps() -> Steps<Basket> {
+    Steps::<Basket>::new()
+        .given("a basket", |basket, _| basket.ready = true)
+        .given("there are {int} cucumbers", |basket, args| basket.cucumbers = args.get(0))
+        .when(r"^I eat (\d+) cucumbers?$", |basket, args| basket.cucumbers -= args.get::<i32>(0))
+        .then("I should have {int} cucumbers", |basket, args| {
+            assert!(basket.ready);
+            assert_eq!(basket.cucumbers, args.get::<i32>(0));
+        })
+}
+
+feature!("features/cucumbers.feature");
+
+fn shared_steps() -> Steps<Basket> {
+    steps()
+}
+
+// The step definitions can also be given explicitly.
+mod explicit {
+    use super::shared_steps;
+
+    feature!("features/cucumbers.feature", shared_steps());
+}
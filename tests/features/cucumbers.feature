@@ -0,0 +1,26 @@
+Feature: Eating cucumbers
+  Cucumbers disappear when they are eaten.
+
+  Background:
+    Given a basket
+
+  Scenario: Eating one cucumber
+    Given there are 5 cucumbers
+    When I eat 1 cucumber
+    Then I should have 4 cucumbers
+
+  Scenario Outline: Eating <eat> of <start>
+    Given there are <start> cucumbers
+    When I eat <eat> cucumbers
+    Then I should have <left> cucumbers
+
+    Examples:
+      | start | eat | left |
+      |    12 |   5 |    7 |
+      |    20 |   5 |   15 |
+
+  @ignore
+  Scenario: Eating more than there are
+    Given there are 1 cucumbers
+    When I eat 2 cucumbers
+    Then I should have 0 cucumbers
@@ -0,0 +1,88 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+#![feature(plugin)]
+#![plugin(stainless(harness))]
+
+#[macro_use]
+extern crate stainless_runtime;
+
+describe! harness {
+    #![tags(runtime)]
+    // The failing and ignored tests don't use `value`.
+    #![allow(unused_variables, unused_mut)]
+
+    before_each {
+        let mut value = 1;
+    }
+
+    it "should run tests registered with the runtime" {
+        value += 1;
+        assert_eq!(value, 2);
+    }
+
+    failing("expected") "should handle failing tests" {
+        panic!("expected panic");
+    }
+
+    ignore "should handle ignored tests" {
+        panic!("should not run");
+    }
+
+    bench "should run benchmarks" (bencher) {
+        bencher.iter(|| 2 * 2)
+    }
+
+    #[cfg(all(unix, not(unix)))]
+    it "should not register configured out tests" {
+        panic!("should never be compiled");
+    }
+
+    describe! nested {
+        it "should run nested tests" tags(nested) {
+            assert_eq!(value, 1);
+        }
+    }
+
+    describe! serial {
+        #![serial]
+
+        it "should run serial blocks one test at a time" {
+            assert_eq!(value, 1);
+        }
+
+        it "should run tests of serial groups one at a time" serial(db) tags(db) {
+            assert_eq!(value, 1);
+        }
+
+        it "should run serial tests alone" serial {
+            assert_eq!(value, 1);
+        }
+    }
+
+    describe! isolated {
+        #![isolated]
+
+        it "should run isolated tests in a process of their own" {
+            assert!(::std::env::var_os("STAINLESS_ISOLATED_TEST").is_some());
+        }
+
+        it "should combine isolated with other modifiers" isolated serial {
+            assert_eq!(value, 1);
+        }
+    }
+}
+
+mod more {
+    describe! listed_by_path {
+        it "should run blocks listed by their path" {
+            assert_eq!(module_path!(), "harness::more::listed_by_path");
+        }
+    }
+}
+
+// Leaving a block out of the list fails to compile.
+stainless_main!(harness, more::listed_by_path);
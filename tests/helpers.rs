@@ -14,6 +14,8 @@ mod test {
     }
 
     describe! helpers {
+        #![allow(missing_assertion)]
+
         it "should be able to use helpers" {
             test_helper(7, 7);
         }
@@ -0,0 +1,42 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+#![feature(plugin)]
+#![plugin(stainless)]
+
+describe! items {
+    use std::collections::HashMap;
+
+    const ANSWER: i32 = 42;
+
+    struct Counter(i32);
+
+    impl Counter {
+        fn bump(&mut self) { self.0 += 1; }
+    }
+
+    fn answer() -> i32 { ANSWER }
+
+    before_each {
+        let mut counter = Counter(0);
+    }
+
+    it "should be able to use items declared in the describe block" {
+        let mut map = HashMap::new();
+        map.insert("answer", answer());
+        counter.bump();
+        assert_eq!(map["answer"], 42);
+        assert_eq!(counter.0, 1);
+    }
+
+    describe! nested {
+        it "should be able to use items of the parent describe block" {
+            counter.bump();
+            assert_eq!(answer(), ANSWER);
+            assert_eq!(counter.0, 1);
+        }
+    }
+}
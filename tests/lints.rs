@@ -0,0 +1,46 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+#![feature(plugin)]
+#![plugin(stainless)]
+
+fn check(value: u32) {
+    assert_eq!(value, 1);
+}
+
+describe! allowed_lints {
+    #![allow(empty_test, missing_assertion, non_sentence_description)]
+
+    it "should allow empty tests" {}
+
+    it "should allow tests asserting in helpers" {
+        check(1);
+    }
+
+    it "works" {
+        check(1);
+    }
+
+    describe! nested {
+        #![allow(unused_before_each, unused_variables, dead_code)]
+
+        before_each {
+            let unused = 1;
+        }
+
+        it "should inherit allowed lints" {}
+
+        fn helper() {}
+    }
+
+    describe! hooks_only {
+        #![allow(hook_without_tests)]
+
+        before_each {
+            let _ = 1;
+        }
+    }
+}
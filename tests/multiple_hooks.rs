@@ -0,0 +1,46 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+#![feature(plugin)]
+#![plugin(stainless)]
+
+describe! top_level {
+    before_each {
+        let mut log = vec!["outer setup"];
+    }
+
+    after_each {
+        log.push("outer teardown");
+        assert_eq!(log, ["outer setup", "database", "cache", "test", "cache closed", "database closed",
+                         "outer teardown"]);
+    }
+
+    describe! nested {
+        before_each {
+            let database = "database";
+            log.push(database);
+        }
+
+        after_each {
+            log.push("database closed");
+        }
+
+        before_each {
+            // Bindings of earlier hooks are in scope.
+            assert_eq!(database, "database");
+            log.push("cache");
+        }
+
+        after_each {
+            log.push("cache closed");
+        }
+
+        it "runs all hooks in order" {
+            assert_eq!(log, ["outer setup", "database", "cache"]);
+            log.push("test");
+        }
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+#![feature(plugin)]
+#![plugin(stainless)]
+
+use std::env;
+use std::process::Command;
+
+describe! stack {
+    // Popping an empty stack doesn't push.
+    #![allow(unused_mut)]
+
+    before_each {
+        let mut pushed = 0;
+    }
+
+    scenario "pushing onto an empty stack" {
+        given "an empty stack" {
+            let mut stack: Vec<u32> = vec![];
+        }
+
+        when "an element is pushed" {
+            stack.push(1);
+            pushed += 1;
+        }
+
+        then "it has one element" {
+            assert_eq!(stack.len(), pushed);
+        }
+
+        and "the element is on top" {
+            assert_eq!(stack.last(), Some(&1));
+        }
+
+        but "it can be popped again" {
+            assert_eq!(stack.pop(), Some(1));
+        }
+    }
+
+    scenario "popping an empty stack" tags(edge) {
+        given "an empty stack" {
+            let mut stack: Vec<u32> = vec![];
+        }
+
+        when "an element is popped" {
+            let popped = stack.pop();
+        }
+
+        then "there is no element" {
+            assert_eq!(popped, None);
+            assert_eq!(pushed, 0);
+        }
+    }
+
+    // Fails on purpose, see `names_the_failing_step`.
+    #[should_panic(expected = "assertion failed")]
+    scenario "popping more than was pushed" {
+        given "a stack with one element" {
+            let mut stack = vec![1];
+            pushed += 1;
+        }
+
+        when "two elements are popped" {
+            stack.pop();
+            let popped = stack.pop();
+        }
+
+        then "the second one is there" {
+            assert_eq!(stack.len() + 1, pushed);
+            assert_eq!(popped, Some(1));
+        }
+    }
+}
+
+#[test]
+fn names_the_failing_step() {
+    // The test harness only shows the output of failing tests, so the scenario runs on its own.
+    let output = Command::new(env::current_exe().unwrap())
+        .args(&["stack::popping_more_than_was_pushed", "--exact", "--nocapture"])
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("step failed: Then the second one is there (tests/scenario.rs:72)\n"), "{}", stderr);
+    assert!(!stderr.contains("step failed: Given"), "{}", stderr);
+}
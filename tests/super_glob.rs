@@ -0,0 +1,30 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+#![feature(plugin)]
+#![plugin(stainless)]
+
+#[allow(dead_code)]
+struct HashMap;
+
+describe! no_super_glob {
+    #![no_super_glob]
+
+    // Would be ambiguous with the outer `HashMap` if `use super::*` was emitted.
+    use std::collections::*;
+
+    it "should not import the items of the enclosing module" {
+        let map: HashMap<i32, i32> = HashMap::new();
+        assert!(map.is_empty());
+    }
+
+    describe! nested {
+        it "should still see the imports of its parent" {
+            let set: HashSet<i32> = HashSet::new();
+            assert!(set.is_empty());
+        }
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright 2017 The Stainless Developers. See the LICENSE file at the top-level directory of
+// this distrubution.
+//
+// Licensed under the MIT license. This file may not be copied, modified, or distributed except
+// according to those terms.
+
+#![feature(plugin)]
+#![plugin(stainless)]
+
+describe! tagged {
+    #![tags(db)]
+
+    it "should accept tags" tags(slow, network) {
+        assert_eq!(1, 1);
+    }
+
+    failing "should accept tags on failing tests" tags(slow) {
+        panic!("failing");
+    }
+
+    it "should accept an empty tag list" tags() {
+        assert_eq!(2, 2);
+    }
+
+    describe! nested {
+        #![tags(slow)]
+
+        it "should inherit tags" {
+            assert_eq!(3, 3);
+        }
+    }
+}
@@ -10,14 +10,31 @@
 #[derive(Copy, Clone)]
 pub struct X(i32);
 
+#[derive(Copy, Clone)]
+struct Y(i32);
+
 #[cfg(test)]
 mod test {
-    // This use must be pub so that the addition sub-module can view it.
     pub use super::X;
+    use super::Y;
+
+    fn private_helper() -> i32 { 5 }
 
     describe! stainless {
+        #![allow(missing_assertion)]
+
         it "should be able to see outer pub uses" {
             let _ = X(5);
         }
+
+        it "should be able to see outer private uses and items" {
+            let _ = Y(private_helper());
+        }
+
+        describe! nested {
+            it "should be able to see private items through the parent glob" {
+                let _ = Y(private_helper());
+            }
+        }
     }
 }